@@ -0,0 +1,13 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // The gRPC service is generated from its .proto without needing protoc.
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/engine.proto");
+        let fds = protox::compile(["proto/engine.proto"], ["proto"]).expect("proto/engine.proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(fds)
+            .expect("generated gRPC code");
+    }
+}
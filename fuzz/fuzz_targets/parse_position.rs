@@ -0,0 +1,48 @@
+// Feeds arbitrary move strings to the position parser, which must turn them
+// down or replay them into a sound board, never panic. Run with
+// `cargo fuzz run parse_position` from the repository root.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The engine is a binary, so the modules the parser needs are built in here.
+#[allow(dead_code)]
+#[path = "../../src/game.rs"]
+mod game;
+#[allow(dead_code)]
+#[path = "../../src/i18n.rs"]
+mod i18n;
+#[allow(dead_code)]
+#[path = "../../src/json.rs"]
+mod json;
+#[allow(dead_code)]
+#[path = "../../src/rules.rs"]
+mod rules;
+
+use game::{BOT, ConnectFour, EMPTY, MAX_COLS, PLAYER, parse_column};
+
+fuzz_target!(|data: &[u8]| {
+    let [rows, cols, first, moves @ ..] = data else {
+        return;
+    };
+    let Ok(moves) = std::str::from_utf8(moves) else {
+        return;
+    };
+    let (rows, cols) = (4 + *rows as usize % 13, 4 + *cols as usize % (MAX_COLS - 3));
+    let first = if first % 2 == 0 { PLAYER } else { BOT };
+    let Ok(game) = ConnectFour::from_moves(rows, cols, moves, first) else {
+        return;
+    };
+    let pieces = game
+        .board
+        .iter()
+        .flatten()
+        .filter(|&&cell| cell != EMPTY)
+        .count();
+    assert_eq!(pieces, moves.chars().filter_map(parse_column).count());
+    for col in 0..cols {
+        for row in 1..rows {
+            assert!(game.board[row - 1][col] == EMPTY || game.board[row][col] != EMPTY);
+        }
+    }
+});
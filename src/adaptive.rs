@@ -0,0 +1,57 @@
+use std::error::Error;
+use tracing::info;
+
+use crate::history::{Database, Filter, GameRecord, Winner};
+
+// Difficulty levels from easiest to hardest as (search depth, blunder rate).
+const LEVELS: [(i32, f64); 11] = [
+    (1, 0.4),
+    (1, 0.2),
+    (2, 0.2),
+    (2, 0.1),
+    (3, 0.1),
+    (4, 0.05),
+    (4, 0.0),
+    (5, 0.0),
+    (6, 0.0),
+    (7, 0.0),
+    (8, 0.0),
+];
+const START_LEVEL: usize = 4;
+
+// The level closest to the settings a recorded game was played with.
+fn level_of(game: &GameRecord) -> usize {
+    (0..LEVELS.len())
+        .min_by(|&a, &b| {
+            let distance = |level: usize| {
+                let (depth, blunder_rate) = LEVELS[level];
+                (depth - game.depth).abs() as f64 + 10.0 * (blunder_rate - game.blunder_rate).abs()
+            };
+            distance(a).total_cmp(&distance(b))
+        })
+        .unwrap_or(START_LEVEL)
+}
+
+// A staircase on the most recent game without a handicap: one level up after
+// a win, one down after a loss. Such a staircase settles where the human wins
+// about half of the games.
+pub fn difficulty(db: &Database) -> Result<(i32, f64), Box<dyn Error>> {
+    let recent = db.list(&Filter {
+        limit: Some(20),
+        ..Filter::default()
+    })?;
+    let level = match recent.iter().find(|game| game.handicap.is_none()) {
+        Some(game) => {
+            let level = level_of(game);
+            match game.winner {
+                Winner::Human => (level + 1).min(LEVELS.len() - 1),
+                Winner::Bot => level.saturating_sub(1),
+                Winner::Draw => level,
+            }
+        }
+        None => START_LEVEL,
+    };
+    let (depth, blunder_rate) = LEVELS[level];
+    info!(level, depth, blunder_rate, "adaptive difficulty");
+    Ok((depth, blunder_rate))
+}
@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::error::Error;
+
+use crate::bot::{Analysis, BotPlayer};
+use crate::game::{BOT, ConnectFour, PLAYER, column_char, symbol};
+use crate::i18n::{Msg, tr};
+use crate::terminal::StopOnInterrupt;
+use crate::theme::Notation;
+
+// Columns in reports are written as in the transcript of the position.
+#[derive(Serialize)]
+struct EvalReport<'a> {
+    position: &'a str,
+    to_move: &'static str,
+    depth: i32,
+    score: i32,
+    best_move: Option<char>,
+    pv: Vec<char>,
+    nodes: u64,
+    // Fraction of the transposition table in use after the search.
+    occupancy: f64,
+}
+
+// The score is from the point of view of `to_move`.
+pub fn evaluate(bot: &mut BotPlayer, game: &ConnectFour, to_move: i8) -> Analysis {
+    bot.analyze_as(game, to_move)
+}
+
+// The score for `piece` once it has played `col`, judged by the search of the
+// opponent's reply.
+pub fn score_after(bot: &mut BotPlayer, game: &ConnectFour, col: usize, piece: i8) -> i32 {
+    if game.winning_move(col, piece) {
+        return bot.reward();
+    }
+    let mut after = game.clone();
+    after.drop_piece(col, piece);
+    -evaluate(bot, &after, -piece).score
+}
+
+// Positions are move strings as accepted by `ConnectFour::from_moves`, with o moving first.
+pub fn parse_position(
+    rows: usize,
+    cols: usize,
+    position: &str,
+) -> Result<(ConnectFour, i8), String> {
+    let game = ConnectFour::from_moves(rows, cols, position, PLAYER)?;
+    if game.check_win(BOT) || game.check_win(PLAYER) {
+        return Err(tr(Msg::AlreadyWon, &[&position]));
+    }
+    let to_move = if position.len().is_multiple_of(2) {
+        PLAYER
+    } else {
+        BOT
+    };
+    Ok((game, to_move))
+}
+
+pub fn run_eval(
+    bot: &mut BotPlayer,
+    rows: usize,
+    cols: usize,
+    position: &str,
+    json: bool,
+    notation: Notation,
+) -> Result<(), Box<dyn Error>> {
+    let (game, to_move) = parse_position(rows, cols, position)?;
+    let analysis = {
+        let _interruptible = StopOnInterrupt::new(bot.stop_flag());
+        evaluate(bot, &game, to_move)
+    };
+    let report = EvalReport {
+        position,
+        to_move: symbol(to_move),
+        depth: analysis.depth,
+        score: analysis.score,
+        best_move: analysis.best_move.map(column_char),
+        pv: analysis.pv.iter().map(|&col| column_char(col)).collect(),
+        nodes: analysis.nodes,
+        occupancy: bot.occupancy(),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+    print!("{}", game);
+    println!(
+        "{}",
+        tr(Msg::EvalPosition, &[&report.position, &report.to_move])
+    );
+    println!(
+        "{}",
+        tr(
+            Msg::Evaluation,
+            &[
+                &format!("{:+}", report.score),
+                &report.depth,
+                &report.nodes,
+                &format!("{:.1}", report.occupancy * 100.0)
+            ]
+        )
+    );
+    match analysis.best_move {
+        Some(col) => println!("{}", tr(Msg::BestMove, &[&notation.label(col)])),
+        None => println!("{}", tr(Msg::BestMoveNone, &[])),
+    }
+    let pv: Vec<String> = analysis
+        .pv
+        .iter()
+        .map(|&col| notation.label(col).to_string())
+        .collect();
+    println!("{}", tr(Msg::Pv, &[&pv.join(" ")]));
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+use tracing::info;
+
+use crate::analysis::{evaluate, parse_position};
+use crate::bot::BotPlayer;
+use crate::i18n::{Msg, tr};
+use crate::solver::{Position, Solver, split_hash};
+use crate::tablebase::Tablebase;
+
+pub struct SolveOptions {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub jobs: usize,
+    pub search: bool,
+    pub rows: usize,
+    pub cols: usize,
+    // Total transposition table budget, split between the workers.
+    pub hash_mb: usize,
+    pub tablebase: Option<Arc<Tablebase>>,
+}
+
+// One position per line, optionally followed by its expected score as found in
+// reference test sets, e.g. `4453 -2`. `#` starts a comment.
+struct Entry {
+    position: String,
+    expected: Option<i32>,
+}
+
+struct Row {
+    score: i32,
+    nodes: u64,
+    millis: u128,
+    // Of the solver's table after the search; searching bots have none.
+    occupancy: Option<f64>,
+}
+
+fn parse(text: &str, rows: usize, cols: usize) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let position = fields.next().unwrap_or_default().to_string();
+        let expected = fields
+            .next()
+            .map(|score| {
+                score
+                    .parse()
+                    .map_err(|_| tr(Msg::InvalidScore, &[&(i + 1), &score]))
+            })
+            .transpose()?;
+        parse_position(rows, cols, &position)
+            .map_err(|err| tr(Msg::LineError, &[&(i + 1), &err]))?;
+        entries.push(Entry { position, expected });
+    }
+    Ok(entries)
+}
+
+enum Evaluator {
+    Solver(Solver),
+    Search(Box<BotPlayer>),
+}
+
+impl Evaluator {
+    fn run(&mut self, entry: &Entry, rows: usize, cols: usize) -> Result<Row, String> {
+        let (game, to_move) = parse_position(rows, cols, &entry.position)?;
+        let start = Instant::now();
+        let (score, nodes, occupancy) = match self {
+            Evaluator::Solver(solver) => {
+                let pos = Position::from_game(&game, to_move)
+                    .ok_or_else(|| tr(Msg::BoardTooLargeSearch, &[]))?;
+                let before = solver.nodes();
+                let score = solver.solve(&pos);
+                (score, solver.nodes() - before, Some(solver.occupancy()))
+            }
+            Evaluator::Search(bot) => {
+                let analysis = evaluate(bot, &game, to_move);
+                (analysis.score, analysis.nodes, None)
+            }
+        };
+        Ok(Row {
+            score,
+            nodes,
+            millis: start.elapsed().as_millis(),
+            occupancy,
+        })
+    }
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+// Positions are handed out to the workers one at a time, each worker keeping
+// its own solver or bot so no tables are shared.
+pub fn run(
+    options: &SolveOptions,
+    make_bot: impl Fn() -> BotPlayer + Sync,
+) -> Result<(), Box<dyn Error>> {
+    let entries = parse(&read(&options.input)?, options.rows, options.cols)
+        .map_err(|err| format!("{}: {}", options.input.display(), err))?;
+    let results: Mutex<Vec<Option<Result<Row, String>>>> =
+        Mutex::new((0..entries.len()).map(|_| None).collect());
+    let next = AtomicUsize::new(0);
+    let start = Instant::now();
+
+    // Searching bots have no table, so only solvers are held to the budget.
+    let (jobs, hash_mb) = if options.search {
+        (options.jobs.max(1), options.hash_mb)
+    } else {
+        split_hash(options.hash_mb, options.jobs)
+    };
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                let mut evaluator = if options.search {
+                    Evaluator::Search(Box::new(make_bot()))
+                } else {
+                    Evaluator::Solver(
+                        Solver::new(hash_mb).with_tablebase(options.tablebase.clone()),
+                    )
+                };
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = entries.get(i) else {
+                        break;
+                    };
+                    let row = evaluator.run(entry, options.rows, options.cols);
+                    if let Ok(row) = &row {
+                        info!(
+                            position = %entry.position,
+                            score = row.score,
+                            nodes = row.nodes,
+                            occupancy = row.occupancy,
+                            "position solved"
+                        );
+                    }
+                    results.lock().unwrap()[i] = Some(row);
+                }
+            });
+        }
+    });
+
+    let mut csv = String::from("position,score,nodes,millis,occupancy,expected,match\n");
+    let mut mismatches = 0;
+    for (entry, row) in entries.iter().zip(results.into_inner().unwrap()) {
+        let row = row.expect("every position is evaluated")?;
+        let expected = entry.expected.map(|e| e.to_string()).unwrap_or_default();
+        // Search scores are on the bot's scale, so only their sign is compared:
+        // whether the search sees the same winner as the reference.
+        let agrees = |e: i32| {
+            if options.search {
+                e.signum() == row.score.signum()
+            } else {
+                e == row.score
+            }
+        };
+        let matched = match entry.expected {
+            Some(e) if agrees(e) => "yes",
+            Some(_) => {
+                mismatches += 1;
+                "no"
+            }
+            None => "",
+        };
+        let occupancy = row
+            .occupancy
+            .map(|occupancy| format!("{:.3}", occupancy))
+            .unwrap_or_default();
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            entry.position, row.score, row.nodes, row.millis, occupancy, expected, matched
+        )?;
+    }
+
+    match &options.output {
+        Some(path) => fs::write(path, csv).map_err(|err| format!("{}: {}", path.display(), err))?,
+        None => print!("{}", csv),
+    }
+    eprintln!(
+        "{}",
+        tr(
+            Msg::Evaluated,
+            &[
+                &entries.len(),
+                &format!("{:.1}", start.elapsed().as_secs_f64()),
+                &mismatches
+            ]
+        )
+    );
+    Ok(())
+}
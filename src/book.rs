@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use tracing::info;
+
+use crate::i18n::{Msg, tr};
+use crate::score_table::ScoreTable;
+use crate::solver::{Position, Solver, split_hash};
+
+const MAGIC: &[u8; 4] = b"C4BK";
+
+// The key a position and its mirror image share.
+fn canonical_key(pos: &Position) -> u64 {
+    pos.key().min(pos.mirrored_key())
+}
+
+// Exact scores, in the solver's convention, of every position reachable in at
+// most `plies` moves without the game ending, like the classic 8-ply
+// databases. A position and its mirror image share one entry. The file is
+// mapped, so opening even a large book costs nothing at startup.
+pub struct Book {
+    table: ScoreTable,
+}
+
+impl Book {
+    // Only the positions `plies` moves in are solved; each earlier level then
+    // takes the best of its already scored children, as for the tablebase.
+    // The solver's work is shared between `jobs` threads, each with a part of
+    // the `hash_mb` budget. Returns the number of positions written.
+    pub fn generate(
+        width: usize,
+        height: usize,
+        plies: usize,
+        jobs: usize,
+        hash_mb: usize,
+        output: &Path,
+    ) -> Result<usize, String> {
+        let empty = Position::new(width, height).ok_or_else(|| tr(Msg::BoardTooLarge, &[]))?;
+        if plies >= empty.cells() {
+            return Err(format!(
+                "a {}x{} board is full after {} moves",
+                width,
+                height,
+                empty.cells()
+            ));
+        }
+
+        // Winning moves end the game, so their positions are left out.
+        let mut levels: Vec<Vec<Position>> = vec![vec![empty]];
+        for _ in 0..plies {
+            let mut next = HashMap::new();
+            for pos in levels.last().unwrap() {
+                for col in (0..width).filter(|&col| pos.can_play(col)) {
+                    if !pos.is_winning_move(col) {
+                        let mut child = *pos;
+                        child.play(col);
+                        next.entry(canonical_key(&child)).or_insert(child);
+                    }
+                }
+            }
+            levels.push(next.into_values().collect());
+        }
+
+        let deepest = levels.last().unwrap();
+        info!(
+            plies,
+            positions = deepest.len(),
+            "solving the deepest level"
+        );
+        let scores = Mutex::new(HashMap::with_capacity(deepest.len()));
+        let next = AtomicUsize::new(0);
+        let (jobs, hash_mb) = split_hash(hash_mb, jobs);
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| {
+                    let mut solver = Solver::new(hash_mb);
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(pos) = deepest.get(i) else {
+                            break;
+                        };
+                        let score = solver.solve(pos) as i8;
+                        scores.lock().unwrap().insert(canonical_key(pos), score);
+                        if (i + 1).is_multiple_of(1000) {
+                            info!(solved = i + 1, of = deepest.len(), "book positions solved");
+                        }
+                    }
+                });
+            }
+        });
+        let mut scores = scores.into_inner().unwrap();
+
+        let cells = empty.cells();
+        for level in levels[..plies].iter().rev() {
+            for pos in level {
+                let score = if pos.can_win_next() {
+                    ((cells + 1 - pos.moves()) / 2) as i8
+                } else {
+                    (0..width)
+                        .filter(|&col| pos.can_play(col))
+                        .map(|col| {
+                            let mut child = *pos;
+                            child.play(col);
+                            -scores[&canonical_key(&child)]
+                        })
+                        .max()
+                        .unwrap_or(0)
+                };
+                scores.insert(canonical_key(pos), score);
+            }
+            info!(
+                plies = level[0].moves(),
+                positions = level.len(),
+                "book level solved"
+            );
+        }
+
+        let mut entries: Vec<(u64, i8)> = scores.into_iter().collect();
+        entries.sort_unstable_by_key(|&(key, _)| key);
+        ScoreTable::new(MAGIC, width, height, plies, &entries).save(output)?;
+        Ok(entries.len())
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        Ok(Self {
+            table: ScoreTable::open(path, MAGIC, "an opening book")?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn plies(&self) -> usize {
+        self.table.bound()
+    }
+
+    pub fn probe(&self, pos: &Position) -> Option<i32> {
+        if !self.table.fits(pos) || pos.moves() > self.plies() {
+            return None;
+        }
+        self.table.score(canonical_key(pos))
+    }
+
+    // The deepest level has no scored children, so only positions before it
+    // are played from the book.
+    pub fn best_move(&self, pos: &Position) -> Option<(usize, i32)> {
+        if pos.moves() >= self.plies() {
+            return None;
+        }
+        self.probe(pos)?;
+        self.table.best_move(pos, |child| self.probe(child))
+    }
+}
@@ -0,0 +1,979 @@
+use clap::ValueEnum;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
+#[cfg(feature = "grpc")]
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, info_span, warn};
+
+use crate::book::Book;
+use crate::explain;
+use crate::game::{BOT, ConnectFour, EMPTY, PLAYER};
+use crate::i18n::{Msg, tr};
+use crate::json;
+#[cfg(feature = "nn")]
+use crate::nn::Network;
+use crate::player::{GameView, Move, Player};
+use crate::solver::{DEFAULT_HASH_MB, Position};
+use crate::tablebase::Tablebase;
+use crate::theme::Notation;
+use crate::transposition::TranspositionTable;
+use crate::tree::SearchTree;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    Minimax,
+    Random,
+}
+
+impl Engine {
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Minimax => "minimax",
+            Engine::Random => "random",
+        }
+    }
+}
+
+// How positions at the search horizon are scored: statically, by the
+// average result of random playouts from them, or by a trained network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Eval {
+    Static,
+    Rollout,
+    #[cfg(feature = "nn")]
+    Nn,
+}
+
+impl Eval {
+    pub fn name(self) -> &'static str {
+        match self {
+            Eval::Static => "static",
+            Eval::Rollout => "rollout",
+            #[cfg(feature = "nn")]
+            Eval::Nn => "nn",
+        }
+    }
+}
+
+pub const DEFAULT_DEPTH: i32 = 4;
+// Beyond this a search from the opening runs for minutes.
+pub const MAX_DEPTH: i32 = 12;
+pub const DEFAULT_ROLLOUTS: u32 = 8;
+
+// Weights of the static evaluation, from BOT's point of view: a bonus per
+// piece in the center column, and per line of four cells holding two or three
+// pieces of one side and nothing of the other.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub center: i32,
+    pub two: i32,
+    pub three: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            center: 3,
+            two: 2,
+            three: 5,
+        }
+    }
+}
+
+// How the bot leans, on top of its weights: percentages applied to the lines
+// it holds itself and to the opponent's, and the largest random amount added
+// to or taken from every static leaf score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    pub attack: i32,
+    pub defense: i32,
+    pub noise: i32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            attack: 100,
+            defense: 100,
+            noise: 0,
+        }
+    }
+}
+
+// Ready-made characters for the bot, so that games against it feel different.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Personality {
+    // Builds its own lines and cares less about the human's.
+    Aggressive,
+    // Blocks first and keeps the center.
+    Defensive,
+    // Goes for threes that must be answered, varying among similar moves.
+    Trappy,
+    // Plays by feel and now and then not at all sensibly.
+    Drunk,
+}
+
+impl Personality {
+    pub fn name(self) -> &'static str {
+        match self {
+            Personality::Aggressive => "aggressive",
+            Personality::Defensive => "defensive",
+            Personality::Trappy => "trappy",
+            Personality::Drunk => "drunk",
+        }
+    }
+
+    pub fn weights(self) -> Weights {
+        match self {
+            Personality::Aggressive => Weights {
+                center: 2,
+                two: 3,
+                three: 8,
+            },
+            Personality::Defensive => Weights {
+                center: 4,
+                two: 2,
+                three: 5,
+            },
+            Personality::Trappy => Weights {
+                center: 2,
+                two: 1,
+                three: 9,
+            },
+            Personality::Drunk => Weights::default(),
+        }
+    }
+
+    pub fn style(self) -> Style {
+        match self {
+            Personality::Aggressive => Style {
+                attack: 150,
+                defense: 75,
+                noise: 0,
+            },
+            Personality::Defensive => Style {
+                attack: 75,
+                defense: 150,
+                noise: 0,
+            },
+            Personality::Trappy => Style {
+                attack: 125,
+                defense: 100,
+                noise: 2,
+            },
+            Personality::Drunk => Style {
+                attack: 100,
+                defense: 100,
+                noise: 10,
+            },
+        }
+    }
+
+    // The smallest share of moves played at random.
+    pub fn blunder_rate(self) -> f64 {
+        match self {
+            Personality::Drunk => 0.25,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Analysis {
+    #[serde(with = "json::optional_column")]
+    pub best_move: Option<usize>,
+    pub score: i32,
+    #[serde(with = "json::columns")]
+    pub pv: Vec<usize>,
+    pub depth: i32,
+    pub nodes: u64,
+}
+
+// Everything about how the bot searches, short of the tablebase it may use.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSettings {
+    pub depth: i32,
+    pub engine: Engine,
+    pub eval: Eval,
+    pub rollouts: u32,
+    pub weights: Weights,
+    pub style: Style,
+    pub blunder_rate: f64,
+    #[serde(with = "json::optional_secs")]
+    pub move_time: Option<Duration>,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_DEPTH,
+            engine: Engine::Minimax,
+            eval: Eval::Static,
+            rollouts: DEFAULT_ROLLOUTS,
+            weights: Weights::default(),
+            style: Style::default(),
+            blunder_rate: 0.0,
+            move_time: None,
+        }
+    }
+}
+
+// How a stored score relates to the true one: searches that fail low only
+// give an upper bound, and those that fail high a lower one.
+#[derive(Clone, Copy, Default)]
+enum Bound {
+    #[default]
+    Exact,
+    Lower,
+    Upper,
+}
+
+// What a search to `depth` plies made of a position, with the move it liked best.
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    score: i32,
+    depth: i8,
+    bound: Bound,
+    best: u8,
+}
+
+pub struct BotPlayer {
+    max_depth: i32,
+    reward: i32,
+    engine: Engine,
+    eval: Eval,
+    rollouts: u32,
+    weights: Weights,
+    style: Style,
+    blunder_rate: f64,
+    move_time: Option<Duration>,
+    tablebase: Option<Arc<Tablebase>>,
+    book: Option<Arc<Book>>,
+    hash_mb: usize,
+    // Allocated by the first search, so that bots which never search cost nothing.
+    table: Option<TranspositionTable<Entry>>,
+    #[cfg(feature = "nn")]
+    network: Option<Arc<Network>>,
+    // Where the top of each finished search is written.
+    tree: Option<SearchTree>,
+    // The notation explanations name columns in, if the bot explains its moves.
+    explain: Option<Notation>,
+    // Why the last move chosen as a player was played, with `explain`.
+    comment: Option<String>,
+    // Set from anywhere to stop the running search early.
+    stop: Arc<AtomicBool>,
+    // When the running search must stop, with a move time.
+    deadline: Option<Instant>,
+    // Whether the running search was stopped; it then unwinds without
+    // looking at anything more.
+    stopped: bool,
+    nodes: u64,
+}
+
+// Nodes searched between checks of the stop flag and the clock.
+const STOP_CHECK_NODES: u64 = 1024;
+
+impl BotPlayer {
+    pub fn new(max_depth: i32) -> Self {
+        Self {
+            max_depth,
+            reward: 100,
+            engine: Engine::Minimax,
+            eval: Eval::Static,
+            rollouts: DEFAULT_ROLLOUTS,
+            weights: Weights::default(),
+            style: Style::default(),
+            blunder_rate: 0.0,
+            move_time: None,
+            tablebase: None,
+            book: None,
+            hash_mb: DEFAULT_HASH_MB,
+            table: None,
+            #[cfg(feature = "nn")]
+            network: None,
+            tree: None,
+            explain: None,
+            comment: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            stopped: false,
+            nodes: 0,
+        }
+    }
+
+    pub fn from_settings(settings: &SearchSettings) -> Self {
+        Self::new(settings.depth)
+            .with_engine(settings.engine)
+            .with_eval(settings.eval, settings.rollouts)
+            .with_weights(settings.weights)
+            .with_style(settings.style)
+            .with_blunder_rate(settings.blunder_rate)
+            .with_move_time(settings.move_time)
+    }
+
+    pub fn with_engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    // `rollouts` is the number of playouts per leaf with `Eval::Rollout`.
+    pub fn with_eval(mut self, eval: Eval, rollouts: u32) -> Self {
+        self.eval = eval;
+        self.rollouts = rollouts.max(1);
+        self
+    }
+
+    pub fn with_weights(mut self, weights: Weights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    // The share of moves, between 0 and 1, that are picked at random instead of
+    // searched, to make the bot beatable.
+    pub fn with_blunder_rate(mut self, blunder_rate: f64) -> Self {
+        self.blunder_rate = blunder_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    // With a move time the search deepens one ply at a time and stops starting
+    // new iterations once the budget is spent.
+    pub fn with_move_time(mut self, move_time: Option<Duration>) -> Self {
+        self.move_time = move_time;
+        self
+    }
+
+    // Late positions found in the tablebase are played perfectly without a search.
+    pub fn with_tablebase(mut self, tablebase: Option<Arc<Tablebase>>) -> Self {
+        self.tablebase = tablebase;
+        self
+    }
+
+    // Openings found in the book are played perfectly without a search.
+    pub fn with_book(mut self, book: Option<Arc<Book>>) -> Self {
+        self.book = book;
+        self
+    }
+
+    // `hash_mb` is the memory budget of the transposition table in megabytes.
+    pub fn with_hash(mut self, hash_mb: usize) -> Self {
+        self.hash_mb = hash_mb;
+        self.table = None;
+        self
+    }
+
+    // Fraction of transposition table slots holding an entry, 0 before the
+    // first search.
+    pub fn occupancy(&self) -> f64 {
+        self.table.as_ref().map_or(0.0, |table| table.occupancy())
+    }
+
+    // The network scoring leaves with `Eval::Nn` and ordering moves deeper in
+    // the tree. Without one those leaves are scored statically.
+    #[cfg(feature = "nn")]
+    pub fn with_network(mut self, network: Option<Arc<Network>>) -> Self {
+        self.network = network;
+        self
+    }
+
+    // Writes the top of the search tree after every search.
+    pub fn with_tree(mut self, tree: Option<SearchTree>) -> Self {
+        self.tree = tree;
+        self
+    }
+
+    // Explain each move chosen as a player in its comment.
+    pub fn with_explain(mut self, explain: Option<Notation>) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    // A handle that stops the bot's search when set, which then answers with
+    // the best move of the deepest search it finished. Searches never clear
+    // it: whoever sets it clears it again once the stopped move is taken, or
+    // every later search stops at once.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    fn should_stop(&mut self) -> bool {
+        self.stopped = self.stopped
+            || self.stop.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        self.stopped
+    }
+
+    // The score of a won game.
+    pub fn reward(&self) -> i32 {
+        self.reward
+    }
+
+    fn tree_open(&mut self, col: usize, max: bool, alpha: i32, beta: i32) {
+        if let Some(tree) = &mut self.tree {
+            tree.open(col, max, alpha, beta);
+        }
+    }
+
+    fn tree_close(&mut self, score: i32, win: bool) {
+        if let Some(tree) = &mut self.tree {
+            tree.close(score, win);
+        }
+    }
+
+    fn tree_cutoff(&mut self, pruned: usize) {
+        if let Some(tree) = &mut self.tree {
+            tree.cutoff(pruned);
+        }
+    }
+
+    fn minimax(
+        &mut self,
+        game: &mut ConnectFour,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+        is_maximizing: bool,
+        pv: &mut Vec<usize>,
+    ) -> i32 {
+        self.nodes += 1;
+        pv.clear();
+        let valid_moves = game.get_valid_moves();
+        if valid_moves.is_empty() {
+            return 0;
+        }
+        if depth == 0 {
+            return self.leaf_score(game, if is_maximizing { BOT } else { PLAYER });
+        }
+        // The score no longer matters: the search this node is part of is dropped.
+        if self.stopped || (self.nodes.is_multiple_of(STOP_CHECK_NODES) && self.should_stop()) {
+            return 0;
+        }
+        let side = if is_maximizing { BOT } else { PLAYER };
+        let key = game.position_key(side);
+        // Only entries of the same depth are used, so that the table never
+        // changes a score, only how fast it is found.
+        if let Some(entry) = self.probe(key, depth) {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if usable {
+                if let Bound::Exact = entry.bound {
+                    self.table_pv(game, side, depth, entry.best as usize, pv);
+                }
+                return entry.score;
+            }
+        }
+        #[cfg(feature = "nn")]
+        let valid_moves = self.order_moves(game, depth, is_maximizing, valid_moves);
+        let mut line = Vec::new();
+
+        let (window_alpha, window_beta) = (alpha, beta);
+        let mut alpha = alpha;
+        let mut beta = beta;
+
+        let score = if is_maximizing {
+            let mut max_score = i32::MIN;
+
+            for (i, &col) in valid_moves.iter().enumerate() {
+                if let Some(row) = game.landing_row(col) {
+                    game.board[row][col] = BOT;
+
+                    self.tree_open(col, false, alpha, beta);
+                    let won = game.wins_at(row, col, BOT);
+                    let score = if won {
+                        line.clear();
+                        self.reward
+                    } else {
+                        self.minimax(game, depth - 1, alpha, beta, false, &mut line)
+                    };
+                    self.tree_close(score, won);
+                    game.board[row][col] = EMPTY;
+                    if score > max_score {
+                        max_score = score;
+                        pv.clear();
+                        pv.push(col);
+                        pv.append(&mut line);
+                    }
+                    alpha = max(alpha, score);
+                    if beta <= alpha {
+                        self.tree_cutoff(valid_moves.len() - i - 1);
+                        break;
+                    }
+                }
+            }
+            max_score
+        } else {
+            let mut min_score = i32::MAX;
+
+            for (i, &col) in valid_moves.iter().enumerate() {
+                if let Some(row) = game.landing_row(col) {
+                    game.board[row][col] = PLAYER;
+
+                    self.tree_open(col, true, alpha, beta);
+                    let won = game.wins_at(row, col, PLAYER);
+                    let score = if won {
+                        line.clear();
+                        -self.reward
+                    } else {
+                        self.minimax(game, depth - 1, alpha, beta, true, &mut line)
+                    };
+                    self.tree_close(score, won);
+                    game.board[row][col] = EMPTY;
+                    if score < min_score {
+                        min_score = score;
+                        pv.clear();
+                        pv.push(col);
+                        pv.append(&mut line);
+                    }
+                    beta = min(beta, score);
+                    if beta <= alpha {
+                        self.tree_cutoff(valid_moves.len() - i - 1);
+                        break;
+                    }
+                }
+            }
+            min_score
+        };
+        if !self.stopped
+            && let Some(&best) = pv.first()
+        {
+            let bound = if score <= window_alpha {
+                Bound::Upper
+            } else if score >= window_beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.store(
+                key,
+                Entry {
+                    score,
+                    depth: depth as i8,
+                    bound,
+                    best: best as u8,
+                },
+            );
+        }
+        score
+    }
+
+    fn probe(&self, key: u64, depth: i32) -> Option<Entry> {
+        let entry = self.table.as_ref()?.get(key)?;
+        (entry.depth as i32 == depth).then_some(entry)
+    }
+
+    fn store(&mut self, key: u64, entry: Entry) {
+        if let Some(table) = &mut self.table {
+            table.put(key, entry);
+        }
+    }
+
+    // The line behind an exact table hit: `best`, then the best moves stored
+    // for the positions it leads to, as far as they were searched.
+    fn table_pv(&self, game: &ConnectFour, side: i8, depth: i32, best: usize, pv: &mut Vec<usize>) {
+        let mut game = game.clone();
+        let (mut side, mut depth, mut col) = (side, depth, best);
+        while let Some(row) = game.landing_row(col) {
+            pv.push(col);
+            game.board[row][col] = side;
+            if game.wins_at(row, col, side) || depth <= 1 {
+                break;
+            }
+            side = -side;
+            depth -= 1;
+            match self.probe(game.position_key(side), depth) {
+                Some(Entry {
+                    bound: Bound::Exact,
+                    best,
+                    ..
+                }) => col = best as usize,
+                _ => break,
+            }
+        }
+    }
+
+    // Leaf scores stay within half the reward so that a proven win or loss
+    // always outweighs them.
+    fn leaf_score(&self, game: &ConnectFour, to_move: i8) -> i32 {
+        match self.eval {
+            Eval::Static => self.static_score(game),
+            Eval::Rollout => {
+                let mut rng = rand::rng();
+                let total: i32 = (0..self.rollouts)
+                    .map(|_| playout(game, to_move, &mut rng))
+                    .sum();
+                total * (self.reward / 2) / self.rollouts as i32
+            }
+            #[cfg(feature = "nn")]
+            Eval::Nn => match &self.network {
+                Some(network) => {
+                    let (value, _) = network.evaluate(game, to_move);
+                    // The network's outlook is for the side to move.
+                    let score = (value * (self.reward / 2) as f32).round() as i32;
+                    if to_move == BOT { score } else { -score }
+                }
+                None => self.static_score(game),
+            },
+        }
+    }
+
+    fn static_score(&self, game: &ConnectFour) -> i32 {
+        let limit = self.reward / 2;
+        let noise = match self.style.noise {
+            0 => 0,
+            noise => rand::rng().random_range(-noise..=noise),
+        };
+        (self.heuristic(game) + noise).clamp(-limit, limit)
+    }
+
+    // With `Eval::Nn`, tries the moves the policy head likes best first, which
+    // makes for more cutoffs. Nodes just above the leaves keep the plain order,
+    // as there asking the network costs about as much as it saves.
+    #[cfg(feature = "nn")]
+    fn order_moves(
+        &self,
+        game: &ConnectFour,
+        depth: i32,
+        is_maximizing: bool,
+        mut moves: Vec<usize>,
+    ) -> Vec<usize> {
+        if let (Eval::Nn, Some(network)) = (self.eval, &self.network)
+            && depth > 1
+        {
+            let (_, policy) = network.evaluate(game, if is_maximizing { BOT } else { PLAYER });
+            moves.sort_by(|&a, &b| policy[b].total_cmp(&policy[a]));
+        }
+        moves
+    }
+
+    fn heuristic(&self, game: &ConnectFour) -> i32 {
+        let center = game.cols / 2;
+        let mut score: i32 = (0..game.rows)
+            .map(|row| -game.board[row][center] as i32 * self.weights.center)
+            .sum();
+        // Line directions as (row, col) steps: right, down, and both diagonals.
+        for (dr, dc) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+            for row in 0..game.rows as isize {
+                for col in 0..game.cols as isize {
+                    let end = (row + 3 * dr, col + 3 * dc);
+                    if end.0 >= game.rows as isize || end.1 < 0 || end.1 >= game.cols as isize {
+                        continue;
+                    }
+                    let (mut bot, mut player) = (0, 0);
+                    for i in 0..4 {
+                        match game.board[(row + i * dr) as usize][(col + i * dc) as usize] {
+                            BOT => bot += 1,
+                            PLAYER => player += 1,
+                            _ => {}
+                        }
+                    }
+                    let value = |count| match count {
+                        2 => self.weights.two,
+                        3 => self.weights.three,
+                        _ => 0,
+                    };
+                    if player == 0 {
+                        score += value(bot) * self.style.attack / 100;
+                    } else if bot == 0 {
+                        score -= value(player) * self.style.defense / 100;
+                    }
+                }
+            }
+        }
+        score
+    }
+
+    // Pie rule: `game` holds the opponent's first piece with BOT to move. Taking
+    // that piece over leaves the opponent in exactly the position BOT faces now,
+    // so swapping pays off whenever that position is bad for the side to move.
+    pub fn wants_swap(&mut self, game: &ConnectFour) -> bool {
+        let score = self.analyze(game).score;
+        debug!(score, "swap considered");
+        score < 0
+    }
+
+    pub fn get_best_move(&mut self, game: &ConnectFour) -> Option<usize> {
+        self.analyze(game).best_move
+    }
+
+    // Searches for `piece`, scoring from its point of view. The search itself
+    // always plays BOT, so PLAYER's positions are searched with the pieces
+    // exchanged; the columns are the same either way.
+    pub fn analyze_as(&mut self, game: &ConnectFour, piece: i8) -> Analysis {
+        if piece == BOT {
+            self.analyze(game)
+        } else {
+            self.analyze(&game.swapped())
+        }
+    }
+
+    // Like `analyze`, but searching to `depth` instead of the bot's own depth.
+    #[cfg(feature = "grpc")]
+    pub fn analyze_at(&mut self, game: &ConnectFour, depth: i32) -> Analysis {
+        let max_depth = mem::replace(&mut self.max_depth, depth);
+        let analysis = self.analyze(game);
+        self.max_depth = max_depth;
+        analysis
+    }
+
+    // Searches as BOT and reports the score from BOT's point of view.
+    pub fn analyze(&mut self, game: &ConnectFour) -> Analysis {
+        self.analyze_with(game, &mut |_| {})
+    }
+
+    // Like `analyze`, handing `progress` the result of every depth searched
+    // with a move time, or just the final one otherwise.
+    pub fn analyze_with(
+        &mut self,
+        game: &ConnectFour,
+        progress: &mut dyn FnMut(&Analysis),
+    ) -> Analysis {
+        let _span =
+            info_span!("search", engine = ?self.engine, max_depth = self.max_depth).entered();
+        let start = Instant::now();
+        self.nodes = 0;
+        self.deadline = self.move_time.map(|move_time| start + move_time);
+        self.stopped = false;
+        if let Some(tree) = &mut self.tree {
+            tree.clear();
+        }
+        if self.engine == Engine::Minimax && self.table.is_none() {
+            let table = TranspositionTable::new(self.hash_mb);
+            debug!(
+                entries = table.entries(),
+                mb = table.megabytes(),
+                "transposition table allocated"
+            );
+            self.table = Some(table);
+        }
+        info!("search started");
+
+        let blunder = self.blunder_rate > 0.0 && rand::rng().random_bool(self.blunder_rate);
+        if blunder {
+            debug!("deliberate blunder");
+        }
+        let analysis = match self.engine {
+            _ if blunder => random_move(game),
+            Engine::Random => random_move(game),
+            Engine::Minimax => match self.probe_book(game).or_else(|| self.probe_tablebase(game)) {
+                Some(analysis) => analysis,
+                None => {
+                    // Looking one move ahead cannot be stopped, so there is always
+                    // a move to fall back on.
+                    let mut analysis = self.search(game, 0, start);
+                    let depths = match self.move_time {
+                        Some(_) => 1..=self.max_depth,
+                        None => self.max_depth.max(1)..=self.max_depth,
+                    };
+                    for depth in depths {
+                        if self.should_stop() {
+                            break;
+                        }
+                        if self.move_time.is_some() {
+                            progress(&analysis);
+                        }
+                        let deeper = self.search(game, depth, start);
+                        if self.stopped {
+                            info!(depth, "search stopped");
+                            break;
+                        }
+                        analysis = deeper;
+                    }
+                    analysis
+                }
+            },
+        };
+        progress(&analysis);
+        if let Some(tree) = &self.tree
+            && let Err(err) = tree.write()
+        {
+            warn!(%err, "could not write the search tree");
+        }
+
+        info!(
+            best_move = ?analysis.best_move.map(|col| col + 1),
+            score = analysis.score,
+            nodes = self.nodes,
+            occupancy = self.occupancy(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "search finished"
+        );
+        analysis
+    }
+
+    fn probe_book(&self, game: &ConnectFour) -> Option<Analysis> {
+        let book = self.book.as_ref()?;
+        let pos = Position::from_game(game, BOT)?;
+        let (col, score) = book.best_move(&pos)?;
+        debug!(col = col + 1, score, "book hit");
+        Some(self.exact(col, score))
+    }
+
+    fn probe_tablebase(&self, game: &ConnectFour) -> Option<Analysis> {
+        let tablebase = self.tablebase.as_ref()?;
+        let pos = Position::from_game(game, BOT)?;
+        let (col, score) = tablebase.best_move(&pos)?;
+        debug!(col = col + 1, score, "tablebase hit");
+        Some(self.exact(col, score))
+    }
+
+    // Book and tablebase scores count moves to the end; the bot only keeps
+    // their sign.
+    fn exact(&self, col: usize, score: i32) -> Analysis {
+        Analysis {
+            best_move: Some(col),
+            score: score.signum() * self.reward,
+            pv: vec![col],
+            depth: 0,
+            nodes: 0,
+        }
+    }
+
+    fn search(&mut self, game: &ConnectFour, depth: i32, start: Instant) -> Analysis {
+        let mut game_clone = game.clone();
+        let mut best_score = i32::MIN;
+        let mut best_moves: Vec<(usize, Vec<usize>)> = Vec::new();
+        if let Some(tree) = &mut self.tree {
+            tree.begin();
+        }
+
+        for col in game.get_valid_moves() {
+            if let Some(row) = game_clone.landing_row(col) {
+                game_clone.board[row][col] = BOT;
+
+                let mut line = Vec::new();
+                self.tree_open(col, false, i32::MIN, i32::MAX);
+                let won = game_clone.wins_at(row, col, BOT);
+                let score = if won {
+                    self.reward
+                } else {
+                    self.minimax(&mut game_clone, depth, i32::MIN, i32::MAX, false, &mut line)
+                };
+                self.tree_close(score, won);
+                game_clone.board[row][col] = EMPTY;
+
+                if score > best_score {
+                    best_score = score;
+                    best_moves.clear();
+                    best_moves.push((col, line));
+                } else if score == best_score {
+                    best_moves.push((col, line));
+                }
+            }
+        }
+
+        if self.stopped {
+            debug!(depth, nodes = self.nodes, "depth abandoned");
+        } else {
+            if let Some(tree) = &mut self.tree {
+                tree.finish(best_score);
+            }
+            debug!(
+                depth,
+                score = best_score,
+                candidates = ?best_moves.iter().map(|(col, _)| col + 1).collect::<Vec<_>>(),
+                nodes = self.nodes,
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "depth completed"
+            );
+        }
+        let chosen = best_moves.choose(&mut rand::rng());
+        Analysis {
+            best_move: chosen.map(|(col, _)| *col),
+            score: if chosen.is_some() { best_score } else { 0 },
+            pv: chosen
+                .map(|(col, line)| [*col].into_iter().chain(line.iter().copied()).collect())
+                .unwrap_or_default(),
+            depth,
+            nodes: self.nodes,
+        }
+    }
+}
+
+impl Player for BotPlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        // The search plays BOT, so PLAYER's positions are mirrored.
+        let game = if view.piece == BOT {
+            view.game.clone()
+        } else {
+            view.game.swapped()
+        };
+        if view.can_swap && self.wants_swap(&game) {
+            return Move::Swap;
+        }
+        let analysis = self.analyze_with(&game, &mut |analysis| (view.progress)(analysis));
+        let Some(col) = analysis.best_move else {
+            return Move::Resign;
+        };
+        if let Some(notation) = self.explain {
+            let reason = explain::explain(&game, col, BOT, &analysis, self.reward, notation);
+            self.comment = Some(tr(Msg::BotExplains, &[&reason]));
+        }
+        Move::Drop(col)
+    }
+
+    fn comment(&mut self) -> Option<String> {
+        self.comment.take()
+    }
+}
+
+fn random_move(game: &ConnectFour) -> Analysis {
+    let best_move = game.get_valid_moves().choose(&mut rand::rng()).cloned();
+    Analysis {
+        best_move,
+        score: 0,
+        pv: best_move.into_iter().collect(),
+        depth: 0,
+        nodes: 0,
+    }
+}
+
+// Plays random moves to the end of the game: 1 if BOT wins, -1 if PLAYER does.
+fn playout(game: &ConnectFour, to_move: i8, rng: &mut impl Rng) -> i32 {
+    let mut game = game.clone();
+    let mut piece = to_move;
+    while let Some(&col) = game.get_valid_moves().choose(rng) {
+        if game.winning_move(col, piece) {
+            return if piece == BOT { 1 } else { -1 };
+        }
+        game.drop_piece(col, piece);
+        piece = -piece;
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_table_never_changes_a_score() {
+        let game = ConnectFour::from_moves(6, 7, "4453", PLAYER).unwrap();
+        let mut fresh = BotPlayer::new(5);
+        let score = fresh.analyze(&game).score;
+        assert!(fresh.occupancy() > 0.0);
+        // A second search starts from a full table.
+        assert_eq!(fresh.analyze(&game).score, score);
+        let mut tiny = BotPlayer::new(5).with_hash(1);
+        assert_eq!(tiny.analyze(&game).score, score);
+    }
+}
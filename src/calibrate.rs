@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::bot::BotPlayer;
+use crate::game::{BOT, ConnectFour, GameStatus, PLAYER};
+use crate::i18n::{Msg, tr};
+use crate::solver::{Position, Solver, column_order};
+
+const STANDARD_OPENINGS: &str = include_str!("openings.txt");
+
+pub struct CalibrateOptions {
+    pub min_depth: i32,
+    pub max_depth: i32,
+    // Openings to play instead of the built-in ones, one move string per line.
+    pub openings: Option<PathBuf>,
+    pub rows: usize,
+    pub cols: usize,
+    pub hash_mb: usize,
+}
+
+// One move string per line; `#` starts a comment.
+fn parse_openings(text: &str, rows: usize, cols: usize) -> Result<Vec<String>, String> {
+    let mut openings = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let opening = line.split('#').next().unwrap_or("").trim();
+        if opening.is_empty() {
+            continue;
+        }
+        let game = ConnectFour::from_moves(rows, cols, opening, PLAYER)
+            .map_err(|err| tr(Msg::LineError, &[&(i + 1), &err]))?;
+        if game.status() != GameStatus::InProgress {
+            return Err(tr(Msg::GameAlreadyOver, &[&(i + 1)]));
+        }
+        openings.push(opening.to_string());
+    }
+    Ok(openings)
+}
+
+fn load_openings(options: &CalibrateOptions) -> Result<Vec<String>, Box<dyn Error>> {
+    let openings = match &options.openings {
+        Some(path) => {
+            let text =
+                fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            parse_openings(&text, options.rows, options.cols)
+                .map_err(|err| format!("{}: {}", path.display(), err))?
+        }
+        None if (options.rows, options.cols) == (6, 7) => {
+            parse_openings(STANDARD_OPENINGS, options.rows, options.cols)?
+        }
+        None => {
+            return Err(tr(Msg::OpeningsNeedStandardBoard, &[]).into());
+        }
+    };
+    if openings.is_empty() {
+        return Err(tr(Msg::NoOpenings, &[]).into());
+    }
+    Ok(openings)
+}
+
+// The outcome for the side to move of playing `col`, as `Solver::outcome`.
+fn outcome_after(solver: &mut Solver, pos: &Position, col: usize) -> i32 {
+    if pos.is_winning_move(col) {
+        return 1;
+    }
+    let mut after = *pos;
+    after.play(col);
+    -solver.outcome(&after)
+}
+
+#[derive(Default)]
+struct Tally {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    moves: u32,
+    // Bot moves that threw away a win or a draw.
+    errors: u32,
+}
+
+// Plays the bot against perfect play from `opening`, the bot moving with
+// `bot_piece`. Every bot move is checked against the solver: it is an error
+// when it leaves the bot worse off than the best move would, a won game
+// turning into a draw or a loss, or a drawn one into a loss.
+fn play(
+    bot: &mut BotPlayer,
+    solver: &mut Solver,
+    opening: &str,
+    bot_piece: i8,
+    rows: usize,
+    cols: usize,
+    tally: &mut Tally,
+) -> Result<(), String> {
+    let mut game = ConnectFour::from_moves(rows, cols, opening, PLAYER)?;
+    let mut piece = if opening.len().is_multiple_of(2) {
+        PLAYER
+    } else {
+        BOT
+    };
+    let status = loop {
+        let status = game.status();
+        if status != GameStatus::InProgress {
+            break status;
+        }
+        let pos = Position::from_game(&game, piece).ok_or_else(|| tr(Msg::BoardTooLarge, &[]))?;
+        let best = solver.outcome(&pos);
+        let col = if piece == bot_piece {
+            let col = bot
+                .analyze_as(&game, piece)
+                .best_move
+                .filter(|&col| pos.can_play(col))
+                .ok_or_else(|| tr(Msg::NoLegalMove, &[]))?;
+            tally.moves += 1;
+            if outcome_after(solver, &pos, col) < best {
+                tally.errors += 1;
+            }
+            col
+        } else {
+            // The first move keeping the outcome, nearest the center.
+            column_order(pos.width())
+                .filter(|&col| pos.can_play(col))
+                .find(|&col| outcome_after(solver, &pos, col) == best)
+                .expect("some move keeps the outcome")
+        };
+        game.drop_piece(col, piece);
+        piece = -piece;
+    };
+    match status {
+        GameStatus::Won { player, .. } if player == bot_piece => tally.wins += 1,
+        GameStatus::Won { .. } => tally.losses += 1,
+        _ => tally.draws += 1,
+    }
+    Ok(())
+}
+
+// Plays every opening twice per depth, the bot taking each side once, against
+// the solver, and reports how often the bot goes wrong at each depth.
+pub fn run(
+    make_bot: impl Fn(i32) -> BotPlayer,
+    options: &CalibrateOptions,
+) -> Result<(), Box<dyn Error>> {
+    let openings = load_openings(options)?;
+    let mut solver = Solver::new(options.hash_mb);
+    eprintln!(
+        "Playing depths {} to {} against the solver from {} openings, both sides each.",
+        options.min_depth,
+        options.max_depth,
+        openings.len()
+    );
+    println!("depth  games  won  drawn  lost  moves  errors  error rate");
+    for depth in options.min_depth..=options.max_depth {
+        let mut bot = make_bot(depth);
+        let mut tally = Tally::default();
+        for opening in &openings {
+            for bot_piece in [PLAYER, BOT] {
+                play(
+                    &mut bot,
+                    &mut solver,
+                    opening,
+                    bot_piece,
+                    options.rows,
+                    options.cols,
+                    &mut tally,
+                )?;
+            }
+        }
+        let rate = 100.0 * tally.errors as f64 / tally.moves.max(1) as f64;
+        info!(
+            depth,
+            wins = tally.wins,
+            draws = tally.draws,
+            losses = tally.losses,
+            moves = tally.moves,
+            errors = tally.errors,
+            solver_nodes = solver.nodes(),
+            "depth calibrated"
+        );
+        println!(
+            "{:>5}  {:>5}  {:>3}  {:>5}  {:>4}  {:>5}  {:>6}  {:>9.1}%",
+            depth,
+            tally.wins + tally.draws + tally.losses,
+            tally.wins,
+            tally.draws,
+            tally.losses,
+            tally.moves,
+            tally.errors,
+            rate
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::analysis::score_after;
+use crate::bot::BotPlayer;
+use crate::game::{ConnectFour, PLAYER};
+use crate::i18n::{Msg, tr};
+use crate::theme::Notation;
+
+// How readily the coach objects to a move: lenient only warns about moves that
+// lose by force, the others also about moves well below the best one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Strictness {
+    Lenient,
+    Normal,
+    Strict,
+}
+
+impl Strictness {
+    // Largest score drop, on the bot's scale, let through without a warning.
+    fn tolerance(self) -> Option<i32> {
+        match self {
+            Strictness::Lenient => None,
+            Strictness::Normal => Some(30),
+            Strictness::Strict => Some(10),
+        }
+    }
+}
+
+// A warning if the human's intended move `col` is a mistake, naming a better one
+// in `notation`.
+pub fn review(
+    coach: &mut BotPlayer,
+    game: &ConnectFour,
+    col: usize,
+    strictness: Strictness,
+    notation: Notation,
+) -> Option<String> {
+    let chosen = score_after(coach, game, col, PLAYER);
+    let (best_col, best) = game
+        .get_valid_moves()
+        .into_iter()
+        .map(|other| (other, score_after(coach, game, other, PLAYER)))
+        .max_by_key(|&(_, score)| score)?;
+    if best <= chosen {
+        return None;
+    }
+    let reward = coach.reward();
+    let problem = if chosen <= -reward && best > -reward {
+        Msg::CoachForcedLoss
+    } else if strictness
+        .tolerance()
+        .is_some_and(|tolerance| best - chosen > tolerance)
+    {
+        Msg::CoachGivesAway
+    } else {
+        return None;
+    };
+    let (column, best_column) = (notation.label(col), notation.label(best_col));
+    Some(tr(
+        Msg::CoachWarning,
+        &[&column, &tr(problem, &[]), &best_column, &column],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // o to move, with three of the bot's pieces stacked in column 4.
+    const THREAT: &str = "142434";
+
+    fn warning(col: usize, strictness: Strictness) -> Option<String> {
+        let mut coach = BotPlayer::new(4);
+        let game = ConnectFour::from_moves(6, 7, THREAT, PLAYER).unwrap();
+        review(&mut coach, &game, col, strictness, Notation::Numbers)
+    }
+
+    #[test]
+    fn the_best_move_passes() {
+        assert_eq!(warning(3, Strictness::Strict), None);
+    }
+
+    #[test]
+    fn even_a_lenient_coach_warns_of_a_forced_loss() {
+        let text = warning(6, Strictness::Lenient).unwrap();
+        assert!(text.starts_with("Coach: column 7 lets the bot force a win; column 4 is better."));
+    }
+
+    #[test]
+    fn stricter_coaches_tolerate_less() {
+        assert_eq!(Strictness::Lenient.tolerance(), None);
+        assert!(Strictness::Strict.tolerance() < Strictness::Normal.tolerance());
+    }
+}
@@ -0,0 +1,114 @@
+use serde::Deserialize;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::bot::{Engine, Eval, Personality, Weights};
+use crate::coach::Strictness;
+use crate::game::{First, Handicap, OnTimeout};
+use crate::i18n::Lang;
+use crate::rules::Variant;
+use crate::theme::{Notation, Symbol};
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub depth: Option<i32>,
+    pub engine: Option<Engine>,
+    pub move_time: Option<f64>,
+    pub blitz: Option<f64>,
+    pub on_timeout: Option<OnTimeout>,
+    pub eval: Option<Eval>,
+    pub personality: Option<Personality>,
+    pub rollouts: Option<u32>,
+    pub hash: Option<usize>,
+    pub tablebase: Option<PathBuf>,
+    pub book: Option<PathBuf>,
+    #[cfg(feature = "nn")]
+    pub nn_weights: Option<PathBuf>,
+    pub swap: Option<bool>,
+    pub adaptive: Option<bool>,
+    pub explain: Option<bool>,
+    pub coach: Option<Strictness>,
+    pub review: Option<bool>,
+    pub first: Option<First>,
+    pub symbol: Option<Symbol>,
+    pub notation: Option<Notation>,
+    pub handicap: Option<Handicap>,
+    pub random_start: Option<usize>,
+    pub variant: Option<Variant>,
+    pub flip_every: Option<usize>,
+    pub sound: SoundConfig,
+    pub lang: Option<Lang>,
+    pub weights: Weights,
+    pub board: BoardConfig,
+    pub colors: ColorConfig,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoardConfig {
+    pub rows: Option<usize>,
+    pub cols: Option<usize>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    pub player: Option<String>,
+    pub bot: Option<String>,
+}
+
+// Cues to leave out when sound is on.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SoundConfig {
+    pub enabled: Option<bool>,
+    pub bot_move: Option<bool>,
+    pub threat: Option<bool>,
+    pub game_end: Option<bool>,
+}
+
+fn base_dir(xdg_var: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var_os(xdg_var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => Some(
+            PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?)
+                .join(fallback),
+        ),
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        base_dir("XDG_CONFIG_HOME", ".config")?
+            .join("connect-four-bot")
+            .join("config.toml"),
+    )
+}
+
+// Where state such as puzzle streaks is kept between runs.
+pub fn data_dir() -> Option<PathBuf> {
+    Some(base_dir("XDG_DATA_HOME", ".local/share")?.join("connect-four-bot"))
+}
+
+// An explicitly requested file must exist; the default one is optional.
+pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == ErrorKind::NotFound && !required => {
+            return Ok(Config::default());
+        }
+        Err(err) => return Err(format!("{}: {}", path.display(), err).into()),
+    };
+    toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err).into())
+}
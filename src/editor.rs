@@ -0,0 +1,263 @@
+use crossterm::style::Stylize;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::io::{IsTerminal, stdin};
+
+use crate::game::{BOT, ConnectFour, EMPTY, PLAYER, column_char, symbol};
+use crate::i18n::{Msg, tr};
+use crate::input::{self, Key};
+use crate::terminal::clear_screen;
+use crate::theme::{Notation, Theme};
+
+// What to do with the position once the editor is closed.
+pub enum Outcome {
+    Play(String),
+    Analyze(String),
+    Quit,
+}
+
+// Checks that `game` can arise in a real game with o moving first, and returns
+// a move sequence reaching it. Columns in errors are named in `notation`.
+pub fn validate(game: &ConnectFour, notation: Notation) -> Result<String, String> {
+    for col in 0..game.cols {
+        // Row 0 is the top, so a column is filled from the last row upwards.
+        let height = (0..game.rows)
+            .rev()
+            .take_while(|&row| game.board[row][col] != EMPTY)
+            .count();
+        if (0..game.rows - height).any(|row| game.board[row][col] != EMPTY) {
+            return Err(tr(Msg::FloatingPiece, &[&notation.label(col)]));
+        }
+    }
+    let count = |piece: i8| game.board.iter().flatten().filter(|&&c| c == piece).count();
+    let (player, bot) = (count(PLAYER), count(BOT));
+    if player != bot && player != bot + 1 {
+        return Err(tr(Msg::PieceCounts, &[&player, &bot]));
+    }
+    match (game.check_win(PLAYER), game.check_win(BOT)) {
+        (true, true) => return Err(tr(Msg::BothWin, &[])),
+        (true, false) if player == bot => {
+            return Err(tr(Msg::OWinsOutOfTurn, &[]));
+        }
+        (false, true) if player > bot => {
+            return Err(tr(Msg::XWinsOutOfTurn, &[]));
+        }
+        _ => {}
+    }
+    let last = if player > bot { PLAYER } else { BOT };
+    let mut cols = Vec::new();
+    let mut seen = HashSet::new();
+    if !unplay(&mut game.clone(), last, &mut seen, &mut cols) {
+        return Err(tr(Msg::NoMoveOrder, &[]));
+    }
+    Ok(cols.iter().map(|&col| column_char(col)).collect())
+}
+
+// Takes back moves of `last` and its opponent in turn until the board is empty,
+// never passing through a position where the game would already have ended.
+// The columns are pushed in the order they were played.
+fn unplay(
+    game: &mut ConnectFour,
+    last: i8,
+    seen: &mut HashSet<Vec<Vec<i8>>>,
+    cols: &mut Vec<usize>,
+) -> bool {
+    if game.board.iter().flatten().all(|&cell| cell == EMPTY) {
+        return true;
+    }
+    for col in 0..game.cols {
+        let Some(row) = (0..game.rows).find(|&row| game.board[row][col] != EMPTY) else {
+            continue;
+        };
+        if game.board[row][col] != last {
+            continue;
+        }
+        game.board[row][col] = EMPTY;
+        let open = !game.check_win(PLAYER) && !game.check_win(BOT);
+        if open && seen.insert(game.board.clone()) && unplay(game, -last, seen, cols) {
+            game.board[row][col] = last;
+            cols.push(col);
+            return true;
+        }
+        game.board[row][col] = last;
+    }
+    false
+}
+
+fn render(game: &ConnectFour, theme: &Theme, cursor: (usize, usize)) -> String {
+    let mut out = String::new();
+    for (r, row) in game.board.iter().enumerate() {
+        for (c, &cell) in row.iter().enumerate() {
+            let styled = match cell {
+                BOT => symbol(cell).with(theme.bot),
+                PLAYER => symbol(cell).with(theme.player),
+                _ => symbol(cell).stylize(),
+            };
+            let _ = if (r, c) == cursor {
+                write!(out, " {}", styled.reverse())
+            } else {
+                write!(out, " {}", styled)
+            };
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn run(rows: usize, cols: usize, theme: &Theme) -> Outcome {
+    let mut game = ConnectFour::new(rows, cols);
+    if stdin().is_terminal() {
+        edit_keys(&mut game, theme)
+    } else {
+        edit_lines(&mut game, theme.notation)
+    }
+}
+
+// A cursor moved with the arrow keys; o and x place a piece, Delete or space
+// clears the cell.
+fn edit_keys(game: &mut ConnectFour, theme: &Theme) -> Outcome {
+    let notation = theme.notation;
+    let mut cursor = (game.rows - 1, game.cols / 2);
+    let mut notice = String::new();
+    loop {
+        clear_screen();
+        print!("{}", render(game, theme, cursor));
+        println!("{}", tr(Msg::EditorKeys, &[]));
+        println!("{}", notice);
+        notice.clear();
+        let (row, col) = cursor;
+        match input::read_key() {
+            Key::Left => cursor.1 = col.saturating_sub(1),
+            Key::Right => cursor.1 = (col + 1).min(game.cols - 1),
+            Key::Up => cursor.0 = row.saturating_sub(1),
+            Key::Down => cursor.0 = (row + 1).min(game.rows - 1),
+            Key::Char('o') => game.board[row][col] = PLAYER,
+            Key::Char('x') => game.board[row][col] = BOT,
+            Key::Char(' ') | Key::Char('.') | Key::Delete => game.board[row][col] = EMPTY,
+            Key::Char('c') => *game = ConnectFour::new(game.rows, game.cols),
+            Key::Char('p') | Key::Enter => match validate(game, notation) {
+                Ok(moves) => return Outcome::Play(moves),
+                Err(err) => notice = tr(Msg::InvalidPosition, &[&err]),
+            },
+            Key::Char('a') => match validate(game, notation) {
+                Ok(moves) => return Outcome::Analyze(moves),
+                Err(err) => notice = tr(Msg::InvalidPosition, &[&err]),
+            },
+            Key::Quit => return Outcome::Quit,
+            _ => {}
+        }
+    }
+}
+
+// Commands read line by line, for input that is not a terminal. Rows are
+// counted from the bottom, starting at 1.
+fn edit_lines(game: &mut ConnectFour, notation: Notation) -> Outcome {
+    println!("{}", tr(Msg::EditorCommands, &[]));
+    loop {
+        print!("{}", game);
+        let mut line = String::new();
+        match stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => return Outcome::Quit,
+            Ok(_) => {}
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words.as_slice() {
+            [] => Ok(()),
+            ["quit"] => return Outcome::Quit,
+            ["clear"] => {
+                *game = ConnectFour::new(game.rows, game.cols);
+                Ok(())
+            }
+            ["play"] => match validate(game, notation) {
+                Ok(moves) => return Outcome::Play(moves),
+                Err(err) => Err(err),
+            },
+            ["analyze"] => match validate(game, notation) {
+                Ok(moves) => return Outcome::Analyze(moves),
+                Err(err) => Err(err),
+            },
+            [piece, col, row] => set_cell(game, piece, col, row),
+            _ => Err(tr(Msg::UnknownCommand, &[&line.trim()])),
+        };
+        if let Err(err) = result {
+            println!("{}", tr(Msg::Error, &[&err]));
+        }
+    }
+}
+
+fn set_cell(game: &mut ConnectFour, piece: &str, col: &str, row: &str) -> Result<(), String> {
+    let piece = match piece {
+        "o" => PLAYER,
+        "x" => BOT,
+        "." => EMPTY,
+        other => return Err(tr(Msg::UnknownPiece, &[&other])),
+    };
+    let col = col
+        .parse::<usize>()
+        .ok()
+        .filter(|col| (1..=game.cols).contains(col))
+        .ok_or_else(|| tr(Msg::ColumnRange, &[&game.cols]))?;
+    let row = row
+        .parse::<usize>()
+        .ok()
+        .filter(|row| (1..=game.rows).contains(row))
+        .ok_or_else(|| tr(Msg::RowRange, &[&game.rows]))?;
+    game.board[game.rows - row][col - 1] = piece;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn played_positions_validate_to_a_move_order() {
+        let game = ConnectFour::from_moves(6, 7, "4453321", PLAYER).unwrap();
+        let moves = validate(&game, Notation::Numbers).unwrap();
+        let replayed = ConnectFour::from_moves(6, 7, &moves, PLAYER).unwrap();
+        assert_eq!(replayed.board, game.board);
+    }
+
+    #[test]
+    fn impossible_positions_are_rejected() {
+        let mut game = ConnectFour::new(6, 7);
+        set_cell(&mut game, "o", "2", "2").unwrap();
+        assert_eq!(
+            validate(&game, Notation::Letters),
+            Err(tr(Msg::FloatingPiece, &[&'b']))
+        );
+        set_cell(&mut game, "o", "2", "1").unwrap();
+        assert_eq!(
+            validate(&game, Notation::Letters),
+            Err(tr(Msg::PieceCounts, &[&2, &0]))
+        );
+        // o has four in a row but x has as many pieces, so x moved after the win.
+        let game = ConnectFour::from_moves(6, 7, "1212121", PLAYER).unwrap();
+        let mut late = game.clone();
+        late.board[late.rows - 1][6] = BOT;
+        assert_eq!(
+            validate(&late, Notation::Numbers),
+            Err(tr(Msg::OWinsOutOfTurn, &[]))
+        );
+        assert!(validate(&game, Notation::Numbers).is_ok());
+    }
+
+    #[test]
+    fn cells_are_counted_from_the_bottom_left() {
+        let mut game = ConnectFour::new(6, 7);
+        set_cell(&mut game, "x", "1", "1").unwrap();
+        assert_eq!(game.board[5][0], BOT);
+        assert_eq!(
+            set_cell(&mut game, "x", "8", "1"),
+            Err(tr(Msg::ColumnRange, &[&7]))
+        );
+        assert_eq!(
+            set_cell(&mut game, "x", "1", "0"),
+            Err(tr(Msg::RowRange, &[&6]))
+        );
+        assert_eq!(
+            set_cell(&mut game, "y", "1", "1"),
+            Err(tr(Msg::UnknownPiece, &[&"y"]))
+        );
+    }
+}
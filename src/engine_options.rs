@@ -0,0 +1,204 @@
+use clap::ValueEnum;
+use std::time::Duration;
+
+use crate::bot::{Engine, Eval, MAX_DEPTH, Personality, Weights};
+use crate::i18n::{Msg, tr};
+
+// An engine setting that can change between moves, as UCI's `setoption`
+// allows: `set <option> <value>` in a game, `setoption <option> <value>` over
+// the engine protocol. A personality brings its own weights, so setting
+// weights drops it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    Depth(i32),
+    Engine(Engine),
+    Eval(Eval),
+    Rollouts(u32),
+    Weights(Weights),
+    Personality(Option<Personality>),
+    MoveTime(Option<Duration>),
+    Hash(usize),
+}
+
+// Whatever bots are built from, so that a setting reaches the next one.
+pub trait Configurable {
+    fn set(&mut self, setting: Setting) -> Result<(), String>;
+}
+
+// The names of the options' values, as "minimax|random".
+fn values<T: ValueEnum>() -> String {
+    let names: Vec<String> = T::value_variants()
+        .iter()
+        .filter_map(|value| value.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    names.join("|")
+}
+
+// Every option with the values it takes, for help and error messages.
+pub fn options() -> Vec<(&'static str, String)> {
+    vec![
+        ("depth", format!("0-{}", MAX_DEPTH)),
+        ("engine", values::<Engine>()),
+        ("eval", values::<Eval>()),
+        ("rollouts", "<playouts>".to_string()),
+        ("weights", "<center>,<two>,<three>".to_string()),
+        ("personality", format!("{}|none", values::<Personality>())),
+        ("move-time", "<secs>|none".to_string()),
+        ("hash", "<mb>".to_string()),
+    ]
+}
+
+fn variant<T: ValueEnum>(value: &str) -> Option<T> {
+    T::from_str(value, true).ok()
+}
+
+pub fn names() -> String {
+    let names: Vec<&str> = options().iter().map(|&(name, _)| name).collect();
+    names.join(", ")
+}
+
+impl Setting {
+    // `line` is an option's name followed by its value, as in `depth 8`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let Some((name, value)) = line.trim().split_once(char::is_whitespace) else {
+            return Err(tr(Msg::ExpectedOption, &[&names()]));
+        };
+        let value = value.trim();
+        let Some((_, expected)) = options().into_iter().find(|&(option, _)| option == name) else {
+            return Err(tr(Msg::UnknownOption, &[&name, &names()]));
+        };
+        let invalid = || tr(Msg::InvalidOption, &[&name, &value, &expected]);
+        let setting = match name {
+            "depth" => Setting::Depth(
+                value
+                    .parse()
+                    .ok()
+                    .filter(|depth| (0..=MAX_DEPTH).contains(depth))
+                    .ok_or_else(invalid)?,
+            ),
+            "engine" => Setting::Engine(variant(value).ok_or_else(invalid)?),
+            "eval" => Setting::Eval(variant(value).ok_or_else(invalid)?),
+            "rollouts" => {
+                Setting::Rollouts(value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?)
+            }
+            "weights" => {
+                let numbers: Vec<i32> = value
+                    .split(',')
+                    .map(|n| n.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?;
+                let [center, two, three] = numbers[..] else {
+                    return Err(invalid());
+                };
+                Setting::Weights(Weights { center, two, three })
+            }
+            "personality" if value == "none" => Setting::Personality(None),
+            "personality" => Setting::Personality(Some(variant(value).ok_or_else(invalid)?)),
+            "move-time" if value == "none" => Setting::MoveTime(None),
+            "move-time" => Setting::MoveTime(Some(
+                value
+                    .parse()
+                    .ok()
+                    .filter(|secs: &f64| secs.is_finite() && *secs > 0.0)
+                    .map(Duration::from_secs_f64)
+                    .ok_or_else(invalid)?,
+            )),
+            _ => Setting::Hash(
+                value
+                    .parse()
+                    .ok()
+                    .filter(|&mb| mb > 0)
+                    .ok_or_else(invalid)?,
+            ),
+        };
+        Ok(setting)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::Depth(_) => "depth",
+            Setting::Engine(_) => "engine",
+            Setting::Eval(_) => "eval",
+            Setting::Rollouts(_) => "rollouts",
+            Setting::Weights(_) => "weights",
+            Setting::Personality(_) => "personality",
+            Setting::MoveTime(_) => "move-time",
+            Setting::Hash(_) => "hash",
+        }
+    }
+
+    // The value as `parse` takes it.
+    pub fn value(&self) -> String {
+        match *self {
+            Setting::Depth(depth) => depth.to_string(),
+            Setting::Engine(engine) => engine.name().to_string(),
+            Setting::Eval(eval) => eval.name().to_string(),
+            Setting::Rollouts(rollouts) => rollouts.to_string(),
+            Setting::Weights(weights) => {
+                format!("{},{},{}", weights.center, weights.two, weights.three)
+            }
+            Setting::Personality(personality) => {
+                personality.map_or("none", Personality::name).to_string()
+            }
+            Setting::MoveTime(move_time) => {
+                move_time.map_or("none".to_string(), |time| time.as_secs_f64().to_string())
+            }
+            Setting::Hash(mb) => mb.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_option() {
+        assert_eq!(Setting::parse("depth 8"), Ok(Setting::Depth(8)));
+        assert_eq!(
+            Setting::parse("engine  Random "),
+            Ok(Setting::Engine(Engine::Random))
+        );
+        assert_eq!(Setting::parse("rollouts 50"), Ok(Setting::Rollouts(50)));
+        assert_eq!(
+            Setting::parse("weights 3, 5,100"),
+            Ok(Setting::Weights(Weights {
+                center: 3,
+                two: 5,
+                three: 100
+            }))
+        );
+        assert_eq!(
+            Setting::parse("personality none"),
+            Ok(Setting::Personality(None))
+        );
+        assert_eq!(
+            Setting::parse("move-time 0.5"),
+            Ok(Setting::MoveTime(Some(Duration::from_millis(500))))
+        );
+        assert_eq!(
+            Setting::parse("move-time none"),
+            Ok(Setting::MoveTime(None))
+        );
+        assert_eq!(Setting::parse("hash 64"), Ok(Setting::Hash(64)));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        for line in [
+            "depth",
+            "depth -1",
+            "depth 13",
+            "speed 3",
+            "rollouts 0",
+            "weights 1,2",
+            "move-time 0",
+            "move-time inf",
+            "engine alphazero",
+            "hash 0",
+        ] {
+            assert!(Setting::parse(line).is_err(), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::bot::Analysis;
+use crate::game::ConnectFour;
+use crate::i18n::{Msg, tr};
+use crate::theme::Notation;
+
+// Columns where `piece` would win by dropping a piece now.
+pub fn winning_columns(game: &ConnectFour, piece: i8) -> Vec<usize> {
+    game.get_valid_moves()
+        .into_iter()
+        .filter(|&col| game.winning_move(col, piece))
+        .collect()
+}
+
+// Directions of the lines of four through (`row`, `col`) made of `piece`.
+fn lines_through(game: &ConnectFour, row: usize, col: usize, piece: i8) -> Vec<Msg> {
+    let directions = [
+        ((0, 1), Msg::Horizontal),
+        ((1, 0), Msg::Vertical),
+        ((1, 1), Msg::Diagonal),
+        ((1, -1), Msg::Diagonal),
+    ];
+    let mut names = Vec::new();
+    for ((dr, dc), name) in directions {
+        let count = |sign: isize| {
+            (1..4)
+                .take_while(|&i| {
+                    let r = row as isize + sign * i * dr;
+                    let c = col as isize + sign * i * dc;
+                    r >= 0
+                        && c >= 0
+                        && (r as usize) < game.rows
+                        && (c as usize) < game.cols
+                        && game.board[r as usize][c as usize] == piece
+                })
+                .count()
+        };
+        if count(1) + count(-1) >= 3 && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn list(columns: &[usize], notation: Notation) -> String {
+    let names: Vec<String> = columns
+        .iter()
+        .map(|&col| notation.label(col).to_string())
+        .collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => {
+            format!("{} {} {}", rest.join(", "), tr(Msg::And, &[]), last)
+        }
+        _ => names.join(""),
+    }
+}
+
+// A one-line reason for the move `col` that `piece` is about to play in
+// `game`, from the threats on the board before and after it and the search
+// result. `reward` is the score the search gives a won game; columns are named
+// in `notation`.
+pub fn explain(
+    game: &ConnectFour,
+    col: usize,
+    piece: i8,
+    analysis: &Analysis,
+    reward: i32,
+    notation: Notation,
+) -> String {
+    let column = notation.label(col);
+    let Some(row) = game.landing_row(col) else {
+        return tr(Msg::Plays, &[&column]);
+    };
+    let mut after = game.clone();
+    after.drop_piece(col, piece);
+
+    if game.wins_at(row, col, piece) {
+        return tr(Msg::PlaysAndWins, &[&column]);
+    }
+    if winning_columns(game, -piece).contains(&col) {
+        let mut blocked = game.clone();
+        blocked.board[row][col] = -piece;
+        let lines = lines_through(&blocked, row, col, -piece);
+        return match lines.as_slice() {
+            [line] => tr(Msg::BlocksLine, &[&column, &tr(*line, &[])]),
+            _ => tr(Msg::BlocksThreat, &[&column]),
+        };
+    }
+    let threats = winning_columns(&after, piece);
+    let opponent_threats = winning_columns(&after, -piece);
+    if threats.len() >= 2 {
+        return tr(Msg::DoubleThreat, &[&column, &list(&threats, notation)]);
+    }
+    if let [threat] = threats.as_slice()
+        && opponent_threats.is_empty()
+    {
+        return tr(Msg::Threatens, &[&column, &notation.label(*threat)]);
+    }
+    if analysis.score >= reward {
+        let moves = analysis.pv.len().div_ceil(2);
+        return tr(Msg::ForcedWin, &[&column, &moves]);
+    }
+    if analysis.score <= -reward {
+        return tr(Msg::StubbornDefense, &[&column]);
+    }
+    // The move may open up the opponent's winning cell in the same column.
+    if after.winning_move(col, -piece) {
+        return tr(Msg::AllowsWinAbove, &[&column]);
+    }
+    if col == game.cols / 2 {
+        return tr(Msg::TakesCenter, &[&column]);
+    }
+    tr(Msg::BestAtDepth, &[&column, &analysis.depth])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BOT, PLAYER};
+
+    fn analysis(score: i32) -> Analysis {
+        Analysis {
+            best_move: None,
+            score,
+            pv: Vec::new(),
+            depth: 6,
+            nodes: 0,
+        }
+    }
+
+    fn reason(moves: &str, col: usize, piece: i8) -> String {
+        let game = ConnectFour::from_moves(6, 7, moves, PLAYER).unwrap();
+        explain(&game, col, piece, &analysis(0), 100, Notation::Letters)
+    }
+
+    #[test]
+    fn both_sides_see_a_column_of_three() {
+        let game = ConnectFour::from_moves(6, 7, "414141", PLAYER).unwrap();
+        assert_eq!(winning_columns(&game, PLAYER), vec![3]);
+        assert_eq!(reason("414141", 3, PLAYER), "plays d and wins");
+        assert_eq!(
+            reason("41414", 3, BOT),
+            "plays d to block your vertical three"
+        );
+    }
+
+    #[test]
+    fn threats_are_named_by_column() {
+        assert_eq!(
+            reason("3141", 4, PLAYER),
+            "plays e to create a double threat on columns b and f"
+        );
+        assert_eq!(
+            reason("34", 4, PLAYER),
+            "plays e as its best move at depth 6"
+        );
+    }
+
+    #[test]
+    fn quiet_moves_fall_back_on_the_search() {
+        let game = ConnectFour::new(6, 7);
+        let won = Analysis {
+            pv: vec![3, 3, 3],
+            ..analysis(100)
+        };
+        assert_eq!(
+            explain(&game, 0, PLAYER, &won, 100, Notation::Numbers),
+            "plays 1 and sees a forced win within 2 moves"
+        );
+        assert_eq!(reason("", 3, PLAYER), "plays d to take the center");
+    }
+}
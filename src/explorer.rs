@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::io::{IsTerminal, stdin, stdout};
+
+use crate::analysis::parse_position;
+use crate::game::{BOT, ConnectFour, PLAYER, column_char, parse_column, symbol};
+use crate::history::{Database, Filter, Winner};
+use crate::i18n::{Msg, tr};
+use crate::input::{self, Input};
+use crate::terminal::clear_screen;
+use crate::theme::Theme;
+
+#[derive(Default)]
+struct ColumnStats {
+    games: u32,
+    human: u32,
+    draws: u32,
+    bot: u32,
+}
+
+// Recorded games are matched by position rather than by move order, so
+// transpositions into the current position are counted too.
+fn column_stats(games: &[(String, Winner)], game: &ConnectFour, ply: usize) -> Vec<ColumnStats> {
+    let mut stats: Vec<ColumnStats> = (0..game.cols).map(|_| ColumnStats::default()).collect();
+    for (moves, winner) in games {
+        let Some(next) = moves.as_bytes().get(ply) else {
+            continue;
+        };
+        let Ok(reached) = ConnectFour::from_moves(game.rows, game.cols, &moves[..ply], PLAYER)
+        else {
+            continue;
+        };
+        if reached.board != game.board {
+            continue;
+        }
+        // Swaps and passes are not counted as columns.
+        let Some(entry) = parse_column(*next as char).and_then(|col| stats.get_mut(col)) else {
+            continue;
+        };
+        entry.games += 1;
+        match winner {
+            Winner::Human => entry.human += 1,
+            Winner::Draw => entry.draws += 1,
+            Winner::Bot => entry.bot += 1,
+        }
+    }
+    stats
+}
+
+fn percent(part: u32, total: u32) -> String {
+    format!("{:.0}%", 100.0 * part as f64 / total as f64)
+}
+
+fn print_stats(stats: &[ColumnStats], theme: &Theme) {
+    let total: u32 = stats.iter().map(|entry| entry.games).sum();
+    if total == 0 {
+        println!("{}", tr(Msg::NoGamesReach, &[]));
+        return;
+    }
+    println!(
+        "{:>6}  {:>5}  {:>6}  {:>6}  {:>6}  {:>6}",
+        "column", "games", "played", "human", "draw", "bot"
+    );
+    for (col, entry) in stats.iter().enumerate() {
+        if entry.games == 0 {
+            continue;
+        }
+        println!(
+            "{:>6}  {:>5}  {:>6}  {:>6}  {:>6}  {:>6}",
+            theme.column_label(col),
+            entry.games,
+            percent(entry.games, total),
+            percent(entry.human, entry.games),
+            percent(entry.draws, entry.games),
+            percent(entry.bot, entry.games)
+        );
+    }
+}
+
+pub fn run(
+    db: &Database,
+    rows: usize,
+    cols: usize,
+    position: &str,
+    theme: &Theme,
+) -> Result<(), Box<dyn Error>> {
+    parse_position(rows, cols, position)?;
+    let games: Vec<(String, Winner)> = db
+        .list(&Filter {
+            board: Some((rows, cols)),
+            ..Filter::default()
+        })?
+        .into_iter()
+        .map(|record| (record.moves, record.winner))
+        .collect();
+    let interactive = stdin().is_terminal() && stdout().is_terminal();
+    let mut moves = position.to_string();
+
+    loop {
+        let game = ConnectFour::from_moves(rows, cols, &moves, PLAYER)?;
+        if interactive {
+            clear_screen();
+        }
+        println!("{}", theme.render(&game));
+        let to_move = if moves.len().is_multiple_of(2) {
+            PLAYER
+        } else {
+            BOT
+        };
+        let start = tr(Msg::StartPosition, &[]);
+        println!(
+            "{}",
+            tr(
+                Msg::ExplorerPosition,
+                &[
+                    &if moves.is_empty() { &start } else { &moves },
+                    &symbol(to_move),
+                    &games.len()
+                ]
+            )
+        );
+        print_stats(&column_stats(&games, &game, moves.len()), theme);
+        if !interactive {
+            return Ok(());
+        }
+
+        println!("{}", tr(Msg::ExplorerKeys, &[]));
+        match input::read_column(&game) {
+            Input::Column(col) => {
+                let next = format!("{}{}", moves, column_char(col));
+                if parse_position(rows, cols, &next).is_ok() {
+                    moves = next;
+                }
+            }
+            Input::Back => {
+                if moves.len() > position.len() {
+                    moves.pop();
+                }
+            }
+            Input::Invalid
+            | Input::Redraw
+            | Input::Help
+            | Input::Rules
+            | Input::Swap
+            | Input::Set(_) => {}
+            Input::Quit => return Ok(()),
+        }
+    }
+}
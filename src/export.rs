@@ -0,0 +1,206 @@
+use clap::ValueEnum;
+use gif::{Encoder, Frame, Repeat};
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::game::{BOT, ConnectFour, PLAYER};
+use crate::history::GameRecord;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Gif,
+    Svg,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Gif => "gif",
+            Format::Svg => "svg",
+        }
+    }
+}
+
+// Pixels per board cell, and the radius of a piece within it.
+const CELL: usize = 40;
+const RADIUS: usize = 16;
+// Seconds each move is shown; the final position stays up longer.
+const MOVE_SECS: f64 = 0.7;
+const LAST_SECS: f64 = 3.0;
+
+// Board, empty cell, o and x, as RGB.
+const PALETTE: [[u8; 3]; 4] = [
+    [0x1d, 0x4e, 0xd8],
+    [0xf8, 0xfa, 0xfc],
+    [0xf5, 0xc5, 0x18],
+    [0xd6, 0x28, 0x28],
+];
+
+// The board after every ply of the game, starting with the empty board.
+fn frames(record: &GameRecord) -> Result<Vec<ConnectFour>, String> {
+    (0..=record.moves.len())
+        .map(|ply| ConnectFour::from_moves(record.rows, record.cols, &record.moves[..ply], PLAYER))
+        .collect()
+}
+
+fn color_index(cell: i8) -> usize {
+    match cell {
+        PLAYER => 2,
+        BOT => 3,
+        _ => 1,
+    }
+}
+
+fn hex(index: usize) -> String {
+    let [r, g, b] = PALETTE[index];
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Every position is its own group, made visible in turn by a discrete
+// animation that loops over the whole game.
+pub fn svg(record: &GameRecord) -> Result<String, Box<dyn Error>> {
+    let frames = frames(record)?;
+    let (width, height) = (record.cols * CELL, record.rows * CELL);
+    let total = MOVE_SECS * (frames.len() - 1) as f64 + LAST_SECS;
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="{}" height="{}" fill="{}"/>"#,
+        width,
+        height,
+        hex(0)
+    )?;
+    for (i, game) in frames.iter().enumerate() {
+        let start = MOVE_SECS * i as f64 / total;
+        let end = if i + 1 == frames.len() {
+            1.0
+        } else {
+            MOVE_SECS * (i + 1) as f64 / total
+        };
+        writeln!(out, r#"<g visibility="hidden">"#)?;
+        writeln!(
+            out,
+            r#"<animate attributeName="visibility" values="hidden;visible;hidden" keyTimes="0;{:.4};{:.4}" calcMode="discrete" dur="{:.2}s" repeatCount="indefinite"/>"#,
+            start, end, total
+        )?;
+        for (row, cells) in game.board.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                writeln!(
+                    out,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    col * CELL + CELL / 2,
+                    row * CELL + CELL / 2,
+                    RADIUS,
+                    hex(color_index(cell))
+                )?;
+            }
+        }
+        writeln!(out, "</g>")?;
+    }
+    writeln!(out, "</svg>")?;
+    Ok(out)
+}
+
+fn rasterize(game: &ConnectFour) -> Vec<u8> {
+    let (width, height) = (game.cols * CELL, game.rows * CELL);
+    let mut pixels = vec![0; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let (row, col) = (y / CELL, x / CELL);
+            let dx = (x % CELL) as isize - (CELL / 2) as isize;
+            let dy = (y % CELL) as isize - (CELL / 2) as isize;
+            if dx * dx + dy * dy <= (RADIUS * RADIUS) as isize {
+                pixels[y * width + x] = color_index(game.board[row][col]) as u8;
+            }
+        }
+    }
+    pixels
+}
+
+pub fn gif(record: &GameRecord) -> Result<Vec<u8>, Box<dyn Error>> {
+    let frames = frames(record)?;
+    let (width, height) = (record.cols * CELL, record.rows * CELL);
+    let palette: Vec<u8> = PALETTE.iter().flatten().copied().collect();
+    let mut out = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut out, width as u16, height as u16, &palette)?;
+        encoder.set_repeat(Repeat::Infinite)?;
+        for (i, game) in frames.iter().enumerate() {
+            let pixels = rasterize(game);
+            let mut frame = Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+            let secs = if i + 1 == frames.len() {
+                LAST_SECS
+            } else {
+                MOVE_SECS
+            };
+            // GIF delays are in hundredths of a second.
+            frame.delay = (secs * 100.0) as u16;
+            encoder.write_frame(&frame)?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Winner;
+
+    fn record(moves: &str) -> GameRecord {
+        GameRecord {
+            id: 1,
+            started_at: 0,
+            finished_at: 0,
+            rows: 6,
+            cols: 7,
+            moves: moves.to_string(),
+            winner: Winner::Human,
+            engine: "minimax".to_string(),
+            depth: 4,
+            move_time: None,
+            handicap: None,
+            blunder_rate: 0.0,
+            match_id: None,
+        }
+    }
+
+    #[test]
+    fn svg_shows_every_position() {
+        let svg = svg(&record("4455")).unwrap();
+        assert_eq!(svg.matches("<g ").count(), 5);
+        assert_eq!(svg.matches("<circle ").count(), 5 * 6 * 7);
+        // o's pieces in the five positions.
+        assert_eq!(
+            svg.matches(&format!(r#"fill="{}""#, hex(2))).count(),
+            1 + 1 + 2 + 2
+        );
+    }
+
+    #[test]
+    fn gif_has_a_frame_per_position() {
+        let data = gif(&record("445")).unwrap();
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(data.as_slice())
+            .unwrap();
+        assert_eq!(
+            (decoder.width(), decoder.height()),
+            (7 * CELL as u16, 6 * CELL as u16)
+        );
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, [70, 70, 70, 300]);
+    }
+
+    #[test]
+    fn invalid_moves_are_refused() {
+        assert!(svg(&record("4444444")).is_err());
+    }
+}
@@ -0,0 +1,545 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::i18n::{Msg, tr};
+use crate::json;
+use crate::rules::{DEFAULT_FLIP_EVERY, Rules, Standard, Variant};
+
+pub const BOT: i8 = -1;
+pub const EMPTY: i8 = 0;
+pub const PLAYER: i8 = 1;
+
+// Written in place of the second move when the pie rule is used and the second
+// player takes over the first player's piece.
+pub const SWAP: char = 's';
+// Written for a move that is skipped, as when a handicap gives one side tempo.
+// A game the bot opens starts with the human passing.
+pub const PASS: char = '-';
+
+// The widest board, its last column written `p`.
+pub const MAX_COLS: usize = 16;
+
+// The tallest board, kept to the same size.
+pub const MAX_ROWS: usize = 16;
+
+// How a column is written in transcripts: the first nine as the digits they
+// always were, the ones past them as the letters of their place, `j` to `p`.
+pub fn column_char(col: usize) -> char {
+    if col < 9 {
+        (b'1' + col as u8) as char
+    } else {
+        (b'a' + col as u8) as char
+    }
+}
+
+// A column written as a digit from 1 or a letter from a, in either case.
+// Transcripts may mix the two.
+pub fn parse_column(c: char) -> Option<usize> {
+    match c {
+        '1'..='9' => Some(c as usize - '1' as usize),
+        'a'..='p' => Some(c as usize - 'a' as usize),
+        'A'..='P' => Some(c as usize - 'A' as usize),
+        _ => None,
+    }
+}
+
+// Whether the side to move after `moves` may swap: the only piece on the board
+// was played by the last move.
+pub fn can_swap(moves: &str) -> bool {
+    moves.ends_with(|c: char| parse_column(c).is_some())
+        && moves.chars().filter(|&c| parse_column(c).is_some()).count() == 1
+}
+
+// Who makes the first move of a game against the bot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum First {
+    Human,
+    Bot,
+    // Decided by a coin flip when the game starts.
+    Random,
+}
+
+// What happens when the human runs out of time for a move in blitz games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnTimeout {
+    // A random legal move is played instead.
+    Random,
+    // The game is lost.
+    Forfeit,
+}
+
+// Handicaps the stronger side gives at the start of a game, written into the
+// transcript as pre-placed pieces and skipped moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Handicap {
+    // The human starts with a piece in the center column and moves again.
+    Center,
+    // The bot skips its first move.
+    Tempo,
+    // The bot starts with a piece in the center column.
+    BotCenter,
+}
+
+impl Handicap {
+    pub fn name(self) -> &'static str {
+        match self {
+            Handicap::Center => "center",
+            Handicap::Tempo => "tempo",
+            Handicap::BotCenter => "bot-center",
+        }
+    }
+
+    // The moves placed before the game starts, with the human moving first.
+    pub fn setup(self, cols: usize) -> String {
+        let center = (cols / 2 + 1).to_string();
+        match self {
+            Handicap::Center => format!("{}{}", center, PASS),
+            Handicap::Tempo => String::new(),
+            Handicap::BotCenter => format!("{}{}", PASS, center),
+        }
+    }
+}
+
+// Where a game stands. A won game keeps the (row, col) cells of the winning
+// four, from one end of the line to the other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum GameStatus {
+    InProgress,
+    Won {
+        #[serde(with = "json::piece")]
+        player: i8,
+        #[serde(with = "json::cells")]
+        line: [(usize, usize); 4],
+    },
+    Draw,
+}
+
+// How often each position has come up in a game, a position being the board
+// together with the side to move. Only rules with a repetition limit are
+// counted: where every move adds a piece no position comes up twice.
+#[derive(Clone, Default)]
+pub struct PositionHistory {
+    seen: HashMap<u64, usize>,
+}
+
+impl PositionHistory {
+    // Counts the position reached, returning whether it has now come up as
+    // often as the game's rules allow before calling it a draw.
+    pub fn record(&mut self, game: &ConnectFour, to_move: i8) -> bool {
+        let Some(limit) = game.rules.repetition_limit() else {
+            return false;
+        };
+        let count = self.seen.entry(game.position_key(to_move)).or_default();
+        *count += 1;
+        *count >= limit
+    }
+}
+
+// A board in JSON: its size, the rules it is played under and its rows from
+// the top, written as in `Display`, e.g. "..ox...".
+#[derive(Serialize, Deserialize)]
+struct BoardJson {
+    rows: usize,
+    cols: usize,
+    variant: Variant,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    flip_every: Option<usize>,
+    board: Vec<String>,
+}
+
+impl From<ConnectFour> for BoardJson {
+    fn from(game: ConnectFour) -> Self {
+        Self {
+            rows: game.rows,
+            cols: game.cols,
+            variant: game.rules.variant(),
+            flip_every: game.rules.flip_every(),
+            board: game
+                .board
+                .iter()
+                .map(|row| row.iter().map(|&cell| symbol(cell)).collect())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<BoardJson> for ConnectFour {
+    type Error = String;
+
+    fn try_from(json: BoardJson) -> Result<Self, String> {
+        if json.rows < 4 || json.cols < 4 {
+            return Err(format!(
+                "a {}x{} board is too small for four in a row",
+                json.cols, json.rows
+            ));
+        }
+        if json.board.len() != json.rows {
+            return Err(format!(
+                "expected {} rows, found {}",
+                json.rows,
+                json.board.len()
+            ));
+        }
+        let flip_every = json.flip_every.unwrap_or(DEFAULT_FLIP_EVERY);
+        let mut game =
+            ConnectFour::new(json.rows, json.cols).with_rules(json.variant.rules(flip_every));
+        for (row, text) in json.board.iter().enumerate() {
+            let cells = text
+                .chars()
+                .map(|c| match c {
+                    'o' => Ok(PLAYER),
+                    'x' => Ok(BOT),
+                    '.' => Ok(EMPTY),
+                    _ => Err(tr(Msg::InvalidCell, &[&c, &(row + 1)])),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if cells.len() != json.cols {
+                return Err(format!(
+                    "expected {} cells in row {}, found {}",
+                    json.cols,
+                    row + 1,
+                    cells.len()
+                ));
+            }
+            game.board[row] = cells;
+        }
+        Ok(game)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "BoardJson", try_from = "BoardJson")]
+pub struct ConnectFour {
+    pub rows: usize,
+    pub cols: usize,
+    pub board: Vec<Vec<i8>>,
+    pub rules: Arc<dyn Rules>,
+}
+
+impl ConnectFour {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            board: vec![vec![EMPTY; cols]; rows],
+            rules: Arc::new(Standard),
+        }
+    }
+
+    pub fn with_rules(mut self, rules: Arc<dyn Rules>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Moves are columns as written by `column_char`, e.g. "4453", with `first`
+    // moving first; letters may stand in for digits, as in "ddec".
+    // A swap as the second move hands the first piece over, and `first` moves
+    // again, now as the second player. A pass skips the mover's turn.
+    pub fn from_moves(rows: usize, cols: usize, moves: &str, first: i8) -> Result<Self, String> {
+        Self::new(rows, cols).replay(moves, first)
+    }
+
+    // Plays `moves`, written as for `from_moves`, on this board under its rules.
+    pub fn replay(self, moves: &str, first: i8) -> Result<Self, String> {
+        let cols = self.cols;
+        let mut game = self;
+        let mut piece = first;
+        let mut won = game.check_win(BOT) || game.check_win(PLAYER);
+        for (i, c) in moves.char_indices() {
+            if c == SWAP {
+                if !can_swap(&moves[..i]) {
+                    return Err(format!(
+                        "a swap is only allowed right after the first piece, found at move {}",
+                        i + 1
+                    ));
+                }
+                // The player who swapped now owns the piece; the other moves.
+                game = game.swapped();
+                piece = -piece;
+                continue;
+            }
+            if c == PASS {
+                piece = -piece;
+                continue;
+            }
+            let col = parse_column(c)
+                .filter(|&col| col < cols)
+                .ok_or_else(|| tr(Msg::InvalidColumnAt, &[&c, &(i + 1)]))?;
+            if won {
+                return Err(tr(Msg::MoveAfterWin, &[&(i + 1)]));
+            }
+            won = game.winning_move(col, piece);
+            if !game.drop_piece(col, piece) {
+                return Err(tr(Msg::ColumnFullAt, &[&(col + 1), &(i + 1)]));
+            }
+            piece = -piece;
+        }
+        Ok(game)
+    }
+
+    pub fn get_valid_moves(&self) -> Vec<usize> {
+        (0..self.cols)
+            .filter(|&col| self.landing_row(col).is_some())
+            .collect()
+    }
+
+    // The row a piece played in `col` lands on, or None if the column is full.
+    pub fn landing_row(&self, col: usize) -> Option<usize> {
+        self.rules.landing_row(&self.board, col)
+    }
+
+    // Whether `piece` dropped in `col` would complete four in a row.
+    pub fn winning_move(&self, col: usize, piece: i8) -> bool {
+        self.landing_row(col)
+            .is_some_and(|row| self.wins_at(row, col, piece))
+    }
+
+    // Whether `piece` at (`row`, `col`) makes four in a row, whatever the cell
+    // holds now. Only the lines through the cell are looked at, so this is
+    // the check to make after every move.
+    pub fn wins_at(&self, row: usize, col: usize, piece: i8) -> bool {
+        let owned = |r: isize, c: isize| {
+            r >= 0
+                && c >= 0
+                && (r as usize) < self.rows
+                && (c as usize) < self.cols
+                && self.board[r as usize][c as usize] == piece
+        };
+        let (row, col) = (row as isize, col as isize);
+        [(0, 1), (1, 0), (1, 1), (1, -1)].iter().any(|&(dr, dc)| {
+            let run = |sign: isize| {
+                (1..4)
+                    .take_while(|&i| owned(row + sign * i * dr, col + sign * i * dc))
+                    .count()
+            };
+            run(1) + run(-1) >= 3
+        })
+    }
+
+    // Scans the whole board; after a move, `wins_at` is much cheaper.
+    pub fn check_win(&self, player: i8) -> bool {
+        for row in 0..self.rows {
+            for col in 0..(self.cols - 3) {
+                if (0..4).all(|i| self.board[row][col + i] == player) {
+                    return true;
+                }
+            }
+        }
+        for row in 0..(self.rows - 3) {
+            for col in 0..self.cols {
+                if (0..4).all(|i| self.board[row + i][col] == player) {
+                    return true;
+                }
+            }
+        }
+        for row in 0..(self.rows - 3) {
+            for col in 0..(self.cols - 3) {
+                if (0..4).all(|i| self.board[row + i][col + i] == player) {
+                    return true;
+                }
+                if (0..4).all(|i| self.board[row + 3 - i][col + i] == player) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // The first four of `player`'s pieces in a row, if there is one.
+    pub fn winning_line(&self, player: i8) -> Option<[(usize, usize); 4]> {
+        let owned = |line: [(usize, usize); 4]| {
+            line.iter()
+                .all(|&(row, col)| self.board[row][col] == player)
+                .then_some(line)
+        };
+        for row in 0..self.rows {
+            for col in 0..(self.cols - 3) {
+                if let Some(line) = owned([0, 1, 2, 3].map(|i| (row, col + i))) {
+                    return Some(line);
+                }
+            }
+        }
+        for row in 0..(self.rows - 3) {
+            for col in 0..self.cols {
+                if let Some(line) = owned([0, 1, 2, 3].map(|i| (row + i, col))) {
+                    return Some(line);
+                }
+            }
+        }
+        for row in 0..(self.rows - 3) {
+            for col in 0..(self.cols - 3) {
+                if let Some(line) = owned([0, 1, 2, 3].map(|i| (row + i, col + i))) {
+                    return Some(line);
+                }
+                if let Some(line) = owned([0, 1, 2, 3].map(|i| (row + 3 - i, col + i))) {
+                    return Some(line);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn status(&self) -> GameStatus {
+        for player in [BOT, PLAYER] {
+            if let Some(line) = self.winning_line(player) {
+                return GameStatus::Won { player, line };
+            }
+        }
+        if self.get_valid_moves().is_empty() {
+            GameStatus::Draw
+        } else {
+            GameStatus::InProgress
+        }
+    }
+
+    // A hash of the board and the side to move, the same for equal positions.
+    pub fn position_key(&self, to_move: i8) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.board.hash(&mut hasher);
+        to_move.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // The same position with the two sides' pieces exchanged.
+    pub fn swapped(&self) -> Self {
+        let mut game = self.clone();
+        for cell in game.board.iter_mut().flatten() {
+            *cell = -*cell;
+        }
+        game
+    }
+
+    pub fn drop_piece(&mut self, col: usize, piece: i8) -> bool {
+        match self.landing_row(col) {
+            Some(row) => {
+                self.board[row][col] = piece;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub fn symbol(val: i8) -> &'static str {
+    match val {
+        BOT => "x",
+        EMPTY => ".",
+        PLAYER => "o",
+        _ => " ",
+    }
+}
+
+impl fmt::Display for ConnectFour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.board {
+            for &cell in row {
+                write!(f, " {}", symbol(cell))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Standard gravity, drawn when a position comes up a third time.
+    struct Repeating;
+
+    impl Rules for Repeating {
+        fn landing_row(&self, board: &[Vec<i8>], col: usize) -> Option<usize> {
+            Standard.landing_row(board, col)
+        }
+
+        fn variant(&self) -> Variant {
+            Variant::Standard
+        }
+
+        fn repetition_limit(&self) -> Option<usize> {
+            Some(3)
+        }
+    }
+
+    // Whether the game is drawn each time the same position comes up again.
+    fn draws(game: ConnectFour) -> Vec<bool> {
+        let mut history = PositionHistory::default();
+        let game = game.replay("4", PLAYER).unwrap();
+        (0..5).map(|_| history.record(&game, BOT)).collect()
+    }
+
+    #[test]
+    fn board_json_round_trip() {
+        let game = ConnectFour::from_moves(4, 5, "3345", PLAYER)
+            .unwrap()
+            .with_rules(Variant::FlipGravity.rules(3));
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rows":4,"cols":5,"variant":"flip-gravity","flip_every":3,"board":[".....",".....","..x..","..oox"]}"#
+        );
+        let back: ConnectFour = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.board, game.board);
+        assert_eq!(back.rules.flip_every(), Some(3));
+    }
+
+    #[test]
+    fn board_json_rejects_bad_boards() {
+        for (json, err) in [
+            (
+                r#"{"rows":3,"cols":5,"variant":"standard","board":[]}"#,
+                "too small",
+            ),
+            (
+                r#"{"rows":4,"cols":4,"variant":"standard","board":["...."]}"#,
+                "expected 4 rows, found 1",
+            ),
+            (
+                r#"{"rows":4,"cols":4,"variant":"standard","board":["....","....","...","...."]}"#,
+                "expected 4 cells in row 3, found 3",
+            ),
+            (
+                r#"{"rows":4,"cols":4,"variant":"standard","board":["....","....","..q.","...."]}"#,
+                "invalid cell 'q' in row 3",
+            ),
+        ] {
+            let result = serde_json::from_str::<ConnectFour>(json);
+            assert!(result.err().unwrap().to_string().contains(err), "{}", json);
+        }
+    }
+
+    #[test]
+    fn handicaps_set_up_the_center_column() {
+        assert_eq!(Handicap::Center.setup(7), "4-");
+        assert_eq!(Handicap::BotCenter.setup(6), "-4");
+        assert_eq!(Handicap::Tempo.setup(7), "");
+        // The side given the center holds the only piece on the board.
+        for (handicap, piece) in [(Handicap::Center, PLAYER), (Handicap::BotCenter, BOT)] {
+            let game = ConnectFour::from_moves(6, 7, &handicap.setup(7), PLAYER).unwrap();
+            assert_eq!(game.board[5][3], piece);
+            assert_eq!(
+                game.board.iter().flatten().filter(|&&c| c != EMPTY).count(),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn repetition_draws_only_under_a_limit() {
+        assert_eq!(draws(ConnectFour::new(6, 7)), [false; 5]);
+        assert_eq!(
+            draws(ConnectFour::new(6, 7).with_rules(Arc::new(Repeating))),
+            [false, false, true, true, true]
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{debug, info};
+
+use crate::bot::BotPlayer;
+use crate::game::{self, BOT, ConnectFour, PLAYER, column_char};
+use crate::rules::Rules;
+use crate::sessions::{SEARCHES_PER_SESSION, SearchPermit, SearchRefused, Session, SessionManager};
+use crate::solver::{Position, Solver};
+
+mod proto {
+    tonic::include_proto!("connect_four");
+}
+
+use proto::engine_server::{Engine, EngineServer};
+use proto::{
+    BestMoveRequest, GameState, NewGameRequest, Piece, PlayMoveRequest, SearchInfo, SolveReply,
+    SolveRequest, Status as GameStatus,
+};
+
+pub struct GrpcOptions {
+    pub listen: String,
+    pub rows: usize,
+    pub cols: usize,
+    pub depth: i32,
+    pub hash: usize,
+    pub rules: Arc<dyn Rules>,
+    // Games untouched for this long are dropped.
+    pub idle_timeout: Duration,
+    // Searches and solves running at once across all games.
+    pub searches: usize,
+}
+
+struct Game {
+    game: ConnectFour,
+    moves: String,
+    // The game's own engine; the solver and its table are made on first use.
+    bot: BotPlayer,
+    solver: Option<Solver>,
+}
+
+impl Game {
+    fn is_over(&self) -> bool {
+        self.game.status() != game::GameStatus::InProgress
+    }
+
+    fn to_move(&self) -> i8 {
+        if self.moves.len().is_multiple_of(2) {
+            PLAYER
+        } else {
+            BOT
+        }
+    }
+
+    fn state(&self, id: u64) -> GameState {
+        let status = match self.game.status() {
+            game::GameStatus::Won { player: PLAYER, .. } => GameStatus::OWon,
+            game::GameStatus::Won { .. } => GameStatus::XWon,
+            game::GameStatus::Draw => GameStatus::Draw,
+            game::GameStatus::InProgress => GameStatus::Ongoing,
+        };
+        let to_move = match status {
+            GameStatus::Ongoing if self.to_move() == PLAYER => Piece::O,
+            GameStatus::Ongoing => Piece::X,
+            _ => Piece::None,
+        };
+        GameState {
+            game_id: id,
+            rows: self.game.rows as u32,
+            cols: self.game.cols as u32,
+            moves: self.moves.clone(),
+            to_move: to_move.into(),
+            status: status.into(),
+            legal_columns: self
+                .game
+                .get_valid_moves()
+                .into_iter()
+                .map(|col| col as u32 + 1)
+                .collect(),
+            board: self.game.to_string(),
+        }
+    }
+}
+
+struct Service {
+    make_bot: Arc<dyn Fn(i32) -> BotPlayer + Send + Sync>,
+    options: GrpcOptions,
+    sessions: Arc<SessionManager<Game>>,
+}
+
+impl Service {
+    fn session(&self, id: u64) -> Result<Arc<Session<Game>>, Status> {
+        self.sessions
+            .get(id)
+            .ok_or_else(|| Status::not_found(format!("no game {}", id)))
+    }
+
+    // A session whose game is still going, with a search slot reserved in it.
+    async fn searchable(&self, id: u64) -> Result<(Arc<Session<Game>>, SearchPermit), Status> {
+        let session = self.session(id)?;
+        if session.state.lock().await.is_over() {
+            return Err(Status::failed_precondition(format!("game {} is over", id)));
+        }
+        let permit =
+            self.sessions
+                .search_permit(&session)
+                .await
+                .map_err(|refused| match refused {
+                    SearchRefused::SessionBusy => Status::resource_exhausted(format!(
+                        "game {} already has {} searches running",
+                        id, SEARCHES_PER_SESSION
+                    )),
+                    SearchRefused::Closed => Status::unavailable("the server is shutting down"),
+                })?;
+        Ok((session, permit))
+    }
+}
+
+fn size(requested: u32, default: usize) -> usize {
+    if requested == 0 {
+        default
+    } else {
+        requested as usize
+    }
+}
+
+#[tonic::async_trait]
+impl Engine for Service {
+    async fn new_game(
+        &self,
+        request: Request<NewGameRequest>,
+    ) -> Result<Response<GameState>, Status> {
+        let request = request.into_inner();
+        let rows = size(request.rows, self.options.rows);
+        let cols = size(request.cols, self.options.cols);
+        // Transcripts run out of column letters past the widest board.
+        if !(4..=game::MAX_ROWS).contains(&rows) || !(4..=game::MAX_COLS).contains(&cols) {
+            return Err(Status::invalid_argument(format!(
+                "board must have 4 to 16 rows and 4 to {} columns, got {}x{}",
+                game::MAX_COLS,
+                cols,
+                rows
+            )));
+        }
+        let game = ConnectFour::new(rows, cols)
+            .with_rules(self.options.rules.clone())
+            .replay(&request.moves, PLAYER)
+            .map_err(Status::invalid_argument)?;
+        let game = Game {
+            game,
+            moves: request.moves,
+            bot: (self.make_bot)(self.options.depth),
+            solver: None,
+        };
+        let session = self.sessions.create(game);
+        let state = session.state.lock().await.state(session.id);
+        info!(
+            id = session.id,
+            rows,
+            cols,
+            open = self.sessions.len(),
+            "grpc game started"
+        );
+        Ok(Response::new(state))
+    }
+
+    async fn play_move(
+        &self,
+        request: Request<PlayMoveRequest>,
+    ) -> Result<Response<GameState>, Status> {
+        let request = request.into_inner();
+        let session = self.session(request.game_id)?;
+        // Held throughout, so that concurrent moves in one game are played in turn.
+        let mut game = session.state.lock().await;
+        if game.is_over() {
+            return Err(Status::failed_precondition(format!(
+                "game {} is over",
+                request.game_id
+            )));
+        }
+        let col = (request.column as usize)
+            .checked_sub(1)
+            .filter(|col| game.game.get_valid_moves().contains(col))
+            .ok_or_else(|| {
+                Status::invalid_argument(format!("column {} is not playable", request.column))
+            })?;
+        let piece = game.to_move();
+        game.game.drop_piece(col, piece);
+        game.moves.push(column_char(col));
+        debug!(id = request.game_id, col = col + 1, "grpc move played");
+        Ok(Response::new(game.state(request.game_id)))
+    }
+
+    type BestMoveStream = ReceiverStream<Result<SearchInfo, Status>>;
+
+    async fn best_move(
+        &self,
+        request: Request<BestMoveRequest>,
+    ) -> Result<Response<Self::BestMoveStream>, Status> {
+        let request = request.into_inner();
+        let depth = match request.depth {
+            0 => self.options.depth,
+            depth if depth > 0 => depth,
+            depth => {
+                return Err(Status::invalid_argument(format!(
+                    "depth must not be negative, got {}",
+                    depth
+                )));
+            }
+        };
+        let (session, permit) = self.searchable(request.game_id).await?;
+        let (sender, receiver) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let mut game = session.state.blocking_lock();
+            // The bot searches as BOT, so the other side's positions are mirrored.
+            let position = if game.to_move() == BOT {
+                game.game.clone()
+            } else {
+                game.game.swapped()
+            };
+            for depth in 1..=depth.max(1) {
+                let analysis = game.bot.analyze_at(&position, depth);
+                let info = SearchInfo {
+                    depth: analysis.depth,
+                    score: analysis.score,
+                    column: analysis.best_move.map_or(0, |col| col as u32 + 1),
+                    pv: analysis.pv.iter().map(|&col| col as u32 + 1).collect(),
+                    nodes: analysis.nodes,
+                };
+                // The client has gone away.
+                if sender.blocking_send(Ok(info)).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn solve(&self, request: Request<SolveRequest>) -> Result<Response<SolveReply>, Status> {
+        let request = request.into_inner();
+        let (session, permit) = self.searchable(request.game_id).await?;
+        let hash = self.options.hash;
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let mut game = session.state.blocking_lock();
+            let pos = Position::from_game(&game.game, game.to_move()).ok_or_else(|| {
+                Status::failed_precondition("only positions with standard gravity can be solved")
+            })?;
+            // The table carries over between solves of the same game.
+            let solver = game.solver.get_or_insert_with(|| Solver::new(hash));
+            let before = solver.nodes();
+            let score = solver.solve(&pos);
+            Ok(Response::new(SolveReply {
+                score,
+                nodes: solver.nodes() - before,
+            }))
+        })
+        .await
+        .map_err(|err| Status::internal(err.to_string()))?
+    }
+}
+
+// Serves the engine until the process is stopped. `make_bot` builds a bot
+// searching to the given depth.
+pub fn run(
+    make_bot: Arc<dyn Fn(i32) -> BotPlayer + Send + Sync>,
+    options: GrpcOptions,
+) -> Result<(), Box<dyn Error>> {
+    let addr = options
+        .listen
+        .parse()
+        .map_err(|err| format!("{}: {}", options.listen, err))?;
+    let sessions = Arc::new(SessionManager::new(options.idle_timeout, options.searches));
+    let service = Service {
+        make_bot,
+        options,
+        sessions: sessions.clone(),
+    };
+    eprintln!("Listening on {}.", addr);
+    tokio::runtime::Runtime::new()?.block_on(async {
+        sessions.spawn_reaper();
+        tonic::transport::Server::builder()
+            .add_service(EngineServer::new(service))
+            .serve(addr)
+            .await
+    })?;
+    Ok(())
+}
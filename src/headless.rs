@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::bot::{Analysis, BotPlayer};
+use crate::game::{ConnectFour, GameStatus, SWAP, parse_column, symbol};
+use crate::history::Winner;
+use crate::i18n::{Msg, tr};
+use crate::player::{GameView, Move, Player};
+use crate::review;
+
+// The human's moves as columns written like transcripts, one character each,
+// with `s` for a swap. Whitespace and commas between them are ignored.
+pub fn read_script(path: Option<&Path>, cols: usize) -> Result<ScriptedPlayer, Box<dyn Error>> {
+    let text = match path {
+        Some(path) => {
+            fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?
+        }
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        }
+    };
+    let mut moves = VecDeque::new();
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != ',') {
+        let choice = match c {
+            SWAP => Move::Swap,
+            _ => match parse_column(c) {
+                Some(col) if col < cols => Move::Drop(col),
+                _ => return Err(tr(Msg::InvalidMoveInList, &[&c]).into()),
+            },
+        };
+        moves.push_back(choice);
+    }
+    Ok(ScriptedPlayer {
+        moves,
+        played: 0,
+        error: None,
+    })
+}
+
+// Plays the human's side from a move list. Once the list runs out it quits,
+// leaving the game unfinished.
+pub struct ScriptedPlayer {
+    moves: VecDeque<Move>,
+    played: usize,
+    error: Option<String>,
+}
+
+impl ScriptedPlayer {
+    // Why the player quit, if the list held a move it could not play.
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let Some(choice) = self.moves.pop_front() else {
+            return Move::Quit;
+        };
+        self.played += 1;
+        let legal = match choice {
+            Move::Drop(col) => view.game.get_valid_moves().contains(&col),
+            Move::Swap => view.can_swap,
+            Move::Resign | Move::Quit => true,
+        };
+        if !legal {
+            self.error = Some(tr(Msg::UnplayableScriptMove, &[&self.played, &view.moves]));
+            return Move::Quit;
+        }
+        choice
+    }
+}
+
+// What a headless game prints when it is over, columns 1-based as in json.rs.
+#[derive(Serialize)]
+struct Report<'a> {
+    // "human", "bot", "draw", or "unfinished" when the move list ran out first.
+    result: &'static str,
+    moves: &'a str,
+    status: GameStatus,
+    // The search of the position left by an unfinished game, for the side to move.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_move: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    analysis: Option<Analysis>,
+    review: Vec<review::Entry>,
+}
+
+// Prints the report of a game that reached `game` from the empty `board` by
+// `moves`, reviewing every move with `analyst`.
+pub fn report(
+    analyst: &mut BotPlayer,
+    board: &ConnectFour,
+    game: &ConnectFour,
+    moves: &str,
+    winner: Option<Winner>,
+    to_move: i8,
+) -> Result<(), Box<dyn Error>> {
+    let review = review::entries(analyst, board, moves)?;
+    let analysis = winner.is_none().then(|| analyst.analyze_as(game, to_move));
+    let report = Report {
+        result: winner.map_or("unfinished", Winner::as_str),
+        moves,
+        status: game.status(),
+        to_move: winner.is_none().then(|| symbol(to_move)),
+        analysis,
+        review,
+    };
+    println!("{}", serde_json::to_string(&report)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER;
+    use std::{env, process};
+
+    fn script(text: &str, cols: usize) -> Result<ScriptedPlayer, Box<dyn Error>> {
+        let path = env::temp_dir().join(format!("connect-four-script-{}.txt", process::id()));
+        fs::write(&path, text).unwrap();
+        let player = read_script(Some(&path), cols);
+        fs::remove_file(&path).unwrap();
+        player
+    }
+
+    #[test]
+    fn scripts_ignore_separators() {
+        let player = script("4, s\n1 7", 7).unwrap();
+        assert_eq!(
+            Vec::from(player.moves),
+            [Move::Drop(3), Move::Swap, Move::Drop(0), Move::Drop(6)]
+        );
+        let err = script("48", 7).err().unwrap().to_string();
+        assert_eq!(err, tr(Msg::InvalidMoveInList, &[&'8']));
+    }
+
+    #[test]
+    fn the_player_quits_on_a_move_it_cannot_play() {
+        let game = ConnectFour::from_moves(4, 4, "1111", PLAYER).unwrap();
+        let mut player = script("2s1", 4).unwrap();
+        let view = GameView::new(&game, "1111", PLAYER);
+        assert_eq!(player.choose_move(&view), Move::Drop(1));
+        assert_eq!(player.choose_move(&view), Move::Quit);
+        assert_eq!(
+            player.take_error().unwrap(),
+            tr(Msg::UnplayableScriptMove, &[&2, &"1111"])
+        );
+        // Column 1 is full.
+        assert_eq!(player.choose_move(&view), Move::Quit);
+        assert!(player.take_error().unwrap().starts_with("move 3 "));
+        // The list has run out.
+        assert_eq!(player.choose_move(&view), Move::Quit);
+        assert_eq!(player.take_error(), None);
+    }
+}
@@ -0,0 +1,78 @@
+use crossterm::style::Stylize;
+use std::fmt::Write;
+use std::io::{IsTerminal, stdout};
+
+use crate::engine_options;
+use crate::i18n::{Msg, tr};
+use crate::input;
+
+// Bold in a terminal, underlined with dashes otherwise.
+fn heading(text: &str) -> String {
+    if stdout().is_terminal() {
+        format!("{}\n\n", text.bold())
+    } else {
+        format!("{}\n{}\n\n", text, "-".repeat(text.chars().count()))
+    }
+}
+
+// The commands available while it is the human's turn, in the interface the
+// game is read from: keys in a terminal, lines otherwise. `swap` adds the pie
+// rule's swap while it is on offer.
+pub fn commands(cols: usize, terminal: bool, swap: bool) -> String {
+    let mut entries = if terminal {
+        // `h` names a column on wide boards.
+        let help = if cols < 8 { "?, h" } else { "?" };
+        vec![
+            ("Left/Right, mouse".to_string(), Msg::HelpChoose),
+            ("Enter, Space, Down, click".to_string(), Msg::HelpDrop),
+            (input::column_names(cols, true), Msg::HelpDropDigit),
+            (help.to_string(), Msg::HelpHelp),
+            ("r".to_string(), Msg::HelpRules),
+            (":".to_string(), Msg::HelpSet),
+            ("q, Esc".to_string(), Msg::HelpQuit),
+        ]
+    } else {
+        vec![
+            (input::column_names(cols, false), Msg::HelpDropDigit),
+            ("d1".to_string(), Msg::HelpDropCell),
+            ("help, ?".to_string(), Msg::HelpHelp),
+            ("rules".to_string(), Msg::HelpRules),
+            ("set <option> <value>".to_string(), Msg::HelpSet),
+            ("Ctrl-D".to_string(), Msg::HelpQuit),
+        ]
+    };
+    if swap {
+        entries.insert(entries.len() - 1, ("s".to_string(), Msg::HelpSwap));
+    }
+    let width = entries
+        .iter()
+        .map(|(keys, _)| keys.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = heading(&tr(Msg::HelpTitle, &[]));
+    for (keys, msg) in entries {
+        let _ = writeln!(
+            out,
+            "  {:<width$}  {}",
+            keys,
+            tr(msg, &[&engine_options::names()])
+        );
+    }
+    out
+}
+
+// How the game is played: the general rules, who moves first, where pieces
+// land under the variant, and the options that change the game.
+pub fn rules(rows: usize, cols: usize, first: &str, gravity: &str, options: &[String]) -> String {
+    let mut out = heading(&tr(Msg::RulesTitle, &[]));
+    let _ = writeln!(out, "{} {}", tr(Msg::RulesGoal, &[&cols, &rows]), first);
+    let _ = writeln!(out, "{}\n", gravity);
+    out.push_str(&heading(&tr(Msg::OptionsTitle, &[])));
+    if options.is_empty() {
+        let _ = writeln!(out, "{}", tr(Msg::NoOptions, &[]));
+    }
+    for option in options {
+        let _ = writeln!(out, "  - {}", option);
+    }
+    out
+}
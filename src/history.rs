@@ -0,0 +1,439 @@
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::error::Error;
+use std::fs;
+use std::io::{IsTerminal, stdin, stdout};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::game::{ConnectFour, PLAYER};
+use crate::i18n::{Msg, tr};
+use crate::input::{self, Key};
+use crate::terminal::clear_screen;
+use crate::theme::Theme;
+
+const SCHEMA_VERSION: i32 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Winner {
+    Human,
+    Bot,
+    Draw,
+}
+
+impl Winner {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Winner::Human => "human",
+            Winner::Bot => "bot",
+            Winner::Draw => "draw",
+        }
+    }
+
+    pub fn describe(self) -> String {
+        let msg = match self {
+            Winner::Human => Msg::ResultHuman,
+            Winner::Bot => Msg::ResultBot,
+            Winner::Draw => Msg::ResultDraw,
+        };
+        tr(msg, &[])
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "human" => Some(Winner::Human),
+            "bot" => Some(Winner::Bot),
+            "draw" => Some(Winner::Draw),
+            _ => None,
+        }
+    }
+}
+
+// A finished game as stored in the database. Times are Unix seconds and moves
+// use the position string format, with the human moving first.
+#[derive(Clone)]
+pub struct GameRecord {
+    pub id: i64,
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub rows: usize,
+    pub cols: usize,
+    pub moves: String,
+    pub winner: Winner,
+    pub engine: String,
+    pub depth: i32,
+    pub move_time: Option<f64>,
+    // Name of the handicap the game started with, if any.
+    pub handicap: Option<String>,
+    // Share of the bot's moves played at random.
+    pub blunder_rate: f64,
+    // The match the game was played in, if any.
+    pub match_id: Option<i64>,
+}
+
+#[derive(Default)]
+pub struct Filter {
+    pub winner: Option<Winner>,
+    pub engine: Option<String>,
+    pub depth: Option<i32>,
+    // Board size as (rows, cols).
+    pub board: Option<(usize, usize)>,
+    // Only games finished on or after this date (YYYY-MM-DD, local time).
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+}
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    pub fn open_default() -> Result<Self, Box<dyn Error>> {
+        let dir = config::data_dir().ok_or_else(|| tr(Msg::NoDataDir, &[]))?;
+        fs::create_dir_all(&dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        Self::open(&dir.join("games.sqlite"))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let db = Self { conn };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    fn migrate(&self) -> rusqlite::Result<()> {
+        let version: i32 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < 1 {
+            self.conn.execute_batch(
+                "CREATE TABLE games (
+                    id INTEGER PRIMARY KEY,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER NOT NULL,
+                    rows INTEGER NOT NULL,
+                    cols INTEGER NOT NULL,
+                    moves TEXT NOT NULL,
+                    winner TEXT NOT NULL,
+                    engine TEXT NOT NULL,
+                    depth INTEGER NOT NULL,
+                    move_time REAL
+                );
+                CREATE INDEX games_finished_at ON games (finished_at);",
+            )?;
+        }
+        if version < 2 {
+            self.conn
+                .execute_batch("ALTER TABLE games ADD COLUMN handicap TEXT;")?;
+        }
+        if version < 3 {
+            self.conn.execute_batch(
+                "ALTER TABLE games ADD COLUMN blunder_rate REAL NOT NULL DEFAULT 0;",
+            )?;
+        }
+        if version < 4 {
+            // Matches left unfinished have no winner.
+            self.conn.execute_batch(
+                "CREATE TABLE matches (
+                    id INTEGER PRIMARY KEY,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER NOT NULL,
+                    best_of INTEGER NOT NULL,
+                    winner TEXT
+                );
+                ALTER TABLE games ADD COLUMN match_id INTEGER REFERENCES matches (id);",
+            )?;
+        }
+        self.conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION)
+    }
+
+    // Stores a finished game and returns its id; `record.id` is ignored.
+    pub fn record(&self, record: &GameRecord) -> rusqlite::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO games
+                (started_at, finished_at, rows, cols, moves, winner, engine, depth, move_time,
+                 handicap, blunder_rate, match_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                record.started_at,
+                record.finished_at,
+                record.rows as i64,
+                record.cols as i64,
+                record.moves,
+                record.winner.as_str(),
+                record.engine,
+                record.depth,
+                record.move_time,
+                record.handicap,
+                record.blunder_rate,
+                record.match_id,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    // Stores the games of a match, in the order played, together with the
+    // match itself, all or nothing. Returns the match's id.
+    pub fn record_match(
+        &self,
+        best_of: u32,
+        winner: Option<Winner>,
+        games: &[GameRecord],
+    ) -> rusqlite::Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO matches (started_at, finished_at, best_of, winner)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                games.first().map_or_else(now, |game| game.started_at),
+                games.last().map_or_else(now, |game| game.finished_at),
+                best_of,
+                winner.map(Winner::as_str),
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        for game in games {
+            self.record(&GameRecord {
+                match_id: Some(id),
+                ..game.clone()
+            })?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn get(&self, id: i64) -> rusqlite::Result<Option<GameRecord>> {
+        self.conn
+            .query_row("SELECT * FROM games WHERE id = ?1", [id], Self::from_row)
+            .optional()
+    }
+
+    // Most recent games first.
+    pub fn list(&self, filter: &Filter) -> rusqlite::Result<Vec<GameRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM games
+             WHERE (?1 IS NULL OR winner = ?1)
+               AND (?2 IS NULL OR engine = ?2)
+               AND (?3 IS NULL OR depth = ?3)
+               AND (?4 IS NULL OR finished_at >= CAST(strftime('%s', ?4, 'utc') AS INTEGER))
+               AND (?5 IS NULL OR (rows = ?5 AND cols = ?6))
+             ORDER BY finished_at DESC, id DESC
+             LIMIT ?7",
+        )?;
+        let limit = filter.limit.map_or(-1, |limit| limit as i64);
+        let rows = stmt.query_map(
+            params![
+                filter.winner.map(Winner::as_str),
+                filter.engine,
+                filter.depth,
+                filter.since,
+                filter.board.map(|(rows, _)| rows as i64),
+                filter.board.map(|(_, cols)| cols as i64),
+                limit,
+            ],
+            Self::from_row,
+        )?;
+        rows.collect()
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<GameRecord> {
+        let winner: String = row.get("winner")?;
+        Ok(GameRecord {
+            id: row.get("id")?,
+            started_at: row.get("started_at")?,
+            finished_at: row.get("finished_at")?,
+            rows: row.get::<_, i64>("rows")? as usize,
+            cols: row.get::<_, i64>("cols")? as usize,
+            moves: row.get("moves")?,
+            winner: Winner::parse(&winner).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    tr(Msg::UnknownWinner, &[&winner]).into(),
+                )
+            })?,
+            engine: row.get("engine")?,
+            depth: row.get("depth")?,
+            move_time: row.get("move_time")?,
+            handicap: row.get("handicap")?,
+            blunder_rate: row.get("blunder_rate")?,
+            match_id: row.get("match_id")?,
+        })
+    }
+
+    pub fn format_time(&self, time: i64) -> rusqlite::Result<String> {
+        self.conn.query_row(
+            "SELECT datetime(?1, 'unixepoch', 'localtime')",
+            [time],
+            |row| row.get(0),
+        )
+    }
+}
+
+fn describe_result(game: &GameRecord) -> String {
+    let mut text = game.winner.describe();
+    if let Some(handicap) = &game.handicap {
+        text.push_str(&format!(" ({})", tr(Msg::ResultHandicap, &[handicap])));
+    }
+    if let Some(id) = game.match_id {
+        text.push_str(&format!(" ({})", tr(Msg::ResultMatch, &[&id])));
+    }
+    text
+}
+
+pub fn run_list(db: &Database, filter: &Filter) -> Result<(), Box<dyn Error>> {
+    let games = db.list(filter)?;
+    if games.is_empty() {
+        println!("{}", tr(Msg::NoGames, &[]));
+        return Ok(());
+    }
+    println!(
+        "{:>5}  {:<19}  {:<5}  {:>5}  {:<7}  {:>5}  result",
+        "id", "finished", "board", "moves", "engine", "depth"
+    );
+    for game in &games {
+        println!(
+            "{:>5}  {:<19}  {:<5}  {:>5}  {:<7}  {:>5}  {}",
+            game.id,
+            db.format_time(game.finished_at)?,
+            format!("{}x{}", game.cols, game.rows),
+            game.moves.len(),
+            game.engine,
+            game.depth,
+            describe_result(game)
+        );
+    }
+    Ok(())
+}
+
+// Steps through a recorded game; without a terminal only the requested ply is shown.
+pub fn run_open(
+    db: &Database,
+    id: i64,
+    ply: Option<usize>,
+    theme: &Theme,
+) -> Result<(), Box<dyn Error>> {
+    let record = db.get(id)?.ok_or_else(|| tr(Msg::NoGameWithId, &[&id]))?;
+    let total = record.moves.len();
+    let mut ply = ply.unwrap_or(total).min(total);
+    let interactive = stdin().is_terminal() && stdout().is_terminal();
+
+    loop {
+        let game = ConnectFour::from_moves(record.rows, record.cols, &record.moves[..ply], PLAYER)?;
+        if interactive {
+            clear_screen();
+        }
+        println!("{}", theme.render(&game));
+        println!(
+            "{}",
+            tr(
+                Msg::HistoryGame,
+                &[
+                    &record.id,
+                    &db.format_time(record.started_at)?,
+                    &record.engine,
+                    &record.depth,
+                    &describe_result(&record)
+                ]
+            )
+        );
+        println!(
+            "{}",
+            tr(Msg::HistoryMove, &[&ply, &total, &&record.moves[..ply]])
+        );
+        if !interactive {
+            return Ok(());
+        }
+        println!("{}", tr(Msg::HistoryKeys, &[]));
+        match input::read_key() {
+            Key::Left => ply = ply.saturating_sub(1),
+            Key::Right => ply = (ply + 1).min(total),
+            Key::Home | Key::Up => ply = 0,
+            Key::End | Key::Down => ply = total,
+            Key::Quit => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn migrates_a_first_version_database() {
+        let path = env::temp_dir().join(format!("connect-four-games-{}.sqlite", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE games (
+                    id INTEGER PRIMARY KEY,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER NOT NULL,
+                    rows INTEGER NOT NULL,
+                    cols INTEGER NOT NULL,
+                    moves TEXT NOT NULL,
+                    winner TEXT NOT NULL,
+                    engine TEXT NOT NULL,
+                    depth INTEGER NOT NULL,
+                    move_time REAL
+                );
+                INSERT INTO games VALUES (1, 100, 160, 6, 7, '4455667', 'human', 'minimax', 4, NULL);
+                PRAGMA user_version = 1;",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let version: i32 = db
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        let old = db.get(1).unwrap().unwrap();
+        assert_eq!(old.moves, "4455667");
+        assert!(old.winner == Winner::Human);
+        assert_eq!(old.handicap, None);
+        assert_eq!(old.blunder_rate, 0.0);
+        assert_eq!(old.match_id, None);
+
+        // Newer columns and the matches table take games from then on.
+        let match_id = db
+            .record_match(
+                3,
+                Some(Winner::Bot),
+                &[GameRecord {
+                    handicap: Some("center".to_string()),
+                    blunder_rate: 0.25,
+                    ..old
+                }],
+            )
+            .unwrap();
+        let games = db.list(&Filter::default()).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].match_id, Some(match_id));
+        assert_eq!(games[0].handicap.as_deref(), Some("center"));
+        drop(db);
+
+        // Opening an up to date database changes nothing.
+        assert_eq!(
+            Database::open(&path)
+                .unwrap()
+                .list(&Filter::default())
+                .unwrap()
+                .len(),
+            2
+        );
+        let _ = fs::remove_file(&path);
+    }
+}
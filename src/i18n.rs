@@ -0,0 +1,971 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    En,
+    De,
+}
+
+impl Lang {
+    // From the usual locale variables, e.g. LANG=de_DE.UTF-8; English otherwise.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        if locale.starts_with("de") {
+            Lang::De
+        } else {
+            Lang::En
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+// Chosen once at startup; text asked for before that is English.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::En)
+}
+
+// Keys of the message catalog. Each text has a `{}` for every argument, filled
+// in order by `tr`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    Error,
+    Warning,
+    EnterColumn,
+    EnterColumnTimed,
+    SelectColumn,
+    SecondsLeft,
+    GameOver,
+    HumanWins,
+    MatchGame,
+    MatchScore,
+    MatchNextKey,
+    MatchNextEnter,
+    MatchWon,
+    MatchLost,
+    MatchDrawn,
+    MatchStopped,
+    SetPrompt,
+    SettingChanged,
+    MatchHandicap,
+    BotWins,
+    DrawFull,
+    CellName,
+    Draw,
+    DrawRepetition,
+    BotSwaps,
+    BotSkips,
+    SwapOffer,
+    HumanSwaps,
+    BotExplains,
+    TimeRandomMove,
+    TimeLost,
+    FindingOpening,
+    CouldNotReview,
+    CouldNotSave,
+    CouldNotAdapt,
+    DepthRange,
+    MoveTimePositive,
+    BlitzPositive,
+    RolloutsAtLeastOne,
+    HashAtLeastOne,
+    BoardSize,
+    #[cfg(feature = "nn")]
+    NetworkSize,
+    #[cfg(feature = "nn")]
+    NetworkMissing,
+    HandicapWithSwap,
+    HandicapBotFirst,
+    RandomStartCombined,
+    RandomStartMoves,
+    RandomStartVariant,
+    FlipAtLeastOne,
+    EditorOnlyStandard,
+    CoachWarning,
+    CoachForcedLoss,
+    CoachGivesAway,
+    EditorKeys,
+    EditorCommands,
+    InvalidPosition,
+    FloatingPiece,
+    PieceCounts,
+    BothWin,
+    OWinsOutOfTurn,
+    XWinsOutOfTurn,
+    NoMoveOrder,
+    UnknownCommand,
+    UnknownPiece,
+    ColumnRange,
+    RowRange,
+    And,
+    Plays,
+    PlaysAndWins,
+    BlocksLine,
+    BlocksThreat,
+    DoubleThreat,
+    Threatens,
+    ForcedWin,
+    StubbornDefense,
+    AllowsWinAbove,
+    TakesCenter,
+    BestAtDepth,
+    Horizontal,
+    Vertical,
+    Diagonal,
+    HelpTitle,
+    HelpChoose,
+    HelpDrop,
+    HelpDropDigit,
+    HelpDropCell,
+    HelpHelp,
+    HelpRules,
+    HelpSwap,
+    HelpSet,
+    HelpQuit,
+    PressKey,
+    PressEnter,
+    RulesTitle,
+    RulesGoal,
+    RulesStandard,
+    RulesAntiGravity,
+    RulesFlipGravity,
+    OptionsTitle,
+    NoOptions,
+    RulesSwap,
+    RulesHandicapCenter,
+    RulesHandicapTempo,
+    RulesHandicapBotCenter,
+    RulesBlitzRandom,
+    RulesBlitzForfeit,
+    RulesRandomStart,
+    RulesFirstHuman,
+    RulesFirstBot,
+    RulesFirstRandom,
+    RulesSwapHuman,
+    RulesSwapEither,
+    PuzzlePrompt,
+    BoardTooLarge,
+    BoardTooLargeSearch,
+    LineError,
+    PuzzleLineFormat,
+    PuzzleMoveCount,
+    NoPuzzles,
+    PuzzleSolved,
+    PuzzleGood,
+    PuzzleTooSlowReply,
+    PuzzleTooSlow,
+    PuzzleSolution,
+    PuzzleStreak,
+    PuzzleNext,
+    WrotePuzzles,
+    AlreadyWon,
+    EvalPosition,
+    Evaluation,
+    BestMove,
+    BestMoveNone,
+    Pv,
+    NoGames,
+    RecordByDifficulty,
+    CommonBlunders,
+    NoneFound,
+    RatingTrend,
+    NoEvenGames,
+    GameCount,
+    BlunderMissedWin,
+    BlunderIgnoredThreat,
+    BlunderUnderThreat,
+    BlunderForcedWin,
+    NoGamesReach,
+    ExplorerPosition,
+    StartPosition,
+    ExplorerKeys,
+    NoGameWithId,
+    UnknownWinner,
+    NoDataDir,
+    ResultHuman,
+    ResultBot,
+    ResultDraw,
+    ResultHandicap,
+    ResultMatch,
+    HistoryGame,
+    HistoryMove,
+    HistoryKeys,
+    SprtProgress,
+    SprtEngines,
+    SprtResult,
+    SprtH1,
+    SprtH0,
+    SprtInconclusive,
+    WroteTablebase,
+    WroteBook,
+    WroteFile,
+    ExpectedWinner,
+    ExpectedProbability,
+    InvalidMoveInList,
+    UnplayableScriptMove,
+    InvalidScore,
+    ExpectedOption,
+    UnknownOption,
+    InvalidOption,
+    InvalidCell,
+    InvalidColumnAt,
+    MoveAfterWin,
+    ColumnFullAt,
+    UnknownColor,
+    GameAlreadyOver,
+    OpeningsNeedStandardBoard,
+    NoOpenings,
+    NoLegalMove,
+    NoMoveToReview,
+    EmptyEngineCommand,
+    Evaluated,
+}
+
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Error => "error: {}",
+        Msg::Warning => "warning: {}",
+        Msg::EnterColumn => "Enter a column ({}) or help:",
+        Msg::EnterColumnTimed => "Enter a column ({}), {}s left:",
+        Msg::SelectColumn => {
+            "Select a column with Left/Right and Enter, click it, or press {} (? for help):"
+        }
+        Msg::SecondsLeft => "{}s left",
+        Msg::GameOver => "Game over!",
+        Msg::HumanWins => "You win in {} moves, with the line from {} to {}!",
+        Msg::MatchGame => "Game {} of a best-of-{} match. Score: you {}, bot {}.",
+        Msg::MatchScore => "Match score: you {}, bot {}.",
+        Msg::MatchNextKey => "Press any key for the next game, or q to end the match.",
+        Msg::MatchNextEnter => "Press Enter for the next game, or type q to end the match.",
+        Msg::MatchWon => "You win the match {} to {}!",
+        Msg::MatchLost => "The bot wins the match {} to {}.",
+        Msg::MatchDrawn => "The match is drawn, {} to {}.",
+        Msg::MatchStopped => "Match stopped at {} to {}.",
+        Msg::SetPrompt => "Type an option and its value ({}), e.g. depth 6:",
+        Msg::SettingChanged => "The bot's {} is now {}, from its next move.",
+        Msg::MatchHandicap => {
+            "--match cannot be combined with --handicap, which needs you to move first"
+        }
+        Msg::BotWins => "The bot wins in {} moves, with the line from {} to {}.",
+        Msg::DrawFull => "Draw! The board filled up after {} moves.",
+        Msg::CellName => "column {} row {}",
+        Msg::Draw => "Draw!",
+        Msg::DrawRepetition => "The same position has come up three times.",
+        Msg::BotSwaps => "The bot swaps and takes over your first piece. Your move.",
+        Msg::BotSkips => "The bot skips its first move. Your move again.",
+        Msg::SwapOffer => "Pie rule: press s to swap and take over the bot's piece.",
+        Msg::HumanSwaps => "You take over the bot's first piece. The bot moves.",
+        Msg::BotExplains => "The bot {}.",
+        Msg::TimeRandomMove => "Time's up! A random move was played for you in column {}.",
+        Msg::TimeLost => "Time's up! You lose on time.",
+        Msg::FindingOpening => "Looking for a balanced opening...",
+        Msg::CouldNotReview => "could not review the game: {}",
+        Msg::CouldNotSave => "could not save the game: {}",
+        Msg::CouldNotAdapt => "could not adapt the difficulty: {}",
+        Msg::DepthRange => "depth must be between 0 and {}, got {}",
+        Msg::MoveTimePositive => "move time must be positive, got {}",
+        Msg::BlitzPositive => "blitz time must be positive, got {}",
+        Msg::RolloutsAtLeastOne => "rollouts must be at least 1",
+        Msg::HashAtLeastOne => "hash size must be at least 1 MB",
+        Msg::BoardSize => "board must have 4 to 16 rows and 4 to 16 columns, got {}x{}",
+        #[cfg(feature = "nn")]
+        Msg::NetworkSize => "the network is for a {}x{} board, not {}x{}",
+        #[cfg(feature = "nn")]
+        Msg::NetworkMissing => "--eval nn needs weights from --nn-weights",
+        Msg::HandicapWithSwap => "a handicap cannot be combined with the pie rule",
+        Msg::HandicapBotFirst => "a handicap can only be given when the human moves first",
+        Msg::RandomStartCombined => {
+            "a random start cannot be combined with the pie rule or a handicap"
+        }
+        Msg::RandomStartMoves => "a random start needs 1 to {} moves, got {}",
+        Msg::RandomStartVariant => "a random start is not available with the {} variant",
+        Msg::FlipAtLeastOne => "gravity must flip after at least 1 piece",
+        Msg::EditorOnlyStandard => "the editor only sets up positions with standard gravity",
+        Msg::CoachWarning => {
+            "Coach: column {} {}; column {} is better. Play {} again to insist, or pick another column."
+        }
+        Msg::CoachForcedLoss => "lets the bot force a win",
+        Msg::CoachGivesAway => "gives away much of your advantage",
+        Msg::EditorKeys => {
+            "Arrows move, o/x place, space clears, c empties the board, p plays, a analyzes, q quits."
+        }
+        Msg::EditorCommands => {
+            "Commands: o|x|. <col> <row> sets a cell, clear, play, analyze, quit. Rows count from the bottom."
+        }
+        Msg::InvalidPosition => "Invalid position: {}.",
+        Msg::FloatingPiece => "column {} has a floating piece",
+        Msg::PieceCounts => "o must have as many pieces as x or one more, got {} and {}",
+        Msg::BothWin => "both sides have four in a row",
+        Msg::OWinsOutOfTurn => "o has four in a row, but x moved last",
+        Msg::XWinsOutOfTurn => "x has four in a row, but o moved last",
+        Msg::NoMoveOrder => "no order of moves reaches this position without an earlier win",
+        Msg::UnknownCommand => "unknown command '{}'",
+        Msg::UnknownPiece => "unknown piece '{}'",
+        Msg::ColumnRange => "column must be 1 to {}",
+        Msg::RowRange => "row must be 1 to {}",
+        Msg::And => "and",
+        Msg::Plays => "plays {}",
+        Msg::PlaysAndWins => "plays {} and wins",
+        Msg::BlocksLine => "plays {} to block your {} three",
+        Msg::BlocksThreat => "plays {} to block your winning threat",
+        Msg::DoubleThreat => "plays {} to create a double threat on columns {}",
+        Msg::Threatens => "plays {} to threaten a win in column {}",
+        Msg::ForcedWin => "plays {} and sees a forced win within {} moves",
+        Msg::StubbornDefense => "plays {}, the most stubborn defense it finds",
+        Msg::AllowsWinAbove => "plays {}, though it lets you win in the same column",
+        Msg::TakesCenter => "plays {} to take the center",
+        Msg::BestAtDepth => "plays {} as its best move at depth {}",
+        Msg::Horizontal => "horizontal",
+        Msg::Vertical => "vertical",
+        Msg::Diagonal => "diagonal",
+        Msg::HelpTitle => "Commands",
+        Msg::HelpChoose => "choose a column",
+        Msg::HelpDrop => "drop a piece in the chosen column",
+        Msg::HelpDropDigit => "drop a piece in that column",
+        Msg::HelpDropCell => "drop a piece landing on that cell, rows counted from the bottom",
+        Msg::HelpHelp => "show this help",
+        Msg::HelpRules => "show the rules and the enabled options",
+        Msg::HelpSwap => "swap and take over the bot's first piece (pie rule)",
+        Msg::HelpSet => "change a setting of the bot ({})",
+        Msg::HelpQuit => "quit the game",
+        Msg::PressKey => "Press any key to return to the game.",
+        Msg::PressEnter => "Press Enter to return to the game.",
+        Msg::RulesTitle => "Rules",
+        Msg::RulesGoal => {
+            "Players take turns dropping a piece into one of the {} columns of a board {} rows high. The first to line up four pieces horizontally, vertically or diagonally wins; when the board fills up first, the game is drawn."
+        }
+        Msg::RulesStandard => "Pieces fall to the lowest empty cell of their column.",
+        Msg::RulesAntiGravity => {
+            "Anti-gravity: pieces rise to the highest empty cell of their column."
+        }
+        Msg::RulesFlipGravity => {
+            "Flipping gravity: the first {} pieces fall, the next {} rise, and so on."
+        }
+        Msg::OptionsTitle => "Enabled options",
+        Msg::NoOptions => "None.",
+        Msg::RulesSwap => {
+            "Pie rule: after your first move the bot may swap and take over your piece."
+        }
+        Msg::RulesHandicapCenter => {
+            "Handicap: you start with a piece in the center column and move again."
+        }
+        Msg::RulesHandicapTempo => "Handicap: the bot skips its first move.",
+        Msg::RulesHandicapBotCenter => {
+            "Handicap: the bot starts with a piece in the center column."
+        }
+        Msg::RulesBlitzRandom => {
+            "Blitz: {} s per move; when time runs out, a random move is played for you."
+        }
+        Msg::RulesBlitzForfeit => "Blitz: {} s per move; when time runs out, you lose.",
+        Msg::RulesRandomStart => {
+            "Random start: the game begins from a balanced opening of {} moves."
+        }
+        Msg::RulesFirstHuman => "You play {} and move first.",
+        Msg::RulesFirstBot => "You play {}; the bot moves first.",
+        Msg::RulesFirstRandom => "You play {}; a coin flip decides who moves first.",
+        Msg::RulesSwapHuman => {
+            "Pie rule: after the bot's first move you may swap and take over its piece."
+        }
+        Msg::RulesSwapEither => {
+            "Pie rule: after the first move the other side may swap and take over that piece."
+        }
+        Msg::PuzzlePrompt => "Find a forced win in {} (you play {}).",
+        Msg::BoardTooLarge => "board too large for the solver",
+        Msg::BoardTooLargeSearch => "board too large for the solver, use --search",
+        Msg::LineError => "line {}: {}",
+        Msg::PuzzleLineFormat => "line {}: expected `<moves> <n>`",
+        Msg::PuzzleMoveCount => "line {}: invalid move count '{}'",
+        Msg::NoPuzzles => "no puzzles to play",
+        Msg::PuzzleSolved => "Solved!",
+        Msg::PuzzleGood => "Good. The bot answers in column {}. Win in {} more.",
+        Msg::PuzzleTooSlowReply => "Column {} does not win in time: the bot answers in column {}.",
+        Msg::PuzzleTooSlow => "Column {} does not win in time.",
+        Msg::PuzzleSolution => "Column {} was the winning move.",
+        Msg::PuzzleStreak => "Streak: {} (best {}), solved {} of {}.",
+        Msg::PuzzleNext => "Press any key for the next puzzle, or q to quit.",
+        Msg::WrotePuzzles => "Wrote {} puzzles to {} ({} games).",
+        Msg::AlreadyWon => "position '{}' is already won",
+        Msg::EvalPosition => "Position: {} ({} to move)",
+        Msg::Evaluation => "Evaluation: {} at depth {} ({} nodes, hash {}% full)",
+        Msg::BestMove => "Best move: {}",
+        Msg::BestMoveNone => "Best move: none (board is full)",
+        Msg::Pv => "PV: {}",
+        Msg::NoGames => "No games recorded.",
+        Msg::RecordByDifficulty => "Record by difficulty:",
+        Msg::CommonBlunders => "Most common losing blunders:",
+        Msg::NoneFound => "none found",
+        Msg::RatingTrend => "Rating trend (Elo, starting at {}):",
+        Msg::NoEvenGames => "no games without a handicap",
+        Msg::GameCount => "{} games",
+        Msg::BlunderMissedWin => "missed a winning move",
+        Msg::BlunderIgnoredThreat => "did not block an immediate threat",
+        Msg::BlunderUnderThreat => "played under an opponent threat",
+        Msg::BlunderForcedWin => "allowed a forced win",
+        Msg::NoGamesReach => "No recorded games reach this position.",
+        Msg::ExplorerPosition => "Position: {} ({} to move, {} games recorded)",
+        Msg::StartPosition => "start",
+        Msg::ExplorerKeys => "Pick a column to follow it, Backspace to go back, q to quit.",
+        Msg::NoGameWithId => "no game with id {}",
+        Msg::UnknownWinner => "unknown winner '{}'",
+        Msg::NoDataDir => "cannot determine the data directory",
+        Msg::ResultHuman => "human won",
+        Msg::ResultBot => "bot won",
+        Msg::ResultDraw => "draw",
+        Msg::ResultHandicap => "{} handicap",
+        Msg::ResultMatch => "match {}",
+        Msg::HistoryGame => "Game {} played {} against {} at depth {}: {}.",
+        Msg::HistoryMove => "Move {} of {}: {}",
+        Msg::HistoryKeys => "Left/Right to step, Home/End to jump, q to quit.",
+        Msg::SprtProgress => "games {}: +{} ={} -{}, LLR {} [{}, {}]",
+        Msg::SprtEngines => "{} vs {}",
+        Msg::SprtResult => "Games: {} (+{} ={} -{}), Elo difference {}",
+        Msg::SprtH1 => "H1 accepted: the first engine is stronger by at least {} Elo (LLR {}).",
+        Msg::SprtH0 => "H0 accepted: the first engine is not stronger by {} Elo (LLR {}).",
+        Msg::SprtInconclusive => "Inconclusive after {} games (LLR {}).",
+        Msg::WroteTablebase => "Wrote {} positions with at least {} pieces to {} in {}s.",
+        Msg::WroteBook => "Wrote {} positions up to {} moves to {} in {}s.",
+        Msg::WroteFile => "Wrote {}.",
+        Msg::ExpectedWinner => "expected human, bot or draw, got '{}'",
+        Msg::ExpectedProbability => "expected a probability between 0 and 1, got '{}'",
+        Msg::InvalidMoveInList => "invalid move '{}' in the move list",
+        Msg::UnplayableScriptMove => "move {} of the list cannot be played after '{}'",
+        Msg::InvalidScore => "line {}: invalid score '{}'",
+        Msg::ExpectedOption => "expected an option and its value; options: {}",
+        Msg::UnknownOption => "unknown option '{}'; options: {}",
+        Msg::InvalidOption => "invalid {} '{}', expected {}",
+        Msg::InvalidCell => "invalid cell '{}' in row {}",
+        Msg::InvalidColumnAt => "invalid column '{}' at move {}",
+        Msg::MoveAfterWin => "move {} is played after the game is won",
+        Msg::ColumnFullAt => "column {} is full at move {}",
+        Msg::UnknownColor => "unknown color '{}'",
+        Msg::GameAlreadyOver => "line {}: the game is already over",
+        Msg::OpeningsNeedStandardBoard => {
+            "the built-in openings are for the 7x6 board; pass --openings"
+        }
+        Msg::NoOpenings => "no openings to play",
+        Msg::NoLegalMove => "the bot found no legal move",
+        Msg::NoMoveToReview => "no move to review",
+        Msg::EmptyEngineCommand => "empty engine command",
+        Msg::Evaluated => "Evaluated {} positions in {}s with {} mismatches.",
+    }
+}
+
+fn german(msg: Msg) -> &'static str {
+    match msg {
+        Msg::Error => "Fehler: {}",
+        Msg::Warning => "Warnung: {}",
+        Msg::EnterColumn => "Spalte eingeben ({}) oder help:",
+        Msg::EnterColumnTimed => "Spalte eingeben ({}), noch {} s:",
+        Msg::SelectColumn => {
+            "Spalte mit Links/Rechts und Enter wählen, anklicken oder {} drücken (? für Hilfe):"
+        }
+        Msg::SecondsLeft => "noch {} s",
+        Msg::GameOver => "Spiel vorbei!",
+        Msg::HumanWins => "Du gewinnst in {} Zügen, mit der Reihe von {} bis {}!",
+        Msg::MatchGame => "Partie {} eines Matches über {} Partien. Stand: du {}, Bot {}.",
+        Msg::MatchScore => "Matchstand: du {}, Bot {}.",
+        Msg::MatchNextKey => "Beliebige Taste für die nächste Partie, q beendet das Match.",
+        Msg::MatchNextEnter => "Enter für die nächste Partie, q beendet das Match.",
+        Msg::MatchWon => "Du gewinnst das Match {} zu {}!",
+        Msg::MatchLost => "Der Bot gewinnt das Match {} zu {}.",
+        Msg::MatchDrawn => "Das Match endet unentschieden, {} zu {}.",
+        Msg::MatchStopped => "Match beim Stand von {} zu {} abgebrochen.",
+        Msg::SetPrompt => "Option und Wert eingeben ({}), z. B. depth 6:",
+        Msg::SettingChanged => "Die Einstellung {} des Bots ist ab seinem nächsten Zug {}.",
+        Msg::MatchHandicap => {
+            "--match lässt sich nicht mit --handicap verbinden, bei dem du anziehen musst"
+        }
+        Msg::BotWins => "Der Bot gewinnt in {} Zügen, mit der Reihe von {} bis {}.",
+        Msg::DrawFull => "Unentschieden! Das Brett ist nach {} Zügen voll.",
+        Msg::CellName => "Spalte {} Zeile {}",
+        Msg::Draw => "Unentschieden!",
+        Msg::DrawRepetition => "Dieselbe Stellung ist zum dritten Mal entstanden.",
+        Msg::BotSwaps => "Der Bot tauscht und übernimmt deinen ersten Stein. Du bist am Zug.",
+        Msg::BotSkips => "Der Bot setzt seinen ersten Zug aus. Du bist wieder am Zug.",
+        Msg::SwapOffer => "Tauschregel: s drücken, um den Stein des Bots zu übernehmen.",
+        Msg::HumanSwaps => "Du übernimmst den ersten Stein des Bots. Der Bot ist am Zug.",
+        Msg::BotExplains => "Der Bot {}.",
+        Msg::TimeRandomMove => "Zeit abgelaufen! Für dich wurde zufällig in Spalte {} gespielt.",
+        Msg::TimeLost => "Zeit abgelaufen! Du verlierst auf Zeit.",
+        Msg::FindingOpening => "Suche eine ausgeglichene Eröffnung...",
+        Msg::CouldNotReview => "die Partie konnte nicht ausgewertet werden: {}",
+        Msg::CouldNotSave => "die Partie konnte nicht gespeichert werden: {}",
+        Msg::CouldNotAdapt => "die Spielstärke konnte nicht angepasst werden: {}",
+        Msg::DepthRange => "die Suchtiefe muss zwischen 0 und {} liegen, angegeben: {}",
+        Msg::MoveTimePositive => "die Zugzeit muss positiv sein, angegeben: {}",
+        Msg::BlitzPositive => "die Blitz-Zeit muss positiv sein, angegeben: {}",
+        Msg::RolloutsAtLeastOne => "es ist mindestens 1 Playout nötig",
+        Msg::HashAtLeastOne => "die Hashtabelle muss mindestens 1 MB groß sein",
+        Msg::BoardSize => {
+            "das Brett braucht 4 bis 16 Zeilen und 4 bis 16 Spalten, angegeben: {}x{}"
+        }
+        #[cfg(feature = "nn")]
+        Msg::NetworkSize => "das Netz ist für ein {}x{}-Brett, nicht {}x{}",
+        #[cfg(feature = "nn")]
+        Msg::NetworkMissing => "--eval nn braucht Gewichte über --nn-weights",
+        Msg::HandicapWithSwap => "eine Vorgabe lässt sich nicht mit der Tauschregel kombinieren",
+        Msg::HandicapBotFirst => "eine Vorgabe ist nur möglich, wenn der Mensch zuerst zieht",
+        Msg::RandomStartCombined => {
+            "ein zufälliger Start lässt sich nicht mit der Tauschregel oder einer Vorgabe kombinieren"
+        }
+        Msg::RandomStartMoves => "ein zufälliger Start braucht 1 bis {} Züge, angegeben: {}",
+        Msg::RandomStartVariant => "ein zufälliger Start ist mit der Variante {} nicht möglich",
+        Msg::FlipAtLeastOne => "die Schwerkraft muss frühestens nach 1 Stein wechseln",
+        Msg::EditorOnlyStandard => "der Editor kennt nur Stellungen mit normaler Schwerkraft",
+        Msg::CoachWarning => {
+            "Trainer: Spalte {} {}; Spalte {} ist besser. Spiele {} noch einmal, um dabei zu bleiben, oder wähle eine andere Spalte."
+        }
+        Msg::CoachForcedLoss => "lässt den Bot einen Gewinn erzwingen",
+        Msg::CoachGivesAway => "verschenkt einen Großteil deines Vorteils",
+        Msg::EditorKeys => {
+            "Pfeiltasten bewegen, o/x setzen, Leertaste leert, c leert das Brett, p spielt, a analysiert, q beendet."
+        }
+        Msg::EditorCommands => {
+            "Befehle: o|x|. <Spalte> <Zeile> setzt ein Feld, clear, play, analyze, quit. Zeilen zählen von unten."
+        }
+        Msg::InvalidPosition => "Ungültige Stellung: {}.",
+        Msg::FloatingPiece => "in Spalte {} schwebt ein Stein",
+        Msg::PieceCounts => "o braucht so viele Steine wie x oder einen mehr, gezählt: {} und {}",
+        Msg::BothWin => "beide Seiten haben vier in einer Reihe",
+        Msg::OWinsOutOfTurn => "o hat vier in einer Reihe, aber x hat zuletzt gezogen",
+        Msg::XWinsOutOfTurn => "x hat vier in einer Reihe, aber o hat zuletzt gezogen",
+        Msg::NoMoveOrder => "keine Zugfolge erreicht diese Stellung ohne einen früheren Gewinn",
+        Msg::UnknownCommand => "unbekannter Befehl '{}'",
+        Msg::UnknownPiece => "unbekannter Stein '{}'",
+        Msg::ColumnRange => "die Spalte muss zwischen 1 und {} liegen",
+        Msg::RowRange => "die Zeile muss zwischen 1 und {} liegen",
+        Msg::And => "und",
+        Msg::Plays => "spielt {}",
+        Msg::PlaysAndWins => "spielt {} und gewinnt",
+        Msg::BlocksLine => "spielt {} und blockiert deine {} Dreierreihe",
+        Msg::BlocksThreat => "spielt {} und blockiert deine Gewinndrohung",
+        Msg::DoubleThreat => "spielt {} und droht doppelt, in den Spalten {}",
+        Msg::Threatens => "spielt {} und droht, in Spalte {} zu gewinnen",
+        Msg::ForcedWin => "spielt {} und sieht einen erzwungenen Gewinn in höchstens {} Zügen",
+        Msg::StubbornDefense => "spielt {}, die zäheste Verteidigung, die er findet",
+        Msg::AllowsWinAbove => "spielt {}, obwohl du dann in derselben Spalte gewinnst",
+        Msg::TakesCenter => "spielt {} und besetzt die Mitte",
+        Msg::BestAtDepth => "spielt {} als besten Zug bei Tiefe {}",
+        Msg::Horizontal => "waagerechte",
+        Msg::Vertical => "senkrechte",
+        Msg::Diagonal => "diagonale",
+        Msg::HelpTitle => "Befehle",
+        Msg::HelpChoose => "Spalte auswählen",
+        Msg::HelpDrop => "Stein in die gewählte Spalte werfen",
+        Msg::HelpDropDigit => "Stein in diese Spalte werfen",
+        Msg::HelpDropCell => "Stein werfen, der auf diesem Feld landet, Zeilen von unten gezählt",
+        Msg::HelpHelp => "diese Hilfe zeigen",
+        Msg::HelpRules => "Regeln und aktive Optionen zeigen",
+        Msg::HelpSwap => "tauschen und den ersten Stein des Bots übernehmen (Tauschregel)",
+        Msg::HelpSet => "eine Einstellung des Bots ändern ({})",
+        Msg::HelpQuit => "Partie beenden",
+        Msg::PressKey => "Beliebige Taste drücken, um zur Partie zurückzukehren.",
+        Msg::PressEnter => "Enter drücken, um zur Partie zurückzukehren.",
+        Msg::RulesTitle => "Regeln",
+        Msg::RulesGoal => {
+            "Abwechselnd wirft jede Seite einen Stein in eine der {} Spalten eines {} Zeilen hohen Bretts. Wer zuerst vier Steine waagerecht, senkrecht oder diagonal in eine Reihe bringt, gewinnt; ist das Brett vorher voll, endet die Partie unentschieden."
+        }
+        Msg::RulesStandard => "Steine fallen auf das unterste freie Feld ihrer Spalte.",
+        Msg::RulesAntiGravity => {
+            "Antigravitation: Steine steigen auf das oberste freie Feld ihrer Spalte."
+        }
+        Msg::RulesFlipGravity => {
+            "Wechselnde Schwerkraft: die ersten {} Steine fallen, die nächsten {} steigen, und so weiter."
+        }
+        Msg::OptionsTitle => "Aktive Optionen",
+        Msg::NoOptions => "Keine.",
+        Msg::RulesSwap => {
+            "Tauschregel: nach deinem ersten Zug darf der Bot tauschen und deinen Stein übernehmen."
+        }
+        Msg::RulesHandicapCenter => {
+            "Vorgabe: du beginnst mit einem Stein in der mittleren Spalte und ziehst noch einmal."
+        }
+        Msg::RulesHandicapTempo => "Vorgabe: der Bot setzt seinen ersten Zug aus.",
+        Msg::RulesHandicapBotCenter => {
+            "Vorgabe: der Bot beginnt mit einem Stein in der mittleren Spalte."
+        }
+        Msg::RulesBlitzRandom => {
+            "Blitz: {} s pro Zug; läuft die Zeit ab, wird für dich zufällig gezogen."
+        }
+        Msg::RulesBlitzForfeit => "Blitz: {} s pro Zug; läuft die Zeit ab, verlierst du.",
+        Msg::RulesRandomStart => {
+            "Zufälliger Start: die Partie beginnt mit einer ausgeglichenen Eröffnung aus {} Zügen."
+        }
+        Msg::RulesFirstHuman => "Du spielst {} und ziehst zuerst.",
+        Msg::RulesFirstBot => "Du spielst {}; der Bot zieht zuerst.",
+        Msg::RulesFirstRandom => "Du spielst {}; ein Münzwurf entscheidet, wer zuerst zieht.",
+        Msg::RulesSwapHuman => {
+            "Tauschregel: nach dem ersten Zug des Bots darfst du tauschen und seinen Stein übernehmen."
+        }
+        Msg::RulesSwapEither => {
+            "Tauschregel: nach dem ersten Zug darf die andere Seite tauschen und diesen Stein übernehmen."
+        }
+        Msg::PuzzlePrompt => "Finde einen erzwungenen Gewinn in {} (du spielst {}).",
+        Msg::BoardTooLarge => "das Brett ist zu groß für den Löser",
+        Msg::BoardTooLargeSearch => "das Brett ist zu groß für den Löser, --search verwenden",
+        Msg::LineError => "Zeile {}: {}",
+        Msg::PuzzleLineFormat => "Zeile {}: erwartet `<Züge> <n>`",
+        Msg::PuzzleMoveCount => "Zeile {}: ungültige Zugzahl '{}'",
+        Msg::NoPuzzles => "keine Rätsel zum Spielen",
+        Msg::PuzzleSolved => "Gelöst!",
+        Msg::PuzzleGood => "Gut. Der Bot antwortet in Spalte {}. Gewinn in {} weiteren.",
+        Msg::PuzzleTooSlowReply => {
+            "Spalte {} gewinnt nicht rechtzeitig: der Bot antwortet in Spalte {}."
+        }
+        Msg::PuzzleTooSlow => "Spalte {} gewinnt nicht rechtzeitig.",
+        Msg::PuzzleSolution => "Spalte {} war der Gewinnzug.",
+        Msg::PuzzleStreak => "Serie: {} (beste {}), {} von {} gelöst.",
+        Msg::PuzzleNext => "Beliebige Taste für das nächste Rätsel, q beendet.",
+        Msg::WrotePuzzles => "{} Rätsel nach {} geschrieben ({} Partien).",
+        Msg::AlreadyWon => "die Stellung '{}' ist schon gewonnen",
+        Msg::EvalPosition => "Stellung: {} ({} am Zug)",
+        Msg::Evaluation => "Bewertung: {} bei Tiefe {} ({} Knoten, Hashtabelle zu {} % voll)",
+        Msg::BestMove => "Bester Zug: {}",
+        Msg::BestMoveNone => "Bester Zug: keiner (das Brett ist voll)",
+        Msg::Pv => "Hauptvariante: {}",
+        Msg::NoGames => "Keine Partien aufgezeichnet.",
+        Msg::RecordByDifficulty => "Bilanz nach Schwierigkeit:",
+        Msg::CommonBlunders => "Häufigste entscheidende Fehler:",
+        Msg::NoneFound => "keine gefunden",
+        Msg::RatingTrend => "Wertungsverlauf (Elo, Start bei {}):",
+        Msg::NoEvenGames => "keine Partien ohne Vorgabe",
+        Msg::GameCount => "{} Partien",
+        Msg::BlunderMissedWin => "hat einen Gewinnzug verpasst",
+        Msg::BlunderIgnoredThreat => "hat eine unmittelbare Drohung nicht blockiert",
+        Msg::BlunderUnderThreat => "hat unter eine Drohung des Gegners gespielt",
+        Msg::BlunderForcedWin => "hat einen erzwungenen Gewinn zugelassen",
+        Msg::NoGamesReach => "Keine aufgezeichnete Partie erreicht diese Stellung.",
+        Msg::ExplorerPosition => "Stellung: {} ({} am Zug, {} Partien aufgezeichnet)",
+        Msg::StartPosition => "Anfang",
+        Msg::ExplorerKeys => "Spalte wählen, um ihr zu folgen, Rücktaste geht zurück, q beendet.",
+        Msg::NoGameWithId => "keine Partie mit der Nummer {}",
+        Msg::UnknownWinner => "unbekannter Sieger '{}'",
+        Msg::NoDataDir => "das Datenverzeichnis ist nicht zu ermitteln",
+        Msg::ResultHuman => "Mensch gewann",
+        Msg::ResultBot => "Bot gewann",
+        Msg::ResultDraw => "unentschieden",
+        Msg::ResultHandicap => "Vorgabe {}",
+        Msg::ResultMatch => "Match {}",
+        Msg::HistoryGame => "Partie {} vom {} gegen {} bei Tiefe {}: {}.",
+        Msg::HistoryMove => "Zug {} von {}: {}",
+        Msg::HistoryKeys => "Links/Rechts blättert, Pos1/Ende springt, q beendet.",
+        Msg::SprtProgress => "Partien {}: +{} ={} -{}, LLR {} [{}, {}]",
+        Msg::SprtEngines => "{} gegen {}",
+        Msg::SprtResult => "Partien: {} (+{} ={} -{}), Elo-Differenz {}",
+        Msg::SprtH1 => "H1 angenommen: die erste Engine ist um mindestens {} Elo stärker (LLR {}).",
+        Msg::SprtH0 => "H0 angenommen: die erste Engine ist nicht um {} Elo stärker (LLR {}).",
+        Msg::SprtInconclusive => "Nach {} Partien keine Entscheidung (LLR {}).",
+        Msg::WroteTablebase => {
+            "{} Stellungen mit mindestens {} Steinen nach {} geschrieben, in {} s."
+        }
+        Msg::WroteBook => "{} Stellungen bis zu {} Zügen nach {} geschrieben, in {} s.",
+        Msg::WroteFile => "{} geschrieben.",
+        Msg::ExpectedWinner => "erwartet human, bot oder draw, angegeben: '{}'",
+        Msg::ExpectedProbability => {
+            "erwartet eine Wahrscheinlichkeit zwischen 0 und 1, angegeben: '{}'"
+        }
+        Msg::InvalidMoveInList => "ungültiger Zug '{}' in der Zugliste",
+        Msg::UnplayableScriptMove => "Zug {} der Liste kann nach '{}' nicht gespielt werden",
+        Msg::InvalidScore => "Zeile {}: ungültige Bewertung '{}'",
+        Msg::ExpectedOption => "erwartet eine Option und ihren Wert; Optionen: {}",
+        Msg::UnknownOption => "unbekannte Option '{}'; Optionen: {}",
+        Msg::InvalidOption => "ungültiger Wert für {}: '{}', erwartet {}",
+        Msg::InvalidCell => "ungültiges Feld '{}' in Zeile {}",
+        Msg::InvalidColumnAt => "ungültige Spalte '{}' bei Zug {}",
+        Msg::MoveAfterWin => "Zug {} folgt auf den Gewinn der Partie",
+        Msg::ColumnFullAt => "Spalte {} ist bei Zug {} voll",
+        Msg::UnknownColor => "unbekannte Farbe '{}'",
+        Msg::GameAlreadyOver => "Zeile {}: die Partie ist schon vorbei",
+        Msg::OpeningsNeedStandardBoard => {
+            "die eingebauten Eröffnungen sind für das 7x6-Brett; --openings angeben"
+        }
+        Msg::NoOpenings => "keine Eröffnungen zum Spielen",
+        Msg::NoLegalMove => "der Bot hat keinen legalen Zug gefunden",
+        Msg::NoMoveToReview => "kein Zug zum Auswerten",
+        Msg::EmptyEngineCommand => "leerer Engine-Befehl",
+        Msg::Evaluated => "{} Stellungen bewertet, in {} s, mit {} Abweichungen.",
+    }
+}
+
+// The message in the chosen language with its placeholders filled in.
+pub fn tr(msg: Msg, args: &[&dyn Display]) -> String {
+    let text = match lang() {
+        Lang::En => english(msg),
+        Lang::De => german(msg),
+    };
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut pieces = text.split("{}").peekable();
+    while let Some(piece) = pieces.next() {
+        out.push_str(piece);
+        if pieces.peek().is_some()
+            && let Some(arg) = args.next()
+        {
+            out.push_str(&arg.to_string());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Vec<(&'static str, Msg)> {
+        #[allow(unused_mut)]
+        let mut keys = vec![
+            ("Error", Msg::Error),
+            ("Warning", Msg::Warning),
+            ("EnterColumn", Msg::EnterColumn),
+            ("EnterColumnTimed", Msg::EnterColumnTimed),
+            ("SelectColumn", Msg::SelectColumn),
+            ("SecondsLeft", Msg::SecondsLeft),
+            ("GameOver", Msg::GameOver),
+            ("HumanWins", Msg::HumanWins),
+            ("MatchGame", Msg::MatchGame),
+            ("MatchScore", Msg::MatchScore),
+            ("MatchNextKey", Msg::MatchNextKey),
+            ("MatchNextEnter", Msg::MatchNextEnter),
+            ("MatchWon", Msg::MatchWon),
+            ("MatchLost", Msg::MatchLost),
+            ("MatchDrawn", Msg::MatchDrawn),
+            ("MatchStopped", Msg::MatchStopped),
+            ("SetPrompt", Msg::SetPrompt),
+            ("SettingChanged", Msg::SettingChanged),
+            ("MatchHandicap", Msg::MatchHandicap),
+            ("BotWins", Msg::BotWins),
+            ("DrawFull", Msg::DrawFull),
+            ("CellName", Msg::CellName),
+            ("Draw", Msg::Draw),
+            ("DrawRepetition", Msg::DrawRepetition),
+            ("BotSwaps", Msg::BotSwaps),
+            ("BotSkips", Msg::BotSkips),
+            ("SwapOffer", Msg::SwapOffer),
+            ("HumanSwaps", Msg::HumanSwaps),
+            ("BotExplains", Msg::BotExplains),
+            ("TimeRandomMove", Msg::TimeRandomMove),
+            ("TimeLost", Msg::TimeLost),
+            ("FindingOpening", Msg::FindingOpening),
+            ("CouldNotReview", Msg::CouldNotReview),
+            ("CouldNotSave", Msg::CouldNotSave),
+            ("CouldNotAdapt", Msg::CouldNotAdapt),
+            ("DepthRange", Msg::DepthRange),
+            ("MoveTimePositive", Msg::MoveTimePositive),
+            ("BlitzPositive", Msg::BlitzPositive),
+            ("RolloutsAtLeastOne", Msg::RolloutsAtLeastOne),
+            ("HashAtLeastOne", Msg::HashAtLeastOne),
+            ("BoardSize", Msg::BoardSize),
+            ("HandicapWithSwap", Msg::HandicapWithSwap),
+            ("HandicapBotFirst", Msg::HandicapBotFirst),
+            ("RandomStartCombined", Msg::RandomStartCombined),
+            ("RandomStartMoves", Msg::RandomStartMoves),
+            ("RandomStartVariant", Msg::RandomStartVariant),
+            ("FlipAtLeastOne", Msg::FlipAtLeastOne),
+            ("EditorOnlyStandard", Msg::EditorOnlyStandard),
+            ("CoachWarning", Msg::CoachWarning),
+            ("CoachForcedLoss", Msg::CoachForcedLoss),
+            ("CoachGivesAway", Msg::CoachGivesAway),
+            ("EditorKeys", Msg::EditorKeys),
+            ("EditorCommands", Msg::EditorCommands),
+            ("InvalidPosition", Msg::InvalidPosition),
+            ("FloatingPiece", Msg::FloatingPiece),
+            ("PieceCounts", Msg::PieceCounts),
+            ("BothWin", Msg::BothWin),
+            ("OWinsOutOfTurn", Msg::OWinsOutOfTurn),
+            ("XWinsOutOfTurn", Msg::XWinsOutOfTurn),
+            ("NoMoveOrder", Msg::NoMoveOrder),
+            ("UnknownCommand", Msg::UnknownCommand),
+            ("UnknownPiece", Msg::UnknownPiece),
+            ("ColumnRange", Msg::ColumnRange),
+            ("RowRange", Msg::RowRange),
+            ("And", Msg::And),
+            ("Plays", Msg::Plays),
+            ("PlaysAndWins", Msg::PlaysAndWins),
+            ("BlocksLine", Msg::BlocksLine),
+            ("BlocksThreat", Msg::BlocksThreat),
+            ("DoubleThreat", Msg::DoubleThreat),
+            ("Threatens", Msg::Threatens),
+            ("ForcedWin", Msg::ForcedWin),
+            ("StubbornDefense", Msg::StubbornDefense),
+            ("AllowsWinAbove", Msg::AllowsWinAbove),
+            ("TakesCenter", Msg::TakesCenter),
+            ("BestAtDepth", Msg::BestAtDepth),
+            ("Horizontal", Msg::Horizontal),
+            ("Vertical", Msg::Vertical),
+            ("Diagonal", Msg::Diagonal),
+            ("HelpTitle", Msg::HelpTitle),
+            ("HelpChoose", Msg::HelpChoose),
+            ("HelpDrop", Msg::HelpDrop),
+            ("HelpDropDigit", Msg::HelpDropDigit),
+            ("HelpDropCell", Msg::HelpDropCell),
+            ("HelpHelp", Msg::HelpHelp),
+            ("HelpRules", Msg::HelpRules),
+            ("HelpSwap", Msg::HelpSwap),
+            ("HelpSet", Msg::HelpSet),
+            ("HelpQuit", Msg::HelpQuit),
+            ("PressKey", Msg::PressKey),
+            ("PressEnter", Msg::PressEnter),
+            ("RulesTitle", Msg::RulesTitle),
+            ("RulesGoal", Msg::RulesGoal),
+            ("RulesStandard", Msg::RulesStandard),
+            ("RulesAntiGravity", Msg::RulesAntiGravity),
+            ("RulesFlipGravity", Msg::RulesFlipGravity),
+            ("OptionsTitle", Msg::OptionsTitle),
+            ("NoOptions", Msg::NoOptions),
+            ("RulesSwap", Msg::RulesSwap),
+            ("RulesHandicapCenter", Msg::RulesHandicapCenter),
+            ("RulesHandicapTempo", Msg::RulesHandicapTempo),
+            ("RulesHandicapBotCenter", Msg::RulesHandicapBotCenter),
+            ("RulesBlitzRandom", Msg::RulesBlitzRandom),
+            ("RulesBlitzForfeit", Msg::RulesBlitzForfeit),
+            ("RulesRandomStart", Msg::RulesRandomStart),
+            ("RulesFirstHuman", Msg::RulesFirstHuman),
+            ("RulesFirstBot", Msg::RulesFirstBot),
+            ("RulesFirstRandom", Msg::RulesFirstRandom),
+            ("RulesSwapHuman", Msg::RulesSwapHuman),
+            ("RulesSwapEither", Msg::RulesSwapEither),
+            ("PuzzlePrompt", Msg::PuzzlePrompt),
+            ("BoardTooLarge", Msg::BoardTooLarge),
+            ("BoardTooLargeSearch", Msg::BoardTooLargeSearch),
+            ("LineError", Msg::LineError),
+            ("PuzzleLineFormat", Msg::PuzzleLineFormat),
+            ("PuzzleMoveCount", Msg::PuzzleMoveCount),
+            ("NoPuzzles", Msg::NoPuzzles),
+            ("PuzzleSolved", Msg::PuzzleSolved),
+            ("PuzzleGood", Msg::PuzzleGood),
+            ("PuzzleTooSlowReply", Msg::PuzzleTooSlowReply),
+            ("PuzzleTooSlow", Msg::PuzzleTooSlow),
+            ("PuzzleSolution", Msg::PuzzleSolution),
+            ("PuzzleStreak", Msg::PuzzleStreak),
+            ("PuzzleNext", Msg::PuzzleNext),
+            ("WrotePuzzles", Msg::WrotePuzzles),
+            ("AlreadyWon", Msg::AlreadyWon),
+            ("EvalPosition", Msg::EvalPosition),
+            ("Evaluation", Msg::Evaluation),
+            ("BestMove", Msg::BestMove),
+            ("BestMoveNone", Msg::BestMoveNone),
+            ("Pv", Msg::Pv),
+            ("NoGames", Msg::NoGames),
+            ("RecordByDifficulty", Msg::RecordByDifficulty),
+            ("CommonBlunders", Msg::CommonBlunders),
+            ("NoneFound", Msg::NoneFound),
+            ("RatingTrend", Msg::RatingTrend),
+            ("NoEvenGames", Msg::NoEvenGames),
+            ("GameCount", Msg::GameCount),
+            ("BlunderMissedWin", Msg::BlunderMissedWin),
+            ("BlunderIgnoredThreat", Msg::BlunderIgnoredThreat),
+            ("BlunderUnderThreat", Msg::BlunderUnderThreat),
+            ("BlunderForcedWin", Msg::BlunderForcedWin),
+            ("NoGamesReach", Msg::NoGamesReach),
+            ("ExplorerPosition", Msg::ExplorerPosition),
+            ("StartPosition", Msg::StartPosition),
+            ("ExplorerKeys", Msg::ExplorerKeys),
+            ("NoGameWithId", Msg::NoGameWithId),
+            ("UnknownWinner", Msg::UnknownWinner),
+            ("NoDataDir", Msg::NoDataDir),
+            ("ResultHuman", Msg::ResultHuman),
+            ("ResultBot", Msg::ResultBot),
+            ("ResultDraw", Msg::ResultDraw),
+            ("ResultHandicap", Msg::ResultHandicap),
+            ("ResultMatch", Msg::ResultMatch),
+            ("HistoryGame", Msg::HistoryGame),
+            ("HistoryMove", Msg::HistoryMove),
+            ("HistoryKeys", Msg::HistoryKeys),
+            ("SprtProgress", Msg::SprtProgress),
+            ("SprtEngines", Msg::SprtEngines),
+            ("SprtResult", Msg::SprtResult),
+            ("SprtH1", Msg::SprtH1),
+            ("SprtH0", Msg::SprtH0),
+            ("SprtInconclusive", Msg::SprtInconclusive),
+            ("WroteTablebase", Msg::WroteTablebase),
+            ("WroteBook", Msg::WroteBook),
+            ("WroteFile", Msg::WroteFile),
+            ("ExpectedWinner", Msg::ExpectedWinner),
+            ("ExpectedProbability", Msg::ExpectedProbability),
+            ("InvalidMoveInList", Msg::InvalidMoveInList),
+            ("UnplayableScriptMove", Msg::UnplayableScriptMove),
+            ("InvalidScore", Msg::InvalidScore),
+            ("ExpectedOption", Msg::ExpectedOption),
+            ("UnknownOption", Msg::UnknownOption),
+            ("InvalidOption", Msg::InvalidOption),
+            ("InvalidCell", Msg::InvalidCell),
+            ("InvalidColumnAt", Msg::InvalidColumnAt),
+            ("MoveAfterWin", Msg::MoveAfterWin),
+            ("ColumnFullAt", Msg::ColumnFullAt),
+            ("UnknownColor", Msg::UnknownColor),
+            ("GameAlreadyOver", Msg::GameAlreadyOver),
+            ("OpeningsNeedStandardBoard", Msg::OpeningsNeedStandardBoard),
+            ("NoOpenings", Msg::NoOpenings),
+            ("NoLegalMove", Msg::NoLegalMove),
+            ("NoMoveToReview", Msg::NoMoveToReview),
+            ("EmptyEngineCommand", Msg::EmptyEngineCommand),
+            ("Evaluated", Msg::Evaluated),
+        ];
+        #[cfg(feature = "nn")]
+        keys.extend([
+            ("NetworkSize", Msg::NetworkSize),
+            ("NetworkMissing", Msg::NetworkMissing),
+        ]);
+        keys
+    }
+
+    // The names declared in `Msg`, read from this file so that a key missing
+    // from `keys` fails the test rather than going unchecked.
+    fn declared() -> Vec<&'static str> {
+        let source = include_str!("i18n.rs");
+        let body = source
+            .split_once("pub enum Msg {")
+            .and_then(|(_, rest)| rest.split_once('}'))
+            .map(|(body, _)| body)
+            .unwrap();
+        let mut names = Vec::new();
+        let mut enabled = true;
+        for line in body.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if line.starts_with("#[cfg(") {
+                enabled = cfg!(feature = "nn");
+                continue;
+            }
+            if enabled {
+                names.push(line.trim_end_matches(','));
+            }
+            enabled = true;
+        }
+        names
+    }
+
+    #[test]
+    fn every_key_is_listed() {
+        let mut listed: Vec<&str> = keys().iter().map(|&(name, _)| name).collect();
+        let mut declared = declared();
+        listed.sort_unstable();
+        declared.sort_unstable();
+        assert_eq!(listed, declared);
+    }
+
+    #[test]
+    fn translations_keep_every_placeholder() {
+        for (name, msg) in keys() {
+            let (en, de) = (english(msg), german(msg));
+            assert!(!de.is_empty(), "{}", name);
+            assert_eq!(
+                en.matches("{}").count(),
+                de.matches("{}").count(),
+                "{}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crossterm::cursor::MoveTo;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{execute, queue};
+use std::io::{self, IsTerminal, Write, stdin, stdout};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::engine_options;
+use crate::game::{ConnectFour, parse_column};
+use crate::i18n::{Msg, tr};
+use crate::terminal::RawMode;
+
+pub enum Input {
+    Column(usize),
+    // Backspace, or `u` on a line of its own: step back where that makes sense.
+    Back,
+    Invalid,
+    // The terminal was resized, so the screen needs drawing again.
+    Redraw,
+    Help,
+    Rules,
+    // Take over the opponent's first piece under the pie rule.
+    Swap,
+    // `set` followed by an option and its value, as in `depth 8`.
+    Set(String),
+    Quit,
+}
+
+pub fn read_column(game: &ConnectFour) -> Input {
+    if stdin().is_terminal() {
+        select_column(game.rows, game.cols, None)
+            .map(|input| input.unwrap_or(Input::Invalid))
+            .unwrap_or_else(|_| read_line_column(game))
+    } else {
+        read_line_column(game)
+    }
+}
+
+// Like `read_column` with a live countdown; None once `deadline` has passed.
+pub fn read_column_until(game: &ConnectFour, deadline: Instant) -> Option<Input> {
+    if stdin().is_terminal()
+        && let Ok(input) = select_column(game.rows, game.cols, Some(deadline))
+    {
+        return input;
+    }
+    let left = deadline.saturating_duration_since(Instant::now());
+    println!(
+        "{}",
+        tr(
+            Msg::EnterColumnTimed,
+            &[&column_names(game.cols, false), &left.as_secs_f64().ceil()]
+        )
+    );
+    let input = match stdin_lines()
+        .lock()
+        .expect("stdin reader")
+        .recv_timeout(left)
+    {
+        Ok(line) => line,
+        Err(RecvTimeoutError::Timeout) => return None,
+        Err(RecvTimeoutError::Disconnected) => return Some(Input::Quit),
+    };
+    Some(parse_line_column(&input, game))
+}
+
+// Lines of stdin, read on a thread of their own so that waiting for one can
+// time out. Once started it owns stdin for the rest of the program.
+static LINES: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+
+fn stdin_lines() -> &'static Mutex<Receiver<String>> {
+    LINES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdin().lines() {
+                if line.map(|line| sender.send(line)).is_err() {
+                    break;
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+// The next line of stdin, from the reader thread once that has started; None
+// at the end of input.
+fn read_line() -> Option<String> {
+    if let Some(lines) = LINES.get() {
+        return lines.lock().expect("stdin reader").recv().ok();
+    }
+    let mut line = String::new();
+    match stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+// How columns can be named, as "1-7, a-g". Single keys only reach the
+// ninth column by digit.
+pub fn column_names(cols: usize, single_keys: bool) -> String {
+    let digits = if single_keys { cols.min(9) } else { cols };
+    format!("1-{}, a-{}", digits, (b'a' + cols as u8 - 1) as char)
+}
+
+fn read_line_column(game: &ConnectFour) -> Input {
+    println!(
+        "{}",
+        tr(Msg::EnterColumn, &[&column_names(game.cols, false)])
+    );
+    match read_line() {
+        Some(input) => parse_line_column(&input, game),
+        None => Input::Quit,
+    }
+}
+
+fn read_setting() -> Input {
+    println!("{}", tr(Msg::SetPrompt, &[&engine_options::names()]));
+    print!("set ");
+    let _ = stdout().flush();
+    match read_line() {
+        Some(line) => Input::Set(line.trim().to_string()),
+        None => Input::Quit,
+    }
+}
+
+// A column by number, by letter, or by the coordinates of the cell the piece
+// lands in, as `d1` for the bottom of the fourth column.
+fn parse_line_column(input: &str, game: &ConnectFour) -> Input {
+    let input = input.trim();
+    match input {
+        "u" => return Input::Back,
+        "help" | "?" => return Input::Help,
+        "rules" => return Input::Rules,
+        "s" => return Input::Swap,
+        _ => {}
+    }
+    if let Some(setting) = input
+        .strip_prefix("set")
+        .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+    {
+        return Input::Set(setting.to_string());
+    }
+    if let Ok(col) = input.parse::<usize>() {
+        return match col {
+            col if (1..=game.cols).contains(&col) => Input::Column(col - 1),
+            _ => Input::Invalid,
+        };
+    }
+    let mut chars = input.chars();
+    let Some(col) = chars
+        .next()
+        .filter(char::is_ascii_alphabetic)
+        .and_then(parse_column)
+        .filter(|&col| col < game.cols)
+    else {
+        return Input::Invalid;
+    };
+    let row = chars.as_str();
+    // Rows are counted from the bottom, as in the board's coordinates.
+    if row.is_empty()
+        || row.parse::<usize>().is_ok_and(|row| {
+            game.landing_row(col)
+                .is_some_and(|landing| game.rows - landing == row)
+        })
+    {
+        Input::Column(col)
+    } else {
+        Input::Invalid
+    }
+}
+
+// None if `deadline` passes first.
+fn select_column(rows: usize, cols: usize, deadline: Option<Instant>) -> io::Result<Option<Input>> {
+    println!("{}", tr(Msg::SelectColumn, &[&column_names(cols, true)]));
+    let raw = RawMode::enable()?;
+    let mut selected = cols / 2;
+
+    loop {
+        let left = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        draw_selector(rows, cols, selected, left)?;
+        // Wake up often enough to keep the countdown current.
+        if let Some(left) = left {
+            if left.is_zero() {
+                execute!(stdout(), Print("\r\n"))?;
+                return Ok(None);
+            }
+            if !event::poll(left.min(Duration::from_millis(100)))? {
+                continue;
+            }
+        }
+        let input = match event::read()? {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => match code {
+                KeyCode::Left => {
+                    selected = selected.saturating_sub(1);
+                    None
+                }
+                KeyCode::Right => {
+                    selected = (selected + 1).min(cols - 1);
+                    None
+                }
+                KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Down => {
+                    Some(Input::Column(selected))
+                }
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    Some(Input::Quit)
+                }
+                KeyCode::Char('q') | KeyCode::Esc => Some(Input::Quit),
+                KeyCode::Backspace => Some(Input::Back),
+                // From eight columns on `h` names the eighth.
+                KeyCode::Char('?') => Some(Input::Help),
+                KeyCode::Char('h') if cols < 8 => Some(Input::Help),
+                KeyCode::Char('r') => Some(Input::Rules),
+                KeyCode::Char('s') => Some(Input::Swap),
+                // The option and its value are typed as a line, echoed.
+                KeyCode::Char(':') => {
+                    drop(raw);
+                    println!();
+                    return Ok(Some(read_setting()));
+                }
+                KeyCode::Char(c) => parse_column(c).filter(|&col| col < cols).map(Input::Column),
+                _ => None,
+            },
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => {
+                // The board is drawn from the top-left corner, two characters per cell.
+                let col = column as usize / 2;
+                ((row as usize) <= rows && col < cols).then_some(Input::Column(col))
+            }
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Moved,
+                column,
+                row,
+                ..
+            }) => {
+                let col = column as usize / 2;
+                if (row as usize) <= rows && col < cols {
+                    selected = col;
+                }
+                None
+            }
+            Event::Resize(..) => Some(Input::Redraw),
+            _ => None,
+        };
+        if let Some(input) = input {
+            return Ok(Some(input));
+        }
+    }
+}
+
+fn draw_selector(
+    rows: usize,
+    cols: usize,
+    selected: usize,
+    left: Option<Duration>,
+) -> io::Result<()> {
+    let mut out = stdout();
+    let mut line = format!("{:width$}^", "", width = 2 * selected + 1);
+    if let Some(left) = left {
+        line = format!(
+            "{:<width$}  {}",
+            line,
+            tr(Msg::SecondsLeft, &[&left.as_secs_f64().ceil()]),
+            width = 2 * cols
+        );
+    }
+    queue!(
+        out,
+        // Below the board and its column labels.
+        MoveTo(0, rows as u16 + 1),
+        Clear(ClearType::CurrentLine),
+        Print(line),
+    )?;
+    out.flush()
+}
+
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Enter,
+    Delete,
+    Char(char),
+    Quit,
+    Resize,
+    Other,
+}
+
+// Reads a single key press in raw mode; mouse clicks count as `Key::Other`
+// and `q` as `Key::Quit`.
+pub fn read_key() -> Key {
+    let Ok(_raw) = RawMode::enable() else {
+        return Key::Quit;
+    };
+    loop {
+        match event::read() {
+            Ok(Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            })) => {
+                return match code {
+                    KeyCode::Left => Key::Left,
+                    KeyCode::Right => Key::Right,
+                    KeyCode::Up => Key::Up,
+                    KeyCode::Down => Key::Down,
+                    KeyCode::Home => Key::Home,
+                    KeyCode::End => Key::End,
+                    KeyCode::Enter => Key::Enter,
+                    KeyCode::Backspace | KeyCode::Delete => Key::Delete,
+                    KeyCode::Char('q') | KeyCode::Esc => Key::Quit,
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+                    KeyCode::Char(c) => Key::Char(c),
+                    _ => Key::Other,
+                };
+            }
+            Ok(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(_),
+                ..
+            })) => return Key::Other,
+            Ok(Event::Resize(..)) => return Key::Resize,
+            Ok(_) => {}
+            Err(_) => return Key::Quit,
+        }
+    }
+}
+
+// Waits for a key press; false if the human asked to quit instead.
+pub fn wait_for_key() -> bool {
+    if !stdin().is_terminal() {
+        return read_line().is_some_and(|input| input.trim() != "q");
+    }
+    loop {
+        match read_key() {
+            Key::Resize => {}
+            key => return !matches!(key, Key::Quit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER;
+
+    fn column(input: &str, game: &ConnectFour) -> Option<usize> {
+        match parse_line_column(input, game) {
+            Input::Column(col) => Some(col),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn columns_by_number_letter_or_cell() {
+        let game = ConnectFour::from_moves(6, 7, "44", PLAYER).unwrap();
+        assert_eq!(column("1", &game), Some(0));
+        assert_eq!(column(" 7 ", &game), Some(6));
+        assert_eq!(column("c", &game), Some(2));
+        assert_eq!(column("D", &game), Some(3));
+        assert_eq!(column("a1", &game), Some(0));
+        assert_eq!(column("d3", &game), Some(3));
+        // Only the cell the piece lands in names the column.
+        assert_eq!(column("d1", &game), None);
+        assert_eq!(column("0", &game), None);
+        assert_eq!(column("8", &game), None);
+        assert_eq!(column("h", &game), None);
+        assert_eq!(column("", &game), None);
+    }
+
+    #[test]
+    fn commands() {
+        let game = ConnectFour::new(6, 7);
+        assert!(matches!(parse_line_column("u", &game), Input::Back));
+        assert!(matches!(parse_line_column("?", &game), Input::Help));
+        assert!(matches!(parse_line_column("rules", &game), Input::Rules));
+        assert!(matches!(parse_line_column("s", &game), Input::Swap));
+        assert!(matches!(
+            parse_line_column("set depth 8", &game),
+            Input::Set(setting) if setting == " depth 8"
+        ));
+        assert!(
+            matches!(parse_line_column("set", &game), Input::Set(setting) if setting.is_empty())
+        );
+        assert!(matches!(parse_line_column("settle", &game), Input::Invalid));
+    }
+}
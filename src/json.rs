@@ -0,0 +1,131 @@
+// How the core types look in JSON, shared by save files and the server modes.
+// Columns and rows are 1-based, like everywhere the user sees them, with row 1
+// at the top; pieces are "o" and "x". Fields are only ever added to this
+// schema, never renamed or given another meaning.
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::game::{BOT, PLAYER, symbol};
+
+pub mod column {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(col: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+        (col + 1).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+        usize::deserialize(deserializer)?
+            .checked_sub(1)
+            .ok_or_else(|| D::Error::custom("columns start at 1"))
+    }
+}
+
+pub mod optional_column {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(col: &Option<usize>, serializer: S) -> Result<S::Ok, S::Error> {
+        col.map(|col| col + 1).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<usize>, D::Error> {
+        Option::<usize>::deserialize(deserializer)?
+            .map(|col| {
+                col.checked_sub(1)
+                    .ok_or_else(|| D::Error::custom("columns start at 1"))
+            })
+            .transpose()
+    }
+}
+
+pub mod columns {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(cols: &[usize], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(cols.iter().map(|col| col + 1))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<usize>, D::Error> {
+        Vec::<usize>::deserialize(deserializer)?
+            .into_iter()
+            .map(|col| {
+                col.checked_sub(1)
+                    .ok_or_else(|| D::Error::custom("columns start at 1"))
+            })
+            .collect()
+    }
+}
+
+// (row, col) cells, written as [row, column] pairs.
+pub mod cells {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        cells: &[(usize, usize); 4],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(cells.iter().map(|&(row, col)| [row + 1, col + 1]))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[(usize, usize); 4], D::Error> {
+        let cells = <[[usize; 2]; 4]>::deserialize(deserializer)?;
+        let mut line = [(0, 0); 4];
+        for (cell, [row, col]) in line.iter_mut().zip(cells) {
+            *cell = row
+                .checked_sub(1)
+                .zip(col.checked_sub(1))
+                .ok_or_else(|| D::Error::custom("rows and columns start at 1"))?;
+        }
+        Ok(line)
+    }
+}
+
+pub mod piece {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(piece: &i8, serializer: S) -> Result<S::Ok, S::Error> {
+        symbol(*piece).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i8, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        match text.as_str() {
+            "o" => Ok(PLAYER),
+            "x" => Ok(BOT),
+            _ => Err(D::Error::custom(format!(
+                "unknown piece '{}', expected o or x",
+                text
+            ))),
+        }
+    }
+}
+
+// Durations in seconds, as in the config file.
+pub mod optional_secs {
+    use super::*;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| duration.as_secs_f64())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<f64>::deserialize(deserializer)?
+            .map(|secs| {
+                Duration::try_from_secs_f64(secs)
+                    .map_err(|_| D::Error::custom(format!("invalid duration {}", secs)))
+            })
+            .transpose()
+    }
+}
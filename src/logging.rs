@@ -0,0 +1,29 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::stderr;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::filter::LevelFilter;
+
+// Logging stays off unless asked for, since stderr shares the terminal with the board.
+pub fn init(level: Option<LevelFilter>, file: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let level = match (level, file) {
+        (Some(level), _) => level,
+        (None, Some(_)) => LevelFilter::INFO,
+        (None, None) => return Ok(()),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false);
+    match file {
+        Some(path) => {
+            let file = File::create(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            builder
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init();
+        }
+        None => builder.with_writer(stderr).init(),
+    }
+    Ok(())
+}
@@ -1,238 +1,1544 @@
-use rand::seq::IndexedRandom;
-use std::cmp::{max, min};
-use std::fmt;
-use std::io::stdin;
+use clap::{Args, Parser, Subcommand};
+use rand::Rng;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs;
+use std::io::{self, IsTerminal};
 
-const ROWS: usize = 6;
-const COLS: usize = 7;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, info_span, warn};
+use tracing_subscriber::filter::LevelFilter;
 
-const BOT: i8 = -1;
-const EMPTY: i8 = 0;
-const PLAYER: i8 = 1;
+mod adaptive;
+mod analysis;
+mod batch;
+mod book;
+mod bot;
+mod calibrate;
+mod coach;
+mod config;
+mod editor;
+mod engine_options;
+mod explain;
+mod explorer;
+mod export;
+mod game;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod headless;
+mod help;
+mod history;
+mod i18n;
+mod input;
+mod json;
+mod logging;
+#[cfg(feature = "nn")]
+mod nn;
+mod observer;
+mod player;
+#[cfg(test)]
+mod proptests;
+mod protocol;
+mod puzzle;
+mod review;
+mod rules;
+mod score_table;
+mod server;
+#[cfg(feature = "grpc")]
+mod sessions;
+mod solver;
+mod sound;
+mod sprt;
+mod stats;
+mod tablebase;
+mod terminal;
+mod theme;
+mod transposition;
+mod tree;
+mod tune;
 
-struct ConnectFour {
-    board: [[i8; COLS]; ROWS],
+use book::Book;
+use bot::{
+    Analysis, BotPlayer, DEFAULT_DEPTH, Engine, Eval, MAX_DEPTH, Personality, SearchSettings,
+    Weights,
+};
+use coach::Strictness;
+use config::Config;
+use engine_options::{Configurable, Setting};
+use game::{
+    BOT, ConnectFour, First, GameStatus, Handicap, MAX_COLS, MAX_ROWS, OnTimeout, PASS, PLAYER,
+    PositionHistory, SWAP, can_swap, column_char,
+};
+use headless::ScriptedPlayer;
+use history::{Database, GameRecord, Winner};
+use i18n::{Lang, Msg, tr};
+use observer::{Log, Observer, Observers};
+use player::{GameView, HumanCliPlayer, Move, Player};
+use rules::{Rules, Variant};
+use sound::Sound;
+use tablebase::Tablebase;
+use terminal::{StopOnInterrupt, clear_screen};
+use theme::{Notation, Symbol, Theme};
+use tree::SearchTree;
+
+const DEFAULT_ROWS: usize = 6;
+const DEFAULT_COLS: usize = 7;
+
+#[derive(Parser)]
+#[command(version, about = "Play Connect Four against a minimax bot")]
+struct Cli {
+    #[command(flatten)]
+    options: Options,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-impl ConnectFour {
-    fn new() -> Self {
-        Self {
-            board: [[EMPTY; COLS]; ROWS],
-        }
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Find forced wins in puzzle positions
+    Puzzle {
+        /// Puzzle file to use instead of the built-in set
+        #[arg(long)]
+        file: Option<PathBuf>,
+        #[command(subcommand)]
+        action: Option<PuzzleCommand>,
+    },
+    /// Evaluate a single position and exit
+    Eval {
+        /// Moves leading to the position as column digits, e.g. 4453 (o moves first)
+        position: String,
+        /// Print the result as a JSON object
+        #[arg(long)]
+        json: bool,
+    },
+    /// Solve every position in a file and write the results as CSV
+    Solve {
+        /// File with one position per line, optionally followed by its expected score
+        #[arg(long)]
+        input: PathBuf,
+        /// Write the CSV here instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Number of worker threads (defaults to the number of CPUs)
+        #[arg(long)]
+        jobs: Option<usize>,
+        /// Use the bot's depth-limited search instead of solving exactly
+        #[arg(long)]
+        search: bool,
+    },
+    /// Show how often each column was played from a position in recorded games
+    Explorer {
+        /// Moves leading to the starting position, e.g. 44 (empty board by default)
+        #[arg(default_value = "")]
+        position: String,
+    },
+    /// Set up a position by placing pieces freely, then play or analyze it
+    Edit,
+    /// Explain the rules of the game with the chosen variant and options
+    Rules,
+    /// Build an endgame tablebase by retrograde analysis (practical on small boards)
+    Tablebase {
+        /// Include every position with at least this many pieces
+        #[arg(long)]
+        min_pieces: usize,
+        /// File to write the tablebase to
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Solve every position up to a number of moves into an opening book
+    Book {
+        /// Include every position reachable in at most this many moves
+        #[arg(long)]
+        plies: usize,
+        /// File to write the book to
+        #[arg(long)]
+        output: PathBuf,
+        /// Number of worker threads (defaults to the number of CPUs)
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
+    /// Tune the evaluation weights by self-play (SPSA) and print the best set found
+    Tune {
+        /// Number of tuning iterations
+        #[arg(long, default_value_t = 100)]
+        iterations: u32,
+        /// Self-play games per iteration
+        #[arg(long, default_value_t = 8)]
+        games: u32,
+        /// Write the weights to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Speak the line-based engine protocol on stdin/stdout
+    Engine {
+        /// Take one JSON request per line and answer each with the game as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a lobby server where players meet over TCP for rated games
+    Server {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:4000")]
+        listen: String,
+        /// Database of players and games (in the data directory by default)
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Length of a rating season; when one ends its standings are archived
+        /// and ratings reset
+        #[arg(long, value_name = "DAYS", value_parser = clap::value_parser!(u64).range(1..))]
+        season_days: Option<u64>,
+    },
+    /// Serve the engine over gRPC (see proto/engine.proto)
+    #[cfg(feature = "grpc")]
+    Grpc {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        listen: String,
+        /// Seconds a game may go unused before it is dropped
+        #[arg(long, value_name = "SECS", default_value_t = 600, value_parser = clap::value_parser!(u64).range(1..))]
+        idle_timeout: u64,
+        /// Searches running at once across all games (one per core if omitted)
+        #[arg(long)]
+        max_searches: Option<usize>,
+    },
+    /// Play the bot at a range of depths against the solver and report its error rates
+    Calibrate {
+        /// Shallowest depth to calibrate
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(i32).range(0..))]
+        min_depth: i32,
+        /// Deepest depth to calibrate
+        #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(i32).range(0..))]
+        max_depth: i32,
+        /// File with one opening per line to play instead of the built-in set
+        #[arg(long)]
+        openings: Option<PathBuf>,
+    },
+    /// Play two engine commands against each other until an SPRT decides
+    Match {
+        /// Command starting the first engine, e.g. "connect-four --depth 6 engine";
+        /// the bot as configured here plays if omitted
+        #[arg(long)]
+        first: Option<String>,
+        /// Command starting the second engine; the bot as configured here plays
+        /// if omitted
+        #[arg(long)]
+        second: Option<String>,
+        /// Elo difference of the null hypothesis
+        #[arg(long, default_value_t = 0.0)]
+        elo0: f64,
+        /// Elo difference of the alternative hypothesis
+        #[arg(long, default_value_t = 20.0)]
+        elo1: f64,
+        /// Probability of accepting H1 when H0 holds
+        #[arg(long, default_value_t = 0.05, value_parser = parse_probability)]
+        alpha: f64,
+        /// Probability of accepting H0 when H1 holds
+        #[arg(long, default_value_t = 0.05, value_parser = parse_probability)]
+        beta: f64,
+        /// Stop after this many games even without a decision, rounded down to
+        /// whole pairs
+        #[arg(long, default_value_t = 2000, value_parser = clap::value_parser!(u32).range(2..))]
+        max_games: u32,
+    },
+    /// Summarize your record, typical blunders and rating from recorded games
+    Stats,
+    /// Render a recorded game as an animated image, one frame per move
+    Export {
+        /// Id of the game, as shown by `history list`
+        id: i64,
+        #[arg(long, value_enum, default_value = "svg")]
+        format: export::Format,
+        /// File to write (game-<id>.<format> by default)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Browse the database of finished games
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+}
 
-    fn get_valid_moves(&self) -> Vec<usize> {
-        (0..COLS)
-            .filter(|&col| self.board[0][col] == EMPTY)
-            .collect()
-    }
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// List recorded games, most recent first
+    List {
+        /// Only games with this result (human, bot or draw)
+        #[arg(long, value_parser = parse_winner)]
+        winner: Option<Winner>,
+        /// Only games against this engine
+        #[arg(long = "against", value_enum)]
+        engine: Option<Engine>,
+        /// Only games at this search depth
+        #[arg(long = "at-depth")]
+        depth: Option<i32>,
+        /// Only games finished on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Show at most this many games
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Review every move of a recorded game with the bot's search
+    Review {
+        /// Id of the game as shown by `history list`
+        id: i64,
+    },
+    /// Re-open a recorded game and step through its moves
+    Open {
+        /// Id of the game as shown by `history list`
+        id: i64,
+        /// Start at this move instead of the final position
+        #[arg(long)]
+        ply: Option<usize>,
+    },
+}
 
-    fn check_win(&self, player: i8) -> bool {
-        for row in 0..ROWS {
-            for col in 0..(COLS - 3) {
-                if (0..4).all(|i| self.board[row][col + i] == player) {
-                    return true;
-                }
-            }
+fn parse_winner(text: &str) -> Result<Winner, String> {
+    Winner::parse(text).ok_or_else(|| tr(Msg::ExpectedWinner, &[&text]))
+}
+
+// Error rates of 0 or 1 would put the SPRT bounds at infinity.
+fn parse_probability(text: &str) -> Result<f64, String> {
+    text.parse()
+        .ok()
+        .filter(|p| 0.0 < *p && *p < 1.0)
+        .ok_or_else(|| tr(Msg::ExpectedProbability, &[&text]))
+}
+
+#[derive(Subcommand)]
+enum PuzzleCommand {
+    /// Mine self-play games for new puzzles and write them in the puzzle file format
+    Generate {
+        /// Number of puzzles to find
+        #[arg(long, default_value_t = 20)]
+        count: usize,
+        /// Give up after this many self-play games
+        #[arg(long, default_value_t = 1000)]
+        games: usize,
+        /// Shortest forced win worth keeping, in moves
+        #[arg(long, default_value_t = 2)]
+        min_moves: u32,
+        /// Longest forced win to look for, in moves
+        #[arg(long, default_value_t = 5)]
+        max_moves: u32,
+        /// Write the puzzles to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct Options {
+    /// Config file to read instead of ~/.config/connect-four-bot/config.toml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Search depth of the bot
+    #[arg(long, global = true)]
+    depth: Option<i32>,
+    /// Engine the bot uses to pick its moves
+    #[arg(long, value_enum, global = true)]
+    engine: Option<Engine>,
+    /// How the bot scores positions at its search horizon
+    #[arg(long, value_enum, global = true)]
+    eval: Option<Eval>,
+    /// Character of the bot, replacing the evaluation weights
+    #[arg(long, value_enum, global = true)]
+    personality: Option<Personality>,
+    /// Random playouts per position with --eval rollout
+    #[arg(long, global = true)]
+    rollouts: Option<u32>,
+    /// Seconds the bot may spend per move (deepens iteratively up to --depth)
+    #[arg(long, global = true)]
+    move_time: Option<f64>,
+    /// Seconds the human has for each move, shown as a countdown
+    #[arg(long, value_name = "SECS", global = true)]
+    blitz: Option<f64>,
+    /// What happens when the human runs out of time with --blitz
+    #[arg(long, value_enum, global = true)]
+    on_timeout: Option<OnTimeout>,
+    /// Transposition table size in megabytes, of the solver and of each bot
+    #[arg(long, global = true)]
+    hash: Option<usize>,
+    /// Endgame tablebase to probe for perfect late-game play
+    #[arg(long, global = true)]
+    tablebase: Option<PathBuf>,
+    /// Opening book of solved positions to play the opening perfectly from
+    #[arg(long, global = true)]
+    book: Option<PathBuf>,
+    /// Network weights for --eval nn (JSON, see src/nn.rs)
+    #[cfg(feature = "nn")]
+    #[arg(long, value_name = "PATH", global = true)]
+    nn_weights: Option<PathBuf>,
+    /// Write the bot's last search tree to this file, as .dot or .json
+    #[arg(long, value_name = "PATH", global = true)]
+    dump_tree: Option<PathBuf>,
+    /// Plies of the search tree kept by --dump-tree
+    #[arg(long, value_name = "N", default_value_t = tree::DEFAULT_TREE_DEPTH, global = true)]
+    tree_depth: usize,
+    /// Adjust the bot's depth and blunder rate to your recent results
+    #[arg(long, global = true)]
+    adaptive: bool,
+    /// Explain each of the bot's moves
+    #[arg(long, global = true)]
+    explain: bool,
+    /// Warn before moves that lose or drop much evaluation (lenient, normal, strict)
+    #[arg(long, value_enum, global = true)]
+    coach: Option<Strictness>,
+    /// Print a review of every move once the game is over
+    #[arg(long, global = true)]
+    review: bool,
+    /// Play without the screen: take the human's moves from --moves or stdin
+    /// and print only the result and a review as JSON
+    #[arg(long)]
+    headless: bool,
+    /// File with the human's moves for --headless, e.g. 4 4 5 3
+    #[arg(long, requires = "headless")]
+    moves: Option<PathBuf>,
+    /// Play a best-of-N match against the bot, taking turns to move first
+    #[arg(long = "match", value_name = "N", conflicts_with = "headless", value_parser = clap::value_parser!(u32).range(1..))]
+    best_of: Option<u32>,
+    /// Who moves first in games against the bot
+    // Not global, as `match` names its first engine with --first.
+    #[arg(long, value_enum)]
+    first: Option<First>,
+    /// Letter of the human's pieces; the bot plays the other one
+    #[arg(long, value_enum, global = true)]
+    symbol: Option<Symbol>,
+    /// How columns are labelled: numbers, or letters with rows numbered as in d1
+    #[arg(long, value_enum, global = true)]
+    notation: Option<Notation>,
+    /// Play with the pie rule: after the first move the other side may take it over
+    #[arg(long, global = true)]
+    swap: bool,
+    /// Start the game with a handicap for one side
+    #[arg(long, value_enum, global = true)]
+    handicap: Option<Handicap>,
+    /// Start from a random opening of this many moves (8 if omitted) that the
+    /// solver finds balanced; fewer moves take much longer to verify
+    #[arg(long, value_name = "PLIES", num_args = 0..=1, default_missing_value = "8", global = true)]
+    random_start: Option<usize>,
+    /// Rules for where pieces land in games against the bot
+    #[arg(long, value_enum, global = true)]
+    variant: Option<Variant>,
+    /// Pieces played between gravity flips with --variant flip-gravity
+    #[arg(long, global = true)]
+    flip_every: Option<usize>,
+    /// Ring the terminal bell on the bot's moves, new threats and the end of the game
+    #[arg(long, global = true)]
+    sound: bool,
+    /// Language of prompts and messages; defaults to the locale from LANG
+    #[arg(long, value_enum, global = true)]
+    lang: Option<Lang>,
+    /// Number of board rows
+    #[arg(long, global = true)]
+    rows: Option<usize>,
+    /// Number of board columns
+    #[arg(long, global = true)]
+    cols: Option<usize>,
+    /// Color of the human's pieces
+    #[arg(long, global = true)]
+    player_color: Option<String>,
+    /// Color of the bot's pieces
+    #[arg(long, global = true)]
+    bot_color: Option<String>,
+    /// Log verbosity (off, error, warn, info, debug, trace)
+    #[arg(long, global = true)]
+    log_level: Option<LevelFilter>,
+    /// Write logs to this file instead of stderr
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+}
+
+struct Settings {
+    depth: i32,
+    engine: Engine,
+    eval: Eval,
+    personality: Option<Personality>,
+    rollouts: u32,
+    weights: Weights,
+    move_time: Option<Duration>,
+    blitz: Option<Duration>,
+    on_timeout: OnTimeout,
+    hash: usize,
+    tablebase: Option<Arc<Tablebase>>,
+    book: Option<Arc<Book>>,
+    #[cfg(feature = "nn")]
+    network: Option<Arc<nn::Network>>,
+    // The file and depth each bot's search tree is written with.
+    dump_tree: Option<(PathBuf, usize)>,
+    first: First,
+    swap: bool,
+    handicap: Option<Handicap>,
+    random_start: Option<usize>,
+    variant: Variant,
+    flip_every: usize,
+    rules: Arc<dyn Rules>,
+    adaptive: bool,
+    blunder_rate: f64,
+    explain: bool,
+    coach: Option<Strictness>,
+    review: bool,
+    rows: usize,
+    cols: usize,
+    theme: Theme,
+    sound: Sound,
+}
+
+impl Settings {
+    fn resolve(cli: &Options, config: Config) -> Result<Self, String> {
+        let depth = cli.depth.or(config.depth).unwrap_or(DEFAULT_DEPTH);
+        if !(0..=MAX_DEPTH).contains(&depth) {
+            return Err(tr(Msg::DepthRange, &[&MAX_DEPTH, &depth]));
         }
-        for row in 0..(ROWS - 3) {
-            for col in 0..COLS {
-                if (0..4).all(|i| self.board[row + i][col] == player) {
-                    return true;
+        let move_time = match cli.move_time.or(config.move_time) {
+            Some(secs) if secs.is_finite() && secs > 0.0 => Some(Duration::from_secs_f64(secs)),
+            Some(secs) => return Err(tr(Msg::MoveTimePositive, &[&secs])),
+            None => None,
+        };
+        let blitz = match cli.blitz.or(config.blitz) {
+            Some(secs) if secs.is_finite() && secs > 0.0 => Some(Duration::from_secs_f64(secs)),
+            Some(secs) => return Err(tr(Msg::BlitzPositive, &[&secs])),
+            None => None,
+        };
+        let rollouts = cli
+            .rollouts
+            .or(config.rollouts)
+            .unwrap_or(bot::DEFAULT_ROLLOUTS);
+        if rollouts == 0 {
+            return Err(tr(Msg::RolloutsAtLeastOne, &[]));
+        }
+        let hash = cli.hash.or(config.hash).unwrap_or(solver::DEFAULT_HASH_MB);
+        if hash == 0 {
+            return Err(tr(Msg::HashAtLeastOne, &[]));
+        }
+        let rows = cli.rows.or(config.board.rows).unwrap_or(DEFAULT_ROWS);
+        let cols = cli.cols.or(config.board.cols).unwrap_or(DEFAULT_COLS);
+        // Transcripts run out of column letters past the widest board, and
+        // boards are kept no taller than they can be wide.
+        if !(4..=MAX_ROWS).contains(&rows) || !(4..=MAX_COLS).contains(&cols) {
+            return Err(tr(Msg::BoardSize, &[&cols, &rows]));
+        }
+        let tablebase = match cli.tablebase.as_ref().or(config.tablebase.as_ref()) {
+            Some(path) => {
+                let tablebase = Tablebase::load(path)?;
+                info!(path = %path.display(), positions = tablebase.len(), "tablebase loaded");
+                Some(Arc::new(tablebase))
+            }
+            None => None,
+        };
+        let book = match cli.book.as_ref().or(config.book.as_ref()) {
+            Some(path) => {
+                let book = Book::open(path)?;
+                info!(path = %path.display(), positions = book.len(), plies = book.plies(), "book opened");
+                Some(Arc::new(book))
+            }
+            None => None,
+        };
+        let eval = cli.eval.or(config.eval).unwrap_or(Eval::Static);
+        #[cfg(feature = "nn")]
+        let network = match cli.nn_weights.as_ref().or(config.nn_weights.as_ref()) {
+            Some(path) => {
+                let network = nn::Network::load(path).map_err(|err| err.to_string())?;
+                if network.size() != (rows, cols) {
+                    let (net_rows, net_cols) = network.size();
+                    return Err(tr(Msg::NetworkSize, &[&net_cols, &net_rows, &cols, &rows]));
                 }
+                info!(path = %path.display(), "network loaded");
+                Some(Arc::new(network))
             }
+            None if eval == Eval::Nn => return Err(tr(Msg::NetworkMissing, &[])),
+            None => None,
+        };
+        if let Some(path) = &cli.dump_tree {
+            SearchTree::new(path, cli.tree_depth)?;
         }
-        for row in 0..(ROWS - 3) {
-            for col in 0..(COLS - 3) {
-                if (0..4).all(|i| self.board[row + i][col + i] == player) {
-                    return true;
-                }
-                if (0..4).all(|i| self.board[row + 3 - i][col + i] == player) {
-                    return true;
-                }
+        let swap = cli.swap || config.swap.unwrap_or(false);
+        let handicap = cli.handicap.or(config.handicap);
+        if swap && handicap.is_some() {
+            return Err(tr(Msg::HandicapWithSwap, &[]));
+        }
+        let first = cli.first.or(config.first).unwrap_or(First::Human);
+        // Handicaps are written for games the human opens.
+        if first != First::Human && handicap.is_some() {
+            return Err(tr(Msg::HandicapBotFirst, &[]));
+        }
+        let random_start = cli.random_start.or(config.random_start);
+        if let Some(plies) = random_start {
+            if swap || handicap.is_some() {
+                return Err(tr(Msg::RandomStartCombined, &[]));
             }
+            if plies == 0 || plies >= rows * cols {
+                return Err(tr(Msg::RandomStartMoves, &[&(rows * cols - 1), &plies]));
+            }
+        }
+        let variant = cli.variant.or(config.variant).unwrap_or(Variant::Standard);
+        let flip_every = cli
+            .flip_every
+            .or(config.flip_every)
+            .unwrap_or(rules::DEFAULT_FLIP_EVERY);
+        if flip_every == 0 {
+            return Err(tr(Msg::FlipAtLeastOne, &[]));
         }
-        false
+        // The solver that checks random starts assumes standard gravity.
+        if variant != Variant::Standard && random_start.is_some() {
+            return Err(tr(Msg::RandomStartVariant, &[&variant.name()]));
+        }
+        let sound = Sound::new(
+            cli.sound || config.sound.enabled.unwrap_or(false),
+            &config.sound,
+        );
+        let theme = Theme::parse(
+            cli.player_color
+                .as_deref()
+                .or(config.colors.player.as_deref())
+                .unwrap_or("yellow"),
+            cli.bot_color
+                .as_deref()
+                .or(config.colors.bot.as_deref())
+                .unwrap_or("red"),
+        )?
+        .with_symbol(cli.symbol.or(config.symbol).unwrap_or(Symbol::O))
+        .with_notation(
+            cli.notation
+                .or(config.notation)
+                .unwrap_or(Notation::Numbers),
+        );
+
+        Ok(Self {
+            depth,
+            engine: cli.engine.or(config.engine).unwrap_or(Engine::Minimax),
+            eval,
+            personality: cli.personality.or(config.personality),
+            rollouts,
+            weights: config.weights,
+            move_time,
+            blitz,
+            on_timeout: cli
+                .on_timeout
+                .or(config.on_timeout)
+                .unwrap_or(OnTimeout::Random),
+            hash,
+            tablebase,
+            book,
+            #[cfg(feature = "nn")]
+            network,
+            dump_tree: cli.dump_tree.clone().map(|path| (path, cli.tree_depth)),
+            first,
+            swap,
+            handicap,
+            random_start,
+            variant,
+            flip_every,
+            rules: variant.rules(flip_every),
+            adaptive: cli.adaptive || config.adaptive.unwrap_or(false),
+            blunder_rate: 0.0,
+            explain: cli.explain || config.explain.unwrap_or(false),
+            coach: cli.coach.or(config.coach),
+            review: cli.review || config.review.unwrap_or(false),
+            rows,
+            cols,
+            theme,
+            sound,
+        })
     }
 
-    fn drop_piece(&mut self, col: usize, piece: i8) -> bool {
-        for row in (0..ROWS).rev() {
-            if self.board[row][col] == EMPTY {
-                self.board[row][col] = piece;
-                return true;
-            }
-        }
-        false
+    fn bot(&self) -> BotPlayer {
+        self.bot_at(self.depth)
     }
 
-    fn clone(&self) -> Self {
-        Self {
-            board: self.board.clone(),
+    fn bot_at(&self, depth: i32) -> BotPlayer {
+        let mut search = SearchSettings {
+            depth,
+            engine: self.engine,
+            eval: self.eval,
+            rollouts: self.rollouts,
+            weights: self.weights,
+            blunder_rate: self.blunder_rate,
+            move_time: self.move_time,
+            ..SearchSettings::default()
+        };
+        if let Some(personality) = self.personality {
+            search.weights = personality.weights();
+            search.style = personality.style();
+            search.blunder_rate = search.blunder_rate.max(personality.blunder_rate());
         }
+        let tree = self
+            .dump_tree
+            .as_ref()
+            .and_then(|(path, depth)| SearchTree::new(path, *depth).ok());
+        let bot = BotPlayer::from_settings(&search)
+            .with_tablebase(self.tablebase.clone())
+            .with_book(self.book.clone())
+            .with_hash(self.hash)
+            .with_tree(tree);
+        #[cfg(feature = "nn")]
+        let bot = bot.with_network(self.network.clone());
+        bot
     }
 }
 
-impl fmt::Display for ConnectFour {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let symbol = |val: i8| match val {
-            BOT => "x",
-            EMPTY => ".",
-            PLAYER => "o",
-            _ => " ",
-        };
-        for row in &self.board {
-            for &cell in row {
-                write!(f, " {}", symbol(cell))?;
+impl Configurable for Settings {
+    fn set(&mut self, setting: Setting) -> Result<(), String> {
+        match setting {
+            Setting::Depth(depth) => self.depth = depth,
+            Setting::Engine(engine) => self.engine = engine,
+            #[cfg(feature = "nn")]
+            Setting::Eval(Eval::Nn) if self.network.is_none() => {
+                return Err(tr(Msg::NetworkMissing, &[]));
+            }
+            Setting::Eval(eval) => self.eval = eval,
+            Setting::Rollouts(rollouts) => self.rollouts = rollouts,
+            Setting::Weights(weights) => {
+                self.weights = weights;
+                self.personality = None;
             }
-            writeln!(f)?;
+            Setting::Personality(personality) => self.personality = personality,
+            Setting::MoveTime(move_time) => self.move_time = move_time,
+            // Bots and solvers built from here on get a table of the new size.
+            Setting::Hash(mb) => self.hash = mb,
         }
+        info!(option = setting.name(), value = %setting.value(), "setting changed");
         Ok(())
     }
 }
 
-struct BotPlayer {
-    max_depth: i32,
-    reward: i32,
-}
+impl Settings {
+    // Searches like the bot, but never blunders on purpose. Used to judge moves.
+    fn analyst(&self) -> BotPlayer {
+        BotPlayer::new(self.depth)
+            .with_weights(self.weights)
+            .with_tablebase(self.tablebase.clone())
+            .with_book(self.book.clone())
+            .with_hash(self.hash)
+    }
 
-impl BotPlayer {
-    fn new(max_depth: i32) -> Self {
-        Self {
-            max_depth,
-            reward: 100,
-        }
-    }
-
-    fn minimax(
-        &mut self,
-        game: &mut ConnectFour,
-        depth: i32,
-        alpha: i32,
-        beta: i32,
-        is_maximizing: bool,
-    ) -> i32 {
-        let valid_moves = game.get_valid_moves();
-        if valid_moves.is_empty() || depth == 0 {
-            return 0;
-        }
-
-        let mut alpha = alpha;
-        let mut beta = beta;
-
-        if is_maximizing {
-            let mut max_score = i32::MIN;
-
-            for col in valid_moves {
-                if let Some(row) = (0..ROWS).rev().find(|&row| game.board[row][col] == EMPTY) {
-                    game.board[row][col] = BOT;
-
-                    let score = if game.check_win(BOT) {
-                        self.reward
-                    } else {
-                        self.minimax(game, depth - 1, alpha, beta, false)
-                    };
-                    game.board[row][col] = EMPTY;
-                    max_score = max(max_score, score);
-                    alpha = max(alpha, score);
-                    if beta <= alpha {
-                        break;
-                    }
-                }
-            }
-            max_score
-        } else {
-            let mut min_score = i32::MAX;
-
-            for col in valid_moves {
-                if let Some(row) = (0..ROWS).rev().find(|&row| game.board[row][col] == EMPTY) {
-                    game.board[row][col] = PLAYER;
-
-                    let score = if game.check_win(PLAYER) {
-                        -self.reward
-                    } else {
-                        self.minimax(game, depth - 1, alpha, beta, true)
-                    };
-                    game.board[row][col] = EMPTY;
-                    min_score = min(min_score, score);
-                    beta = min(beta, score);
-                    if beta <= alpha {
-                        break;
-                    }
-                }
+    // The moves a game starts from: a balanced opening with --random-start.
+    fn start(&self) -> Result<String, Box<dyn Error>> {
+        match self.random_start {
+            Some(plies) => {
+                eprintln!("{}", tr(Msg::FindingOpening, &[]));
+                Ok(tune::balanced_opening(
+                    self.rows,
+                    self.cols,
+                    plies,
+                    self.hash,
+                    &mut rand::rng(),
+                )?)
             }
-            min_score
+            None => Ok(String::new()),
         }
     }
 
-    fn get_best_move(&mut self, game: &ConnectFour) -> Option<usize> {
-        let mut game_clone = game.clone();
-        let mut best_score = i32::MIN;
-        let mut best_moves: Vec<usize> = Vec::new();
+    // An empty board played under the chosen variant.
+    fn board(&self) -> ConnectFour {
+        ConnectFour::new(self.rows, self.cols).with_rules(self.rules.clone())
+    }
 
-        for col in game.get_valid_moves() {
-            if let Some(row) = (0..ROWS)
-                .rev()
-                .find(|&row| game_clone.board[row][col] == EMPTY)
-            {
-                game_clone.board[row][col] = BOT;
+    // The rules screen for games played with these settings.
+    fn rules(&self) -> String {
+        let gravity = match self.variant {
+            Variant::Standard => tr(Msg::RulesStandard, &[]),
+            Variant::AntiGravity => tr(Msg::RulesAntiGravity, &[]),
+            Variant::FlipGravity => {
+                tr(Msg::RulesFlipGravity, &[&self.flip_every, &self.flip_every])
+            }
+        };
+        let first = tr(
+            match self.first {
+                First::Human => Msg::RulesFirstHuman,
+                First::Bot => Msg::RulesFirstBot,
+                First::Random => Msg::RulesFirstRandom,
+            },
+            &[&self.theme.glyph(PLAYER)],
+        );
+        let mut options = Vec::new();
+        if self.swap {
+            options.push(tr(
+                match self.first {
+                    First::Human => Msg::RulesSwap,
+                    First::Bot => Msg::RulesSwapHuman,
+                    First::Random => Msg::RulesSwapEither,
+                },
+                &[],
+            ));
+        }
+        if let Some(handicap) = self.handicap {
+            options.push(tr(
+                match handicap {
+                    Handicap::Center => Msg::RulesHandicapCenter,
+                    Handicap::Tempo => Msg::RulesHandicapTempo,
+                    Handicap::BotCenter => Msg::RulesHandicapBotCenter,
+                },
+                &[],
+            ));
+        }
+        if let Some(limit) = self.blitz {
+            let msg = match self.on_timeout {
+                OnTimeout::Random => Msg::RulesBlitzRandom,
+                OnTimeout::Forfeit => Msg::RulesBlitzForfeit,
+            };
+            options.push(tr(msg, &[&limit.as_secs_f64()]));
+        }
+        if let Some(plies) = self.random_start {
+            options.push(tr(Msg::RulesRandomStart, &[&plies]));
+        }
+        help::rules(self.rows, self.cols, &first, &gravity, &options)
+    }
 
-                let score =
-                    self.minimax(&mut game_clone, self.max_depth, i32::MIN, i32::MAX, false);
-                game_clone.board[row][col] = EMPTY;
+    fn print_review(&self, moves: &str) {
+        match review::report(
+            &mut self.analyst(),
+            &self.board(),
+            moves,
+            self.theme.notation,
+        ) {
+            Ok(report) => print!("{}", report),
+            Err(err) => eprintln!("{}", warning(Msg::CouldNotReview, &err)),
+        }
+    }
+}
 
-                if score > best_score {
-                    best_score = score;
-                    best_moves.clear();
-                    best_moves.push(col);
-                } else if score == best_score {
-                    best_moves.push(col);
-                }
-            }
+// Who won, after how many of their moves, and with which line. A draw by
+// repetition has already been announced.
+fn game_over(
+    theme: &Theme,
+    game: &ConnectFour,
+    moves: &str,
+    winner: Winner,
+    line: Option<[(usize, usize); 4]>,
+) -> String {
+    // Turns alternate in `moves`, passes and swaps included, and the winner
+    // played the last one.
+    let drops = |step| {
+        moves
+            .chars()
+            .rev()
+            .step_by(step)
+            .filter(|&turn| turn != PASS && turn != SWAP)
+            .count()
+    };
+    let msg = match winner {
+        Winner::Draw if game.get_valid_moves().is_empty() => {
+            return tr(Msg::DrawFull, &[&drops(1)]);
         }
-        best_moves.choose(&mut rand::rng()).cloned()
+        Winner::Draw => return tr(Msg::Draw, &[]),
+        Winner::Human => Msg::HumanWins,
+        Winner::Bot => Msg::BotWins,
+    };
+    match line {
+        Some(line) => {
+            let [(row, col), .., (last_row, last_col)] = line;
+            tr(
+                msg,
+                &[
+                    &drops(2),
+                    &theme.cell_name(game, row, col),
+                    &theme.cell_name(game, last_row, last_col),
+                ],
+            )
+        }
+        None => tr(Msg::GameOver, &[]),
     }
 }
 
-fn clear_screen() {
-    print!("\x1B[2J\x1B[H");
+fn warning(msg: Msg, err: &dyn std::fmt::Display) -> String {
+    tr(Msg::Warning, &[&tr(msg, &[err])])
 }
 
-fn main() {
-    let mut game = ConnectFour::new();
-    let mut bot = BotPlayer::new(4);
-    let mut current_player = PLAYER;
+// The history replays transcripts with standard gravity.
+fn can_save(settings: &Settings) -> bool {
+    if settings.variant != Variant::Standard {
+        info!(variant = settings.variant.name(), "variant game not saved");
+        return false;
+    }
+    true
+}
 
-    loop {
-        clear_screen();
-        println!("{}", game);
+fn save_game(settings: &Settings, record: &GameRecord) {
+    if !can_save(settings) {
+        return;
+    }
+    match Database::open_default().and_then(|db| Ok(db.record(record)?)) {
+        Ok(id) => info!(id, "game saved"),
+        Err(err) => {
+            warn!(%err, "could not save game");
+            eprintln!("{}", warning(Msg::CouldNotSave, &err));
+        }
+    }
+}
 
-        if game.check_win(BOT) || game.check_win(PLAYER) {
-            println!("Game over!");
-            break;
+fn game_record(settings: &Settings, started_at: i64, moves: &str, winner: Winner) -> GameRecord {
+    GameRecord {
+        id: 0,
+        started_at,
+        finished_at: history::now(),
+        rows: settings.rows,
+        cols: settings.cols,
+        moves: moves.to_string(),
+        winner,
+        engine: settings.engine.name().to_string(),
+        depth: settings.depth,
+        move_time: settings.move_time.map(|time| time.as_secs_f64()),
+        handicap: settings
+            .handicap
+            .map(|handicap| handicap.name().to_string()),
+        blunder_rate: settings.blunder_rate,
+        match_id: None,
+    }
+}
+
+// Plays a game from the position reached by `start`, a transcript with o first,
+// and returns its record once it is over, for the caller to save. When the bot
+// moves first, the human passes before `start`. With a `script` the human's
+// moves come from it and nothing but the final report is printed; such games
+// are not returned. `heading` is shown above the first prompt.
+fn play(
+    settings: &mut Settings,
+    start: &str,
+    mut script: Option<ScriptedPlayer>,
+    heading: Option<String>,
+) -> Result<Option<GameRecord>, Box<dyn Error>> {
+    let headless = script.is_some();
+    if settings.first == First::Random {
+        settings.first = if rand::rng().random_bool(0.5) {
+            First::Human
+        } else {
+            First::Bot
+        };
+    }
+    if settings.adaptive {
+        match Database::open_default().and_then(|db| adaptive::difficulty(&db)) {
+            Ok((depth, blunder_rate)) => {
+                settings.depth = depth;
+                settings.blunder_rate = blunder_rate;
+            }
+            Err(err) => eprintln!("{}", warning(Msg::CouldNotAdapt, &err)),
         }
-        if game.get_valid_moves().is_empty() {
-            println!("Draw!");
-            break;
+    }
+    let mut moves = match (settings.handicap, settings.first) {
+        (Some(handicap), _) => handicap.setup(settings.cols),
+        (None, First::Bot) => format!("{}{}", PASS, start),
+        (None, _) => start.to_string(),
+    };
+    let mut game = settings
+        .board()
+        .replay(&moves, PLAYER)
+        .expect("starting position fits the board");
+    let mut current_player = if moves.len().is_multiple_of(2) {
+        PLAYER
+    } else {
+        BOT
+    };
+    let started_at = history::now();
+    // Found from the last move once the game is under way.
+    let mut winner = match game.status() {
+        GameStatus::Won { player: BOT, .. } => Some(Winner::Bot),
+        GameStatus::Won { .. } => Some(Winner::Human),
+        GameStatus::Draw => Some(Winner::Draw),
+        GameStatus::InProgress => None,
+    };
+    let mut history = PositionHistory::default();
+    history.record(&game, current_player);
+    let mut notice = heading;
+    let mut observers = Observers::default();
+    observers.register(Box::new(Log));
+    if !headless {
+        observers.register(Box::new(settings.sound.clone()));
+    }
+    let observers = RefCell::new(observers);
+    let explain = settings.explain.then_some(settings.theme.notation);
+    let mut bot = settings.bot().with_explain(explain);
+    let mut stop = bot.stop_flag();
+    // `set` changes the settings while the human holds on to the theme.
+    let theme = settings.theme.clone();
+    let make_human = |settings: &Settings| {
+        HumanCliPlayer::new(&theme, settings.rules())
+            .with_coach(settings.analyst(), settings.coach)
+            .with_blitz(settings.blitz, settings.on_timeout)
+    };
+    let mut human = make_human(settings);
+    let _span = info_span!("game", rows = settings.rows, cols = settings.cols).entered();
+    info!(
+        depth = settings.depth,
+        engine = ?settings.engine,
+        eval = settings.eval.name(),
+        personality = settings.personality.map(Personality::name),
+        bot_first = settings.first == First::Bot,
+        handicap = settings.handicap.map(Handicap::name),
+        variant = settings.variant.name(),
+        "game started"
+    );
+
+    let finished = loop {
+        let line = match winner {
+            Some(Winner::Human) => game.winning_line(PLAYER),
+            Some(Winner::Bot) => game.winning_line(BOT),
+            _ => None,
+        };
+        if !headless {
+            clear_screen();
+            println!("{}", settings.theme.render_with_line(&game, line.as_ref()));
+            if let Some(notice) = notice.take() {
+                println!("{}", notice);
+            }
         }
 
-        if current_player == BOT {
-            if let Some(col) = bot.get_best_move(&game) {
-                game.drop_piece(col, BOT);
+        if let Some(winner) = winner {
+            observers.borrow_mut().on_game_end(&game, winner);
+            if headless {
+                let board = settings.board();
+                headless::report(
+                    &mut settings.analyst(),
+                    &board,
+                    &game,
+                    &moves,
+                    Some(winner),
+                    current_player,
+                )?;
+                break None;
             }
+            // The handicap's pieces were placed, not played.
+            let setup = settings
+                .handicap
+                .map_or(0, |handicap| handicap.setup(settings.cols).len());
+            println!(
+                "{}",
+                game_over(&settings.theme, &game, &moves[setup..], winner, line)
+            );
+            break Some(end_game(settings, started_at, &moves, winner));
+        }
+        if current_player == BOT && settings.handicap == Some(Handicap::Tempo) && moves.len() == 1 {
+            info!(player = "bot", "move skipped");
+            moves.push(PASS);
+            notice = Some(tr(Msg::BotSkips, &[]));
             current_player = PLAYER;
+            history.record(&game, current_player);
             continue;
         }
 
-        println!("Enter column number (1-7):");
-        let mut input = String::new();
-        stdin().read_line(&mut input).unwrap();
+        let player: &mut dyn Player = match &mut script {
+            _ if current_player == BOT => &mut bot,
+            Some(script) => script,
+            None => &mut human,
+        };
+        let progress = |analysis: &Analysis| observers.borrow_mut().on_search_progress(analysis);
+        let choice = {
+            let _interruptible =
+                (current_player == BOT).then(|| StopOnInterrupt::new(stop.clone()));
+            player.choose_move(
+                &GameView::new(&game, &moves, current_player)
+                    .with_swap(settings.swap && can_swap(&moves))
+                    .with_progress(&progress),
+            )
+        };
+        // The bot has answered a Ctrl-C in its turn, in the swap offer or the
+        // search after it, with this move, so its next turn starts afresh.
+        stop.store(false, Ordering::Relaxed);
+        notice = player.comment();
+        let changes = human.take_settings();
+        if !changes.is_empty() {
+            for setting in changes {
+                if let Err(err) = settings.set(setting) {
+                    notice = Some(err);
+                }
+            }
+            // The coach judges with the bot's settings too.
+            bot = settings.bot().with_explain(explain);
+            stop = bot.stop_flag();
+            human = make_human(settings);
+        }
+        match choice {
+            Move::Drop(col) => {
+                let won = game.winning_move(col, current_player);
+                game.drop_piece(col, current_player);
+                moves.push(column_char(col));
+                observers.borrow_mut().on_move(&game, current_player, col);
+                winner = match (won, current_player) {
+                    (true, BOT) => Some(Winner::Bot),
+                    (true, _) => Some(Winner::Human),
+                    (false, _) if game.get_valid_moves().is_empty() => Some(Winner::Draw),
+                    (false, _) => None,
+                };
+                if winner.is_none() && history.record(&game, -current_player) {
+                    info!("draw by repetition");
+                    notice = Some(tr(Msg::DrawRepetition, &[]));
+                    winner = Some(Winner::Draw);
+                }
+            }
+            Move::Swap => {
+                let (side, msg) = if current_player == BOT {
+                    ("bot", Msg::BotSwaps)
+                } else {
+                    ("human", Msg::HumanSwaps)
+                };
+                info!(player = side, "swap played");
+                game = game.swapped();
+                moves.push(SWAP);
+                notice = Some(tr(msg, &[]));
+                if history.record(&game, -current_player) {
+                    info!("draw by repetition");
+                    notice = Some(tr(Msg::DrawRepetition, &[]));
+                    winner = Some(Winner::Draw);
+                }
+            }
+            Move::Resign => {
+                let winner = if current_player == BOT {
+                    Winner::Human
+                } else {
+                    Winner::Bot
+                };
+                observers.borrow_mut().on_game_end(&game, winner);
+                if headless {
+                    let board = settings.board();
+                    headless::report(
+                        &mut settings.analyst(),
+                        &board,
+                        &game,
+                        &moves,
+                        Some(winner),
+                        current_player,
+                    )?;
+                    break None;
+                }
+                if let Some(notice) = notice.take() {
+                    println!("{}", notice);
+                }
+                break Some(end_game(settings, started_at, &moves, winner));
+            }
+            Move::Quit => {
+                if let Some(script) = &mut script {
+                    if let Some(err) = script.take_error() {
+                        return Err(err.into());
+                    }
+                    let board = settings.board();
+                    headless::report(
+                        &mut settings.analyst(),
+                        &board,
+                        &game,
+                        &moves,
+                        None,
+                        current_player,
+                    )?;
+                }
+                info!("game abandoned");
+                break None;
+            }
+        }
+        current_player = -current_player;
+    };
+    Ok(finished)
+}
 
-        if let Ok(col) = input.trim().parse::<usize>() {
-            let col = col - 1;
-            if game.get_valid_moves().contains(&col) {
-                game.drop_piece(col, PLAYER);
-                current_player = BOT;
+// Plays a best-of-`best_of` match, the side moving first changing every game,
+// until one side is sure of winning it. The games played are saved together
+// as one match, which is left without a winner if the human stops early.
+fn play_match(settings: &mut Settings, best_of: u32) -> Result<(), Box<dyn Error>> {
+    if settings.handicap.is_some() {
+        return Err(tr(Msg::MatchHandicap, &[]).into());
+    }
+    let mut first = match settings.first {
+        First::Random if rand::rng().random_bool(0.5) => First::Bot,
+        First::Random => First::Human,
+        first => first,
+    };
+    let mut records = Vec::new();
+    // A draw gives each side half a point.
+    let (mut human, mut bot) = (0.0, 0.0);
+    let needed = best_of as f64 / 2.0;
+    info!(best_of, "match started");
+    for number in 1..=best_of {
+        settings.first = first;
+        let start = settings.start()?;
+        let heading = tr(Msg::MatchGame, &[&number, &best_of, &human, &bot]);
+        let Some(record) = play(settings, &start, None, Some(heading))? else {
+            break;
+        };
+        match record.winner {
+            Winner::Human => human += 1.0,
+            Winner::Bot => bot += 1.0,
+            Winner::Draw => {
+                human += 0.5;
+                bot += 0.5;
             }
         }
+        records.push(record);
+        println!("{}", tr(Msg::MatchScore, &[&human, &bot]));
+        if human > needed || bot > needed || number == best_of {
+            break;
+        }
+        let msg = if io::stdin().is_terminal() {
+            Msg::MatchNextKey
+        } else {
+            Msg::MatchNextEnter
+        };
+        println!("{}", tr(msg, &[]));
+        if !input::wait_for_key() {
+            break;
+        }
+        first = match first {
+            First::Bot => First::Human,
+            _ => First::Bot,
+        };
+    }
+    let played = records.len() as u32;
+    let winner = if human > needed {
+        Some(Winner::Human)
+    } else if bot > needed {
+        Some(Winner::Bot)
+    } else if played == best_of {
+        Some(Winner::Draw)
+    } else {
+        None
+    };
+    let msg = match winner {
+        Some(Winner::Human) => Msg::MatchWon,
+        Some(Winner::Bot) => Msg::MatchLost,
+        Some(Winner::Draw) => Msg::MatchDrawn,
+        None => Msg::MatchStopped,
+    };
+    println!("{}", tr(msg, &[&human, &bot]));
+    info!(
+        games = played,
+        winner = winner.map(Winner::as_str),
+        "match finished"
+    );
+    if records.is_empty() || !can_save(settings) {
+        return Ok(());
+    }
+    match Database::open_default().and_then(|db| Ok(db.record_match(best_of, winner, &records)?)) {
+        Ok(id) => info!(id, "match saved"),
+        Err(err) => {
+            warn!(%err, "could not save match");
+            eprintln!("{}", warning(Msg::CouldNotSave, &err));
+        }
+    }
+    Ok(())
+}
+
+// Shows the review of a finished game, if asked for, and returns its record.
+fn end_game(settings: &Settings, started_at: i64, moves: &str, winner: Winner) -> GameRecord {
+    if settings.review {
+        settings.print_review(moves);
+    }
+    game_record(settings, started_at, moves, winner)
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let options = &cli.options;
+    logging::init(options.log_level, options.log_file.as_deref())?;
+    terminal::install_shutdown_hook();
+    let config = config::load(options.config.as_deref())?;
+    i18n::set_lang(options.lang.or(config.lang).unwrap_or_else(Lang::from_env));
+    let mut settings = Settings::resolve(options, config)?;
+
+    match &cli.command {
+        None if options.best_of.is_some() => {
+            play_match(&mut settings, options.best_of.unwrap_or(1))?
+        }
+        None => {
+            let start = settings.start()?;
+            let script = options
+                .headless
+                .then(|| headless::read_script(options.moves.as_deref(), settings.cols))
+                .transpose()?;
+            if let Some(record) = play(&mut settings, &start, script, None)? {
+                save_game(&settings, &record);
+            }
+        }
+        Some(Command::Puzzle { file, action }) => match action {
+            None => {
+                let theme = settings.theme.clone();
+                puzzle::run(
+                    file.as_deref(),
+                    &mut settings,
+                    |settings| solver::Solver::new(settings.hash),
+                    &theme,
+                )?
+            }
+            Some(PuzzleCommand::Generate {
+                count,
+                games,
+                min_moves,
+                max_moves,
+                output,
+            }) => puzzle::generate(
+                &mut settings.bot(),
+                &puzzle::GenerateOptions {
+                    count: *count,
+                    games: *games,
+                    min_moves: *min_moves,
+                    max_moves: *max_moves,
+                    output: output.clone(),
+                    hash_mb: settings.hash,
+                },
+            )?,
+        },
+        Some(Command::Eval { position, json }) => analysis::run_eval(
+            &mut settings.bot(),
+            settings.rows,
+            settings.cols,
+            position,
+            *json,
+            settings.theme.notation,
+        )?,
+        Some(Command::Solve {
+            input,
+            output,
+            jobs,
+            search,
+        }) => batch::run(
+            &batch::SolveOptions {
+                input: input.clone(),
+                output: output.clone(),
+                jobs: jobs.unwrap_or_else(|| {
+                    thread::available_parallelism().map_or(1, |jobs| jobs.get())
+                }),
+                search: *search,
+                rows: settings.rows,
+                cols: settings.cols,
+                hash_mb: settings.hash,
+                tablebase: settings.tablebase.clone(),
+            },
+            || settings.bot(),
+        )?,
+        Some(Command::Explorer { position }) => explorer::run(
+            &Database::open_default()?,
+            settings.rows,
+            settings.cols,
+            position,
+            &settings.theme,
+        )?,
+        Some(Command::Rules) => print!("{}", settings.rules()),
+        Some(Command::Edit) if settings.variant != Variant::Standard => {
+            return Err(tr(Msg::EditorOnlyStandard, &[]).into());
+        }
+        Some(Command::Edit) => match editor::run(settings.rows, settings.cols, &settings.theme) {
+            editor::Outcome::Play(moves) => {
+                // A handicap only applies to games from the empty board, and
+                // the position says who is to move.
+                settings.handicap = None;
+                settings.first = First::Human;
+                if let Some(record) = play(&mut settings, &moves, None, None)? {
+                    save_game(&settings, &record);
+                }
+            }
+            editor::Outcome::Analyze(moves) => analysis::run_eval(
+                &mut settings.bot(),
+                settings.rows,
+                settings.cols,
+                &moves,
+                false,
+                settings.theme.notation,
+            )?,
+            editor::Outcome::Quit => {}
+        },
+        Some(Command::Tablebase { min_pieces, output }) => {
+            let start = Instant::now();
+            let tablebase = Tablebase::generate(settings.cols, settings.rows, *min_pieces)?;
+            tablebase.save(output)?;
+            eprintln!(
+                "{}",
+                tr(
+                    Msg::WroteTablebase,
+                    &[
+                        &tablebase.len(),
+                        &tablebase.min_pieces(),
+                        &output.display(),
+                        &format!("{:.1}", start.elapsed().as_secs_f64())
+                    ]
+                )
+            );
+        }
+        Some(Command::Book {
+            plies,
+            output,
+            jobs,
+        }) => {
+            let start = Instant::now();
+            let jobs = jobs
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+            let positions = Book::generate(
+                settings.cols,
+                settings.rows,
+                *plies,
+                jobs,
+                settings.hash,
+                output,
+            )?;
+            eprintln!(
+                "{}",
+                tr(
+                    Msg::WroteBook,
+                    &[
+                        &positions,
+                        plies,
+                        &output.display(),
+                        &format!("{:.1}", start.elapsed().as_secs_f64())
+                    ]
+                )
+            );
+        }
+        Some(Command::Tune {
+            iterations,
+            games,
+            output,
+        }) => tune::run(
+            || settings.bot(),
+            &tune::TuneOptions {
+                initial: settings.weights,
+                iterations: *iterations,
+                games: *games,
+                rows: settings.rows,
+                cols: settings.cols,
+                hash_mb: settings.hash,
+                output: output.clone(),
+            },
+        )?,
+        Some(Command::Engine { json: false }) => {
+            let (rows, cols) = (settings.rows, settings.cols);
+            protocol::serve(&mut settings, Settings::bot, rows, cols)?
+        }
+        Some(Command::Engine { json: true }) => {
+            protocol::serve_json(|| settings.bot(), settings.rows, settings.cols)?
+        }
+        Some(Command::Server {
+            listen,
+            db,
+            season_days,
+        }) => server::run(
+            &|| settings.bot(),
+            &|| settings.analyst(),
+            &server::ServerOptions {
+                listen: listen.clone(),
+                db: db.clone(),
+                rows: settings.rows,
+                cols: settings.cols,
+                season_days: *season_days,
+            },
+        )?,
+        #[cfg(feature = "grpc")]
+        Some(Command::Grpc {
+            listen,
+            idle_timeout,
+            max_searches,
+        }) => {
+            let options = grpc::GrpcOptions {
+                listen: listen.clone(),
+                rows: settings.rows,
+                cols: settings.cols,
+                depth: settings.depth,
+                hash: settings.hash,
+                rules: settings.rules.clone(),
+                idle_timeout: Duration::from_secs(*idle_timeout),
+                searches: max_searches.unwrap_or_else(|| {
+                    thread::available_parallelism().map_or(1, |jobs| jobs.get())
+                }),
+            };
+            grpc::run(Arc::new(move |depth| settings.bot_at(depth)), options)?
+        }
+        Some(Command::Match {
+            first,
+            second,
+            elo0,
+            elo1,
+            alpha,
+            beta,
+            max_games,
+        }) => sprt::run(
+            || settings.bot(),
+            &sprt::MatchOptions {
+                first: first.clone(),
+                second: second.clone(),
+                elo0: *elo0,
+                elo1: *elo1,
+                alpha: *alpha,
+                beta: *beta,
+                max_games: *max_games,
+                rows: settings.rows,
+                cols: settings.cols,
+            },
+        )?,
+        Some(Command::Calibrate {
+            min_depth,
+            max_depth,
+            openings,
+        }) => calibrate::run(
+            |depth| settings.bot_at(depth),
+            &calibrate::CalibrateOptions {
+                min_depth: *min_depth,
+                max_depth: *max_depth,
+                openings: openings.clone(),
+                rows: settings.rows,
+                cols: settings.cols,
+                hash_mb: settings.hash,
+            },
+        )?,
+        Some(Command::Stats) => stats::run(&Database::open_default()?, settings.hash)?,
+        Some(Command::Export { id, format, output }) => {
+            let record = Database::open_default()?
+                .get(*id)?
+                .ok_or_else(|| tr(Msg::NoGameWithId, &[id]))?;
+            let path = output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(format!("game-{}.{}", id, format.extension())));
+            let data = match format {
+                export::Format::Svg => export::svg(&record)?.into_bytes(),
+                export::Format::Gif => export::gif(&record)?,
+            };
+            fs::write(&path, data).map_err(|err| format!("{}: {}", path.display(), err))?;
+            eprintln!("{}", tr(Msg::WroteFile, &[&path.display()]));
+        }
+        Some(Command::History { action }) => {
+            let db = Database::open_default()?;
+            match action {
+                HistoryCommand::List {
+                    winner,
+                    engine,
+                    depth,
+                    since,
+                    limit,
+                } => history::run_list(
+                    &db,
+                    &history::Filter {
+                        winner: *winner,
+                        engine: engine.map(|engine| engine.name().to_string()),
+                        depth: *depth,
+                        board: None,
+                        since: since.clone(),
+                        limit: Some(*limit),
+                    },
+                )?,
+                HistoryCommand::Review { id } => {
+                    let record = db.get(*id)?.ok_or_else(|| tr(Msg::NoGameWithId, &[id]))?;
+                    print!(
+                        "{}",
+                        review::report(
+                            &mut settings.analyst(),
+                            &ConnectFour::new(record.rows, record.cols),
+                            &record.moves,
+                            settings.theme.notation
+                        )?
+                    );
+                }
+                HistoryCommand::Open { id, ply } => {
+                    history::run_open(&db, *id, *ply, &settings.theme)?
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", tr(Msg::Error, &[&err]));
+        process::exit(1);
     }
 }
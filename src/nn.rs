@@ -0,0 +1,125 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::game::{ConnectFour, EMPTY};
+
+// A fully connected layer, with one row of input weights per output.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    weights: Vec<Vec<f32>>,
+    biases: Vec<f32>,
+}
+
+impl Layer {
+    fn check(&self, name: &str, inputs: usize, outputs: Option<usize>) -> Result<(), String> {
+        if self.weights.len() != self.biases.len() {
+            return Err(format!(
+                "{}: {} rows of weights but {} biases",
+                name,
+                self.weights.len(),
+                self.biases.len()
+            ));
+        }
+        if let Some(outputs) = outputs
+            && self.biases.len() != outputs
+        {
+            return Err(format!(
+                "{}: expected {} outputs, found {}",
+                name,
+                outputs,
+                self.biases.len()
+            ));
+        }
+        match self.weights.iter().position(|row| row.len() != inputs) {
+            Some(i) => Err(format!(
+                "{}: row {} has {} weights, expected {}",
+                name,
+                i + 1,
+                self.weights[i].len(),
+                inputs
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights
+            .iter()
+            .zip(&self.biases)
+            .map(|(row, bias)| bias + row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>())
+            .collect()
+    }
+}
+
+// A small policy/value network, trained elsewhere and read from JSON:
+//
+//   {"rows": 6, "cols": 7, "hidden": [layer, ...], "value": layer, "policy": layer}
+//
+// where a layer is {"weights": [[...], ...], "biases": [...]}. The input has
+// one plane of rows * cols cells for the pieces of the side to move and
+// another for the opponent's, each row by row from the top, 1.0 where a piece
+// is. Hidden layers use ReLU. The value head has a single output, squashed
+// with tanh into the outlook for the side to move; the policy head has one
+// logit per column.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Network {
+    rows: usize,
+    cols: usize,
+    hidden: Vec<Layer>,
+    value: Layer,
+    policy: Layer,
+}
+
+impl Network {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text =
+            fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let network: Network =
+            serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        network
+            .check()
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        Ok(network)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let mut inputs = 2 * self.rows * self.cols;
+        for (i, layer) in self.hidden.iter().enumerate() {
+            layer.check(&format!("hidden layer {}", i + 1), inputs, None)?;
+            inputs = layer.biases.len();
+        }
+        self.value.check("value head", inputs, Some(1))?;
+        self.policy.check("policy head", inputs, Some(self.cols))
+    }
+
+    // The board size the network was trained for, as (rows, cols).
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    // The outlook for `to_move`, from -1 (lost) to 1 (won), and a logit for
+    // each column. `game` must have the network's size.
+    pub fn evaluate(&self, game: &ConnectFour, to_move: i8) -> (f32, Vec<f32>) {
+        let cells = self.rows * self.cols;
+        let mut input = vec![0.0; 2 * cells];
+        for (i, &cell) in game.board.iter().flatten().enumerate() {
+            if cell != EMPTY {
+                input[if cell == to_move { i } else { cells + i }] = 1.0;
+            }
+        }
+        for layer in &self.hidden {
+            input = layer.forward(&input);
+            for x in &mut input {
+                *x = x.max(0.0);
+            }
+        }
+        (
+            self.value.forward(&input)[0].tanh(),
+            self.policy.forward(&input),
+        )
+    }
+}
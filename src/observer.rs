@@ -0,0 +1,70 @@
+use tracing::info;
+
+use crate::bot::Analysis;
+use crate::game::{BOT, ConnectFour};
+use crate::history::Winner;
+
+// Told what happens in a game as it is played, so that front-ends can follow
+// it without the game loop knowing about them. Every method does nothing
+// unless overridden.
+pub trait Observer {
+    // `piece` was dropped in `col`; `game` is the board after the move.
+    fn on_move(&mut self, _game: &ConnectFour, _piece: i8, _col: usize) {}
+
+    // The bot finished another depth of the search for its next move.
+    fn on_search_progress(&mut self, _analysis: &Analysis) {}
+
+    // Also called for games lost on time, so `game` need not be over; its
+    // `status` has the winning line otherwise.
+    fn on_game_end(&mut self, _game: &ConnectFour, _winner: Winner) {}
+}
+
+// The observers registered with a game, told of each event in turn.
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Box<dyn Observer>>,
+}
+
+impl Observers {
+    pub fn register(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+}
+
+impl Observer for Observers {
+    fn on_move(&mut self, game: &ConnectFour, piece: i8, col: usize) {
+        for observer in &mut self.observers {
+            observer.on_move(game, piece, col);
+        }
+    }
+
+    fn on_search_progress(&mut self, analysis: &Analysis) {
+        for observer in &mut self.observers {
+            observer.on_search_progress(analysis);
+        }
+    }
+
+    fn on_game_end(&mut self, game: &ConnectFour, winner: Winner) {
+        for observer in &mut self.observers {
+            observer.on_game_end(game, winner);
+        }
+    }
+}
+
+// Writes the game's moves and result to the log; the search logs its own
+// progress.
+pub struct Log;
+
+impl Observer for Log {
+    fn on_move(&mut self, _game: &ConnectFour, piece: i8, col: usize) {
+        let player = if piece == BOT { "bot" } else { "human" };
+        info!(player, col = col + 1, "move played");
+    }
+
+    fn on_game_end(&mut self, _game: &ConnectFour, winner: Winner) {
+        match winner {
+            Winner::Draw => info!("game drawn"),
+            _ => info!(winner = winner.as_str(), "game over"),
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::bot::{Analysis, BotPlayer};
+use crate::coach::{self, Strictness};
+use crate::engine_options::Setting;
+use crate::game::{BOT, ConnectFour, GameStatus, OnTimeout, PLAYER, PositionHistory, column_char};
+use crate::help;
+use crate::i18n::{Msg, tr};
+use crate::input::{self, Input};
+use crate::json;
+use crate::protocol::ExternalEngine;
+use crate::terminal::clear_screen;
+use crate::theme::Theme;
+
+// What a player is shown when asked for a move.
+pub struct GameView<'a> {
+    pub game: &'a ConnectFour,
+    // The transcript so far, with o first.
+    pub moves: &'a str,
+    // The piece the player moves with.
+    pub piece: i8,
+    // Whether the pie rule lets the player take over the first piece.
+    pub can_swap: bool,
+    // Handed every depth a search finishes, for observers.
+    pub progress: &'a dyn Fn(&Analysis),
+}
+
+fn ignore(_: &Analysis) {}
+
+impl<'a> GameView<'a> {
+    pub fn new(game: &'a ConnectFour, moves: &'a str, piece: i8) -> Self {
+        Self {
+            game,
+            moves,
+            piece,
+            can_swap: false,
+            progress: &ignore,
+        }
+    }
+
+    pub fn with_swap(mut self, can_swap: bool) -> Self {
+        self.can_swap = can_swap;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: &'a dyn Fn(&Analysis)) -> Self {
+        self.progress = progress;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Move {
+    Drop(#[serde(with = "json::column")] usize),
+    // Take over the opponent's first piece under the pie rule.
+    Swap,
+    // Give the game up, as on running out of time or playing an illegal move.
+    Resign,
+    // Leave without a result.
+    Quit,
+}
+
+// Anything that can take a side in a game. Drivers ask whoever is to move and
+// play the answer, so they need not know who sits at the board.
+pub trait Player {
+    fn choose_move(&mut self, view: &GameView) -> Move;
+
+    // A line about the move just chosen, for the driver to show.
+    fn comment(&mut self) -> Option<String> {
+        None
+    }
+}
+
+// How a game played out by `play_out` ended.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won(i8),
+    Draw,
+    Quit,
+}
+
+// Plays on from `moves` with the first player moving as PLAYER and the second
+// as BOT, without a pie rule. A player that resigns loses.
+pub fn play_out(
+    players: [&mut dyn Player; 2],
+    rows: usize,
+    cols: usize,
+    moves: &str,
+) -> Result<Outcome, String> {
+    let mut game = ConnectFour::from_moves(rows, cols, moves, PLAYER)?;
+    let mut moves = moves.to_string();
+    let [first, second] = players;
+    match game.status() {
+        GameStatus::Won { player, .. } => return Ok(Outcome::Won(player)),
+        GameStatus::Draw => return Ok(Outcome::Draw),
+        GameStatus::InProgress => {}
+    }
+    let mut history = PositionHistory::default();
+    loop {
+        let piece = if moves.len().is_multiple_of(2) {
+            PLAYER
+        } else {
+            BOT
+        };
+        if history.record(&game, piece) {
+            return Ok(Outcome::Draw);
+        }
+        let player = if piece == PLAYER {
+            &mut *first
+        } else {
+            &mut *second
+        };
+        match player.choose_move(&GameView::new(&game, &moves, piece)) {
+            Move::Drop(col) if game.get_valid_moves().contains(&col) => {
+                if game.winning_move(col, piece) {
+                    return Ok(Outcome::Won(piece));
+                }
+                game.drop_piece(col, piece);
+                moves.push(column_char(col));
+                if game.get_valid_moves().is_empty() {
+                    return Ok(Outcome::Draw);
+                }
+            }
+            Move::Drop(_) | Move::Swap | Move::Resign => return Ok(Outcome::Won(-piece)),
+            Move::Quit => return Ok(Outcome::Quit),
+        }
+    }
+}
+
+// The human at the terminal, or at the other end of a pipe.
+pub struct HumanCliPlayer<'a> {
+    theme: &'a Theme,
+    // The rules screen shown on request.
+    rules: String,
+    coach: Option<(BotPlayer, Strictness)>,
+    blitz: Option<Duration>,
+    on_timeout: OnTimeout,
+    comment: Option<String>,
+    // Settings changed with `set`, for the driver to apply before the bot moves.
+    settings: Vec<Setting>,
+}
+
+impl<'a> HumanCliPlayer<'a> {
+    pub fn new(theme: &'a Theme, rules: String) -> Self {
+        Self {
+            theme,
+            rules,
+            coach: None,
+            blitz: None,
+            on_timeout: OnTimeout::Random,
+            comment: None,
+            settings: Vec::new(),
+        }
+    }
+
+    // `coach` judges the moves chosen, warning about them as strictly as asked.
+    pub fn with_coach(mut self, coach: BotPlayer, strictness: Option<Strictness>) -> Self {
+        self.coach = strictness.map(|strictness| (coach, strictness));
+        self
+    }
+
+    pub fn with_blitz(mut self, blitz: Option<Duration>, on_timeout: OnTimeout) -> Self {
+        self.blitz = blitz;
+        self.on_timeout = on_timeout;
+        self
+    }
+
+    pub fn take_settings(&mut self) -> Vec<Setting> {
+        std::mem::take(&mut self.settings)
+    }
+
+    fn redraw(&self, game: &ConnectFour, notice: Option<String>) {
+        clear_screen();
+        println!("{}", self.theme.render(game));
+        if let Some(notice) = notice {
+            println!("{}", notice);
+        }
+    }
+}
+
+impl Player for HumanCliPlayer<'_> {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        let game = view.game;
+        let deadline = self.blitz.map(|limit| Instant::now() + limit);
+        // A move the coach warned about, played if chosen again.
+        let mut warned = None;
+        // The driver has drawn the board for the first prompt.
+        let mut notice = None;
+        let mut redraw = false;
+        loop {
+            if redraw {
+                self.redraw(game, notice.take());
+            }
+            redraw = true;
+            if view.can_swap {
+                println!("{}", tr(Msg::SwapOffer, &[]));
+            }
+            let input = match deadline {
+                Some(deadline) => input::read_column_until(game, deadline),
+                None => Some(input::read_column(game)),
+            };
+            let Some(input) = input else {
+                return match self.on_timeout {
+                    OnTimeout::Random => {
+                        let col = *game
+                            .get_valid_moves()
+                            .choose(&mut rand::rng())
+                            .expect("the board is not full");
+                        info!("time out, random move played");
+                        self.comment =
+                            Some(tr(Msg::TimeRandomMove, &[&self.theme.column_label(col)]));
+                        Move::Drop(col)
+                    }
+                    OnTimeout::Forfeit => {
+                        info!("game lost on time");
+                        self.comment = Some(tr(Msg::TimeLost, &[]));
+                        Move::Resign
+                    }
+                };
+            };
+            match input {
+                Input::Column(col) if game.get_valid_moves().contains(&col) => {
+                    if let Some((coach, strictness)) = &mut self.coach
+                        && warned != Some(col)
+                        && let Some(warning) =
+                            coach::review(coach, game, col, *strictness, self.theme.notation)
+                    {
+                        info!(col = col + 1, "coach warning");
+                        notice = Some(warning);
+                        warned = Some(col);
+                        continue;
+                    }
+                    return Move::Drop(col);
+                }
+                Input::Help | Input::Rules => {
+                    let terminal = io::stdin().is_terminal();
+                    clear_screen();
+                    match input {
+                        Input::Help => {
+                            print!("{}", help::commands(game.cols, terminal, view.can_swap))
+                        }
+                        _ => print!("{}", self.rules),
+                    }
+                    let msg = if terminal {
+                        Msg::PressKey
+                    } else {
+                        Msg::PressEnter
+                    };
+                    println!("\n{}", tr(msg, &[]));
+                    if !input::wait_for_key() {
+                        return Move::Quit;
+                    }
+                }
+                Input::Set(line) => match Setting::parse(&line) {
+                    Ok(setting) => {
+                        notice = Some(tr(
+                            Msg::SettingChanged,
+                            &[&setting.name(), &setting.value()],
+                        ));
+                        self.settings.push(setting);
+                    }
+                    Err(err) => notice = Some(err),
+                },
+                Input::Swap if view.can_swap => return Move::Swap,
+                Input::Quit => return Move::Quit,
+                Input::Column(_) | Input::Swap | Input::Back | Input::Invalid | Input::Redraw => {}
+            }
+        }
+    }
+
+    fn comment(&mut self) -> Option<String> {
+        self.comment.take()
+    }
+}
+
+// An engine in another process, asked for its moves over the engine protocol.
+pub struct RemotePlayer {
+    engine: ExternalEngine,
+    error: Option<String>,
+}
+
+impl RemotePlayer {
+    pub fn new(engine: ExternalEngine) -> Self {
+        Self {
+            engine,
+            error: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.engine.name()
+    }
+
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.engine.new_game()
+    }
+
+    // Why the engine quit, if it stopped answering.
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+}
+
+impl Player for RemotePlayer {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        match self.engine.best_move(view.moves) {
+            Ok(Some(col)) if view.game.get_valid_moves().contains(&col) => Move::Drop(col),
+            Ok(_) => {
+                info!(engine = self.engine.name(), moves = %view.moves, "illegal move, game forfeited");
+                Move::Resign
+            }
+            Err(err) => {
+                self.error = Some(err);
+                Move::Quit
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// Invariants of the board and the search, checked on random legal games.
+use proptest::prelude::*;
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::OnceLock;
+
+use crate::book::Book;
+use crate::bot::{BotPlayer, Engine, MAX_DEPTH, Personality, Weights};
+use crate::engine_options::Setting;
+use crate::game::{BOT, ConnectFour, EMPTY, MAX_COLS, PLAYER, column_char};
+use crate::rules::{DEFAULT_FLIP_EVERY, Variant};
+use crate::solver::{Position, Solver};
+
+// A random legal game on a random board: each choice picks among the columns
+// still open, and play stops once the game is over.
+#[derive(Debug)]
+struct Game {
+    rows: usize,
+    cols: usize,
+    moves: String,
+    // 0-based, in order.
+    cols_played: Vec<usize>,
+}
+
+fn games(variant: Variant) -> impl Strategy<Value = Game> {
+    (
+        4..=8usize,
+        4..=MAX_COLS,
+        prop::collection::vec(any::<usize>(), 0..=72),
+    )
+        .prop_map(move |(rows, cols, choices)| {
+            let mut game =
+                ConnectFour::new(rows, cols).with_rules(variant.rules(DEFAULT_FLIP_EVERY));
+            let mut moves = String::new();
+            let mut cols_played = Vec::new();
+            let mut piece = PLAYER;
+            for choice in choices {
+                let valid = game.get_valid_moves();
+                if valid.is_empty() || game.check_win(-piece) {
+                    break;
+                }
+                let col = valid[choice % valid.len()];
+                game.drop_piece(col, piece);
+                moves.push(column_char(col));
+                cols_played.push(col);
+                piece = -piece;
+            }
+            Game {
+                rows,
+                cols,
+                moves,
+                cols_played,
+            }
+        })
+}
+
+fn variants() -> impl Strategy<Value = Variant> {
+    prop_oneof![
+        Just(Variant::Standard),
+        Just(Variant::AntiGravity),
+        Just(Variant::FlipGravity),
+    ]
+}
+
+// A game with the variant it was played under.
+fn variant_games() -> impl Strategy<Value = (Variant, Game)> {
+    variants().prop_flat_map(|variant| (Just(variant), games(variant)))
+}
+
+fn replayed(game: &Game, variant: Variant) -> ConnectFour {
+    ConnectFour::new(game.rows, game.cols)
+        .with_rules(variant.rules(DEFAULT_FLIP_EVERY))
+        .replay(&game.moves, PLAYER)
+        .expect("generated games are legal")
+}
+
+// Looks at every cell in every direction, without the bounds the real check
+// relies on.
+fn brute_force_win(game: &ConnectFour, player: i8) -> bool {
+    let (rows, cols) = (game.rows as isize, game.cols as isize);
+    let owned = |row: isize, col: isize| {
+        (0..rows).contains(&row)
+            && (0..cols).contains(&col)
+            && game.board[row as usize][col as usize] == player
+    };
+    (0..rows).any(|row| {
+        (0..cols).any(|col| {
+            [(0, 1), (1, 0), (1, 1), (1, -1)]
+                .iter()
+                .any(|&(dr, dc)| (0..4).all(|i| owned(row + i * dr, col + i * dc)))
+        })
+    })
+}
+
+proptest! {
+    #[test]
+    fn piece_counts_alternate((variant, game) in variant_games()) {
+        let board = replayed(&game, variant);
+        let count = |piece| board.board.iter().flatten().filter(|&&cell| cell == piece).count();
+        let played = game.cols_played.len();
+        prop_assert_eq!(count(PLAYER), played.div_ceil(2));
+        prop_assert_eq!(count(BOT), played / 2);
+        prop_assert_eq!(count(EMPTY), game.rows * game.cols - played);
+    }
+
+    #[test]
+    fn no_floating_discs(game in games(Variant::Standard)) {
+        let board = replayed(&game, Variant::Standard);
+        for col in 0..game.cols {
+            for row in 1..game.rows {
+                prop_assert!(
+                    board.board[row - 1][col] == EMPTY || board.board[row][col] != EMPTY,
+                    "floating disc at row {} column {} after {}", row, col + 1, game.moves
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn win_detection_matches_brute_force(
+        (variant, game) in variant_games(),
+    ) {
+        let board = replayed(&game, variant);
+        for player in [PLAYER, BOT] {
+            let won = brute_force_win(&board, player);
+            prop_assert_eq!(board.check_win(player), won);
+            match board.winning_line(player) {
+                Some(line) => {
+                    prop_assert!(won);
+                    for (row, col) in line {
+                        prop_assert_eq!(board.board[row][col], player);
+                    }
+                }
+                None => prop_assert!(!won),
+            }
+        }
+    }
+
+    #[test]
+    fn winning_moves_match_full_scans((variant, game) in variant_games()) {
+        // Games stop at the first win, so the move before it is still open.
+        let open = &game.moves[..game.moves.len().saturating_sub(1)];
+        let board = ConnectFour::new(game.rows, game.cols)
+            .with_rules(variant.rules(DEFAULT_FLIP_EVERY))
+            .replay(open, PLAYER)
+            .expect("generated games are legal");
+        for col in board.get_valid_moves() {
+            for piece in [PLAYER, BOT] {
+                let mut after = board.clone();
+                after.drop_piece(col, piece);
+                prop_assert_eq!(board.winning_move(col, piece), after.check_win(piece));
+            }
+        }
+    }
+
+    #[test]
+    fn letter_transcripts_match_digits((variant, game) in variant_games()) {
+        let letters: String = game.cols_played.iter().map(|&col| (b'a' + col as u8) as char).collect();
+        let board = ConnectFour::new(game.rows, game.cols)
+            .with_rules(variant.rules(DEFAULT_FLIP_EVERY))
+            .replay(&letters, PLAYER)
+            .expect("letters name the same columns");
+        prop_assert_eq!(board.board, replayed(&game, variant).board);
+    }
+
+    #[test]
+    fn replaying_matches_dropping((variant, game) in variant_games()) {
+        let mut board = ConnectFour::new(game.rows, game.cols)
+            .with_rules(variant.rules(DEFAULT_FLIP_EVERY));
+        let mut piece = PLAYER;
+        for &col in &game.cols_played {
+            prop_assert!(board.drop_piece(col, piece));
+            piece = -piece;
+        }
+        prop_assert_eq!(board.board, replayed(&game, variant).board);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn search_plays_legal_columns(
+        (variant, game) in variant_games(),
+        depth in 0..=3i32,
+        engine in prop_oneof![Just(Engine::Minimax), Just(Engine::Random)],
+    ) {
+        let board = replayed(&game, variant);
+        let to_move = if game.moves.len().is_multiple_of(2) { PLAYER } else { BOT };
+        prop_assume!(!board.check_win(-to_move));
+        let analysis = BotPlayer::new(depth).with_engine(engine).analyze_as(&board, to_move);
+        match analysis.best_move {
+            Some(col) => prop_assert!(board.get_valid_moves().contains(&col)),
+            None => prop_assert!(board.get_valid_moves().is_empty()),
+        }
+    }
+}
+
+// A book small enough to generate in a debug build, shared by every case.
+const BOOK_ROWS: usize = 4;
+const BOOK_COLS: usize = 5;
+const BOOK_PLIES: usize = 5;
+
+fn small_book() -> &'static Book {
+    static BOOK: OnceLock<Book> = OnceLock::new();
+    BOOK.get_or_init(|| {
+        let path = env::temp_dir().join(format!("connect-four-book-{}.c4bk", process::id()));
+        Book::generate(BOOK_COLS, BOOK_ROWS, BOOK_PLIES, 1, 1, &path).expect("book generated");
+        let book = Book::open(&path).expect("book opened");
+        // The mapping outlives the file's name.
+        let _ = fs::remove_file(&path);
+        book
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn book_scores_match_the_solver(choices in prop::collection::vec(any::<usize>(), 0..=BOOK_PLIES)) {
+        let mut pos = Position::new(BOOK_COLS, BOOK_ROWS).unwrap();
+        for choice in choices {
+            let valid: Vec<usize> = (0..BOOK_COLS).filter(|&col| pos.can_play(col)).collect();
+            let col = valid[choice % valid.len()];
+            if pos.is_winning_move(col) {
+                break;
+            }
+            pos.play(col);
+        }
+        prop_assert_eq!(small_book().probe(&pos), Some(Solver::new(1).solve(&pos)));
+    }
+}
+
+fn settings() -> impl Strategy<Value = Setting> {
+    prop_oneof![
+        (0..=MAX_DEPTH).prop_map(Setting::Depth),
+        (1..=1000u32).prop_map(Setting::Rollouts),
+        (-50..=50i32, -50..=50i32, -50..=50i32)
+            .prop_map(|(center, two, three)| Setting::Weights(Weights { center, two, three })),
+        prop_oneof![
+            Just(None),
+            Just(Some(Personality::Aggressive)),
+            Just(Some(Personality::Drunk)),
+        ]
+        .prop_map(Setting::Personality),
+        (1..=4096usize).prop_map(Setting::Hash),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn settings_parse_as_shown(setting in settings()) {
+        let line = format!("{} {}", setting.name(), setting.value());
+        prop_assert_eq!(Setting::parse(&line), Ok(setting));
+    }
+}
@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use tracing::{debug, warn};
+
+use crate::analysis::parse_position;
+use crate::bot::BotPlayer;
+use crate::engine_options::{Configurable, Setting};
+use crate::game::{BOT, ConnectFour, GameStatus, PLAYER, column_char, symbol};
+use crate::i18n::{Msg, tr};
+
+// A line-based engine protocol in the spirit of UCI, spoken over stdin/stdout:
+//
+//   isready             -> readyok
+//   newgame             starts a new game (no reply)
+//   position [moves]    sets the position, moves as in `eval` (no reply)
+//   go                  -> info hashfull <permille>
+//                          bestmove <col> | bestmove none
+//   setoption <option> <value>
+//                       changes a setting for the searches to come, as `set` in
+//                       a game, e.g. `setoption depth 8` (no reply)
+//   quit                exits
+//
+// Columns are 1-based. Errors are answered with `error <message>`. `info`
+// lines report on the search and may be ignored; `hashfull` is how much of
+// the transposition table is in use, in thousandths.
+pub fn serve<S: Configurable>(
+    settings: &mut S,
+    make_bot: impl Fn(&S) -> BotPlayer,
+    rows: usize,
+    cols: usize,
+) -> io::Result<()> {
+    let mut bot = make_bot(settings);
+    let mut position = parse_position(rows, cols, "");
+    let mut out = io::stdout().lock();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        debug!(command = %line, "engine command");
+        let mut words = line.split_whitespace();
+        match words.next() {
+            None => continue,
+            Some("isready") => writeln!(out, "readyok")?,
+            Some("newgame") => {
+                bot = make_bot(settings);
+                position = parse_position(rows, cols, "");
+            }
+            Some("position") => {
+                position = parse_position(rows, cols, words.next().unwrap_or(""));
+                if let Err(err) = &position {
+                    writeln!(out, "error {}", err)?;
+                }
+            }
+            // After an invalid `position` there is nothing to search.
+            Some("go") => match &position {
+                Ok((game, to_move)) => {
+                    let col = best_move(&mut bot, game, *to_move);
+                    writeln!(out, "info hashfull {}", (bot.occupancy() * 1000.0) as u32)?;
+                    match col {
+                        Some(col) => writeln!(out, "bestmove {}", col + 1)?,
+                        None => writeln!(out, "bestmove none")?,
+                    }
+                }
+                Err(err) => writeln!(out, "error {}", err)?,
+            },
+            Some("setoption") => {
+                let line: Vec<&str> = words.collect();
+                match Setting::parse(&line.join(" ")).and_then(|setting| settings.set(setting)) {
+                    Ok(()) => bot = make_bot(settings),
+                    Err(err) => writeln!(out, "error {}", err)?,
+                }
+            }
+            Some("quit") => break,
+            Some(other) => writeln!(out, "error unknown command '{}'", other)?,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+fn best_move(bot: &mut BotPlayer, game: &ConnectFour, to_move: i8) -> Option<usize> {
+    bot.analyze_as(game, to_move).best_move
+}
+
+// The same engine driven by JSON, one object per line each way, for scripts:
+//
+//   {"cmd":"new"}                    starts a new game
+//   {"cmd":"position","moves":"44"}  sets the position, moves as in `eval`
+//   {"cmd":"play","col":3}           plays a move; the engine replies to it
+//   {"cmd":"go"}                     the engine moves for the side to move
+//   {"cmd":"quit"}                   exits
+//
+// Every request is answered with the game as it stands:
+//
+//   {"ok":true,"moves":"443","board":[".......", ...],"to_move":"x",
+//    "legal":[1,2,3,4,5,6,7],"engine_move":3,"result":null}
+//
+// The board is listed top row first. `engine_move` is the move the engine
+// played for this request, if any, and `result` is "o", "x" or "draw" once the
+// game is over. A request that fails is answered with {"ok":false,"error":...}
+// and changes nothing.
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum JsonRequest {
+    New,
+    Position {
+        #[serde(default)]
+        moves: String,
+    },
+    Play {
+        col: usize,
+    },
+    Go,
+    Quit,
+}
+
+#[derive(Serialize)]
+struct JsonState<'a> {
+    ok: bool,
+    moves: &'a str,
+    board: Vec<String>,
+    to_move: Option<&'static str>,
+    legal: Vec<usize>,
+    engine_move: Option<usize>,
+    result: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct JsonError {
+    ok: bool,
+    error: String,
+}
+
+fn result(game: &ConnectFour) -> Option<&'static str> {
+    match game.status() {
+        GameStatus::Won { player, .. } => Some(symbol(player)),
+        GameStatus::Draw => Some("draw"),
+        GameStatus::InProgress => None,
+    }
+}
+
+fn side_to_move(moves: &str) -> i8 {
+    if moves.len().is_multiple_of(2) {
+        PLAYER
+    } else {
+        BOT
+    }
+}
+
+// Plays `col` for the side to move, 0-based.
+fn play_json_move(game: &mut ConnectFour, moves: &mut String, col: usize) {
+    game.drop_piece(col, side_to_move(moves));
+    moves.push(column_char(col));
+}
+
+pub fn serve_json(make_bot: impl Fn() -> BotPlayer, rows: usize, cols: usize) -> io::Result<()> {
+    let mut bot = make_bot();
+    let mut game = ConnectFour::new(rows, cols);
+    let mut moves = String::new();
+    let mut out = io::stdout().lock();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        debug!(command = %line, "json engine command");
+        let mut engine_move = None;
+        let outcome = match serde_json::from_str::<JsonRequest>(&line) {
+            Err(err) => Err(err.to_string()),
+            Ok(JsonRequest::Quit) => break,
+            Ok(JsonRequest::New) => {
+                bot = make_bot();
+                game = ConnectFour::new(rows, cols);
+                moves.clear();
+                Ok(())
+            }
+            Ok(JsonRequest::Position { moves: position }) => {
+                ConnectFour::from_moves(rows, cols, &position, PLAYER).map(|position_game| {
+                    game = position_game;
+                    moves = position;
+                })
+            }
+            Ok(JsonRequest::Play { col }) => {
+                if result(&game).is_some() {
+                    Err("the game is over".to_string())
+                } else if !(1..=cols).contains(&col) || !game.get_valid_moves().contains(&(col - 1))
+                {
+                    Err(format!("column {} is not playable", col))
+                } else {
+                    play_json_move(&mut game, &mut moves, col - 1);
+                    if result(&game).is_none() {
+                        engine_move = best_move(&mut bot, &game, side_to_move(&moves));
+                    }
+                    Ok(())
+                }
+            }
+            Ok(JsonRequest::Go) => match result(&game) {
+                Some(_) => Err("the game is over".to_string()),
+                None => {
+                    engine_move = best_move(&mut bot, &game, side_to_move(&moves));
+                    Ok(())
+                }
+            },
+        };
+        if let Some(col) = engine_move {
+            play_json_move(&mut game, &mut moves, col);
+        }
+        let reply = match outcome {
+            Ok(()) => serde_json::to_string(&JsonState {
+                ok: true,
+                moves: &moves,
+                board: game
+                    .board
+                    .iter()
+                    .map(|row| row.iter().map(|&cell| symbol(cell)).collect())
+                    .collect(),
+                to_move: result(&game)
+                    .is_none()
+                    .then(|| symbol(side_to_move(&moves))),
+                legal: match result(&game) {
+                    Some(_) => Vec::new(),
+                    None => game
+                        .get_valid_moves()
+                        .into_iter()
+                        .map(|col| col + 1)
+                        .collect(),
+                },
+                engine_move: engine_move.map(|col| col + 1),
+                result: result(&game),
+            }),
+            Err(error) => serde_json::to_string(&JsonError { ok: false, error }),
+        }?;
+        writeln!(out, "{}", reply)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// An engine running as a child process and spoken to over the protocol above.
+pub struct ExternalEngine {
+    name: String,
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ExternalEngine {
+    // `command` is split on whitespace; the first word is the program.
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| tr(Msg::EmptyEngineCommand, &[]))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("{}: {}", program, err))?;
+        let stdin = BufWriter::new(child.stdin.take().expect("stdin is piped"));
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut engine = Self {
+            name: command.to_string(),
+            child,
+            stdin,
+            stdout,
+        };
+        engine.send("isready")?;
+        match engine.receive()?.as_str() {
+            "readyok" => Ok(engine),
+            other => Err(format!(
+                "{}: expected readyok, got '{}'",
+                engine.name, other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| format!("{}: {}", self.name, err))
+    }
+
+    fn receive(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.stdout.read_line(&mut line) {
+            Ok(0) => Err(format!("{}: engine exited", self.name)),
+            Ok(_) => Ok(line.trim().to_string()),
+            Err(err) => Err(format!("{}: {}", self.name, err)),
+        }
+    }
+
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.send("newgame")
+    }
+
+    // The engine's move in the position reached by `moves`, 0-based.
+    pub fn best_move(&mut self, moves: &str) -> Result<Option<usize>, String> {
+        self.send(&format!("position {}", moves))?;
+        self.send("go")?;
+        let mut reply = self.receive()?;
+        while reply.starts_with("info ") {
+            reply = self.receive()?;
+        }
+        match reply.split_once(' ') {
+            Some(("bestmove", "none")) => Ok(None),
+            Some(("bestmove", col)) => col
+                .parse::<usize>()
+                .ok()
+                .filter(|&col| col > 0)
+                .map(|col| Some(col - 1))
+                .ok_or_else(|| format!("{}: invalid move '{}'", self.name, col)),
+            _ => Err(format!("{}: unexpected reply '{}'", self.name, reply)),
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        if self.send("quit").is_err() || self.child.wait().is_err() {
+            warn!(engine = %self.name, "engine did not quit cleanly");
+            let _ = self.child.kill();
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use rand::Rng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use crate::bot::BotPlayer;
+use crate::config;
+use crate::engine_options::{Configurable, Setting};
+use crate::game::{BOT, ConnectFour, PLAYER, column_char};
+use crate::i18n::{Msg, tr};
+use crate::input::{self, Input};
+use crate::solver::{Position, Solver, column_order};
+use crate::terminal::clear_screen;
+use crate::theme::Theme;
+
+pub const ROWS: usize = 6;
+pub const COLS: usize = 7;
+
+const STARTER_PUZZLES: &str = include_str!("puzzles.txt");
+
+// One puzzle per line: the moves leading to the position, then the number of
+// moves the side to move needs to force a win, e.g. `4453 3`. `#` starts a comment.
+pub struct Puzzle {
+    pub moves: String,
+    pub n: u32,
+}
+
+impl Puzzle {
+    // The position always has the human to move, whoever made the first move.
+    pub fn game(&self) -> Result<ConnectFour, String> {
+        let first = if self.moves.len().is_multiple_of(2) {
+            PLAYER
+        } else {
+            BOT
+        };
+        ConnectFour::from_moves(ROWS, COLS, &self.moves, first)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Puzzle>, String> {
+    let mut puzzles = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(moves), Some(n), None) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(tr(Msg::PuzzleLineFormat, &[&(i + 1)]));
+        };
+        let n = n
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| tr(Msg::PuzzleMoveCount, &[&(i + 1), &n]))?;
+        let puzzle = Puzzle {
+            moves: moves.to_string(),
+            n,
+        };
+        puzzle
+            .game()
+            .map_err(|err| tr(Msg::LineError, &[&(i + 1), &err]))?;
+        puzzles.push(puzzle);
+    }
+    Ok(puzzles)
+}
+
+pub fn load(file: Option<&Path>) -> Result<Vec<Puzzle>, Box<dyn Error>> {
+    let puzzles = match file {
+        Some(path) => {
+            let text =
+                fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            parse(&text).map_err(|err| format!("{}: {}", path.display(), err))?
+        }
+        None => parse(STARTER_PUZZLES)?,
+    };
+    if puzzles.is_empty() {
+        return Err(tr(Msg::NoPuzzles, &[]).into());
+    }
+    Ok(puzzles)
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Stats {
+    attempted: u32,
+    solved: u32,
+    streak: u32,
+    best_streak: u32,
+}
+
+impl Stats {
+    fn path() -> Option<PathBuf> {
+        Some(config::data_dir()?.join("puzzle-stats.toml"))
+    }
+
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, toml::to_string(self)?)
+            .map_err(|err| format!("{}: {}", path.display(), err).into())
+    }
+
+    fn record(&mut self, solved: bool) {
+        self.attempted += 1;
+        if solved {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+    }
+}
+
+enum Outcome {
+    Solved,
+    Failed,
+    Quit,
+}
+
+enum Defense {
+    // The reply that holds out the longest against the attacker's forced win.
+    Holds(usize),
+    // The attacker's last move was not winning; the reply proves it.
+    Refuted(Option<usize>),
+}
+
+// `pos` has the defender to move and the attacker needing to win within `n` more moves.
+fn best_defense(solver: &mut Solver, pos: &Position, n: u32) -> Defense {
+    let mut best: Option<(u32, usize)> = None;
+    for col in column_order(pos.width()).filter(|&col| pos.can_play(col)) {
+        if pos.is_winning_move(col) {
+            return Defense::Refuted(Some(col));
+        }
+        let mut child = *pos;
+        child.play(col);
+        match (1..=n).find(|&k| solver.wins_within(&child, k)) {
+            Some(k) => {
+                if best.is_none_or(|(best_k, _)| k > best_k) {
+                    best = Some((k, col));
+                }
+            }
+            None => return Defense::Refuted(Some(col)),
+        }
+    }
+    match best {
+        Some((_, col)) => Defense::Holds(col),
+        None => Defense::Refuted(None),
+    }
+}
+
+// Whether playing `col` keeps a forced win within `n` moves.
+fn keeps_win(solver: &mut Solver, pos: &Position, col: usize, n: u32) -> bool {
+    if pos.is_winning_move(col) {
+        return true;
+    }
+    let mut child = *pos;
+    child.play(col);
+    matches!(best_defense(solver, &child, n - 1), Defense::Holds(_))
+}
+
+fn solution(solver: &mut Solver, pos: &Position, n: u32) -> Option<usize> {
+    column_order(pos.width())
+        .filter(|&col| pos.can_play(col))
+        .find(|&col| keeps_win(solver, pos, col, n))
+}
+
+// `set` changes `settings` as in a game; the solver is rebuilt from them.
+fn play_puzzle<S: Configurable>(
+    puzzle: &Puzzle,
+    settings: &mut S,
+    make_solver: &impl Fn(&S) -> Solver,
+    solver: &mut Solver,
+    theme: &Theme,
+) -> Result<Outcome, Box<dyn Error>> {
+    let mut game = puzzle.game()?;
+    let mut remaining = puzzle.n;
+    let mut message = tr(Msg::PuzzlePrompt, &[&puzzle.n, &theme.glyph(PLAYER)]);
+
+    loop {
+        clear_screen();
+        println!("{}", theme.render(&game));
+        println!("{}", message);
+
+        let col = match input::read_column(&game) {
+            Input::Column(col) if game.get_valid_moves().contains(&col) => col,
+            Input::Column(_)
+            | Input::Back
+            | Input::Invalid
+            | Input::Redraw
+            | Input::Help
+            | Input::Rules
+            | Input::Swap => {
+                continue;
+            }
+            Input::Set(line) => {
+                message = match Setting::parse(&line) {
+                    Ok(setting) => {
+                        let (name, value) = (setting.name(), setting.value());
+                        match settings.set(setting) {
+                            Ok(()) => {
+                                *solver = make_solver(settings);
+                                tr(Msg::SettingChanged, &[&name, &value])
+                            }
+                            Err(err) => err,
+                        }
+                    }
+                    Err(err) => err,
+                };
+                continue;
+            }
+            Input::Quit => return Ok(Outcome::Quit),
+        };
+        let pos = Position::from_game(&game, PLAYER).ok_or_else(|| tr(Msg::BoardTooLarge, &[]))?;
+
+        if pos.is_winning_move(col) {
+            game.drop_piece(col, PLAYER);
+            clear_screen();
+            println!("{}", theme.render(&game));
+            println!("{}", tr(Msg::PuzzleSolved, &[]));
+            return Ok(Outcome::Solved);
+        }
+
+        let mut after = pos;
+        after.play(col);
+        match best_defense(solver, &after, remaining - 1) {
+            Defense::Holds(reply) => {
+                game.drop_piece(col, PLAYER);
+                game.drop_piece(reply, BOT);
+                remaining -= 1;
+                message = tr(Msg::PuzzleGood, &[&theme.column_label(reply), &remaining]);
+            }
+            Defense::Refuted(reply) => {
+                let hint = solution(solver, &pos, remaining);
+                game.drop_piece(col, PLAYER);
+                if let Some(reply) = reply {
+                    game.drop_piece(reply, BOT);
+                }
+                clear_screen();
+                println!("{}", theme.render(&game));
+                match reply {
+                    Some(reply) => println!(
+                        "{}",
+                        tr(
+                            Msg::PuzzleTooSlowReply,
+                            &[&theme.column_label(col), &theme.column_label(reply)]
+                        )
+                    ),
+                    None => println!("{}", tr(Msg::PuzzleTooSlow, &[&theme.column_label(col)])),
+                }
+                if let Some(hint) = hint {
+                    println!("{}", tr(Msg::PuzzleSolution, &[&theme.column_label(hint)]));
+                }
+                return Ok(Outcome::Failed);
+            }
+        }
+    }
+}
+
+pub fn run<S: Configurable>(
+    file: Option<&Path>,
+    settings: &mut S,
+    make_solver: impl Fn(&S) -> Solver,
+    theme: &Theme,
+) -> Result<(), Box<dyn Error>> {
+    let mut puzzles = load(file)?;
+    puzzles.shuffle(&mut rand::rng());
+    let mut stats = Stats::load();
+    let mut solver = make_solver(settings);
+
+    for puzzle in &puzzles {
+        let solved = match play_puzzle(puzzle, settings, &make_solver, &mut solver, theme)? {
+            Outcome::Solved => true,
+            Outcome::Failed => false,
+            Outcome::Quit => break,
+        };
+        stats.record(solved);
+        stats.save()?;
+        println!(
+            "{}",
+            tr(
+                Msg::PuzzleStreak,
+                &[
+                    &stats.streak,
+                    &stats.best_streak,
+                    &stats.solved,
+                    &stats.attempted
+                ]
+            )
+        );
+        println!("{}", tr(Msg::PuzzleNext, &[]));
+        if !input::wait_for_key() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub struct GenerateOptions {
+    pub count: usize,
+    pub games: usize,
+    pub min_moves: u32,
+    pub max_moves: u32,
+    pub output: Option<PathBuf>,
+    pub hash_mb: usize,
+}
+
+// The shortest forced win for the side to move, if it lies in the requested
+// range and only one first move achieves it.
+fn unique_forced_win(solver: &mut Solver, pos: &Position, min_n: u32, max_n: u32) -> Option<u32> {
+    let n = (1..=max_n).find(|&n| solver.wins_within(pos, n))?;
+    if n < min_n {
+        return None;
+    }
+    let winners = column_order(pos.width())
+        .filter(|&col| pos.can_play(col))
+        .filter(|&col| keeps_win(solver, pos, col, n))
+        .count();
+    (winners == 1).then_some(n)
+}
+
+fn grade(n: u32) -> &'static str {
+    match n {
+        0..=2 => "easy",
+        3 => "medium",
+        4 => "hard",
+        _ => "expert",
+    }
+}
+
+// Plays the bot against itself, starting from a few random moves for variety,
+// and keeps the first position of each game that makes a good puzzle.
+pub fn generate(bot: &mut BotPlayer, options: &GenerateOptions) -> Result<(), Box<dyn Error>> {
+    let mut rng = rand::rng();
+    let mut solver = Solver::new(options.hash_mb);
+    let mut seen = HashSet::new();
+    let mut found: Vec<Puzzle> = Vec::new();
+    let mut games = 0;
+
+    while found.len() < options.count && games < options.games {
+        games += 1;
+        let mut game = ConnectFour::new(ROWS, COLS);
+        let mut moves = String::new();
+        let mut piece = PLAYER;
+        let random_moves = rng.random_range(0..=4);
+
+        while !game.check_win(-piece) && !game.get_valid_moves().is_empty() {
+            let pos =
+                Position::from_game(&game, piece).ok_or_else(|| tr(Msg::BoardTooLarge, &[]))?;
+            if let Some(n) =
+                unique_forced_win(&mut solver, &pos, options.min_moves, options.max_moves)
+                && seen.insert(moves.clone())
+            {
+                info!(moves = %moves, n, games, "puzzle found");
+                found.push(Puzzle {
+                    moves: moves.clone(),
+                    n,
+                });
+                break;
+            }
+
+            let col = if moves.len() < random_moves {
+                game.get_valid_moves().choose(&mut rng).cloned()
+            } else {
+                bot.analyze_as(&game, piece).best_move
+            };
+            let Some(col) = col else {
+                break;
+            };
+            game.drop_piece(col, piece);
+            moves.push(column_char(col));
+            piece = -piece;
+        }
+    }
+
+    found.sort_by_key(|puzzle| puzzle.n);
+    let mut text = format!(
+        "# Generated by `connect-four puzzle generate` from {} self-play games.\n",
+        games
+    );
+    let mut current = None;
+    for puzzle in &found {
+        if current != Some(puzzle.n) {
+            current = Some(puzzle.n);
+            writeln!(text, "# win in {} ({})", puzzle.n, grade(puzzle.n))?;
+        }
+        writeln!(text, "{} {}", puzzle.moves, puzzle.n)?;
+    }
+
+    match &options.output {
+        Some(path) => {
+            fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))?;
+            eprintln!(
+                "{}",
+                tr(Msg::WrotePuzzles, &[&found.len(), &path.display(), &games])
+            );
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lines_and_comments() {
+        let puzzles = parse("# a comment\n\n4453 3  # win in three\n 33237676 2\n").unwrap();
+        let parsed: Vec<(&str, u32)> = puzzles
+            .iter()
+            .map(|puzzle| (puzzle.moves.as_str(), puzzle.n))
+            .collect();
+        assert_eq!(parsed, [("4453", 3), ("33237676", 2)]);
+    }
+
+    #[test]
+    fn rejects_bad_lines_by_number() {
+        assert_eq!(
+            parse("4453 3\n4453\n").err().unwrap(),
+            "line 2: expected `<moves> <n>`"
+        );
+        assert_eq!(
+            parse("4453 0").err().unwrap(),
+            "line 1: invalid move count '0'"
+        );
+        assert!(parse("4453 2 1").is_err());
+        assert!(parse("8 2").err().unwrap().starts_with("line 1: "));
+    }
+
+    #[test]
+    fn starter_puzzles_parse() {
+        assert!(!parse(STARTER_PUZZLES).unwrap().is_empty());
+    }
+}
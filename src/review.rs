@@ -0,0 +1,258 @@
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::Write;
+
+use crate::analysis::score_after;
+use crate::bot::BotPlayer;
+use crate::game::{BOT, ConnectFour, GameStatus, PASS, PLAYER, SWAP, parse_column, symbol};
+use crate::i18n::{Msg, tr};
+use crate::json;
+use crate::theme::Notation;
+
+// Smallest score losses, on the bot's scale, that earn each label.
+const INACCURACY: i32 = 10;
+const MISTAKE: i32 = 30;
+const BLUNDER: i32 = 60;
+
+struct Move {
+    ply: usize,
+    piece: i8,
+    col: usize,
+    best_col: usize,
+    // Scores for the mover after its move and after the best move.
+    score: i32,
+    best: i32,
+}
+
+impl Move {
+    fn swing(&self) -> i32 {
+        self.best - self.score
+    }
+
+    // Throwing away a won game or walking into a lost one is always a blunder.
+    fn label(&self, reward: i32) -> Option<&'static str> {
+        let decisive = (self.best >= reward && self.score < reward)
+            || (self.best > -reward && self.score <= -reward);
+        match self.swing() {
+            _ if decisive => Some("blunder"),
+            swing if swing >= BLUNDER => Some("blunder"),
+            swing if swing >= MISTAKE => Some("mistake"),
+            swing if swing >= INACCURACY => Some("inaccuracy"),
+            _ => None,
+        }
+    }
+}
+
+fn side(piece: i8) -> &'static str {
+    if piece == PLAYER { "you" } else { "bot" }
+}
+
+// A reviewed move in JSON, numbered from 1.
+#[derive(Serialize)]
+pub struct Entry {
+    #[serde(rename = "move")]
+    number: usize,
+    #[serde(with = "json::piece")]
+    piece: i8,
+    #[serde(with = "json::column")]
+    col: usize,
+    #[serde(with = "json::column")]
+    best: usize,
+    eval: i32,
+    swing: i32,
+    label: Option<&'static str>,
+}
+
+// Swaps and passes, with the plies they were played at.
+type Notes = Vec<(usize, String)>;
+
+// Evaluates every move of a finished game with the bot's search and compares
+// it with the best move the search finds. `moves` is a transcript as recorded
+// in the history, the human moving first with o, played on the empty `board`.
+fn review(
+    bot: &mut BotPlayer,
+    board: &ConnectFour,
+    moves: &str,
+) -> Result<(Vec<Move>, Notes), Box<dyn Error>> {
+    let mut reviewed = Vec::new();
+    let mut notes = Vec::new();
+    // Transcripts are ASCII, so byte offsets are plies.
+    for (ply, c) in moves.bytes().map(char::from).enumerate() {
+        let piece = if ply % 2 == 0 { PLAYER } else { BOT };
+        match c {
+            SWAP => notes.push((ply, format!("{} swapped", side(piece)))),
+            // A game the bot opens is recorded with the human passing first.
+            PASS if ply == 0 => notes.push((ply, "bot moved first".to_string())),
+            PASS => notes.push((ply, format!("{} passed", side(piece)))),
+            _ => {
+                let game = board.clone().replay(&moves[..ply], PLAYER)?;
+                let col = parse_column(c).unwrap_or(0);
+                let (best_col, best) = game
+                    .get_valid_moves()
+                    .into_iter()
+                    .map(|other| (other, score_after(bot, &game, other, piece)))
+                    .max_by_key(|&(_, score)| score)
+                    .ok_or_else(|| tr(Msg::NoMoveToReview, &[]))?;
+                let score = if col == best_col {
+                    best
+                } else {
+                    score_after(bot, &game, col, piece)
+                };
+                reviewed.push(Move {
+                    ply,
+                    piece,
+                    col,
+                    best_col,
+                    score,
+                    best,
+                });
+            }
+        }
+    }
+    Ok((reviewed, notes))
+}
+
+// The review of every drop in `moves`, as `report` judges them.
+pub fn entries(
+    bot: &mut BotPlayer,
+    board: &ConnectFour,
+    moves: &str,
+) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let reward = bot.reward();
+    let (reviewed, _) = review(bot, board, moves)?;
+    Ok(reviewed
+        .iter()
+        .map(|entry| Entry {
+            number: entry.ply + 1,
+            piece: entry.piece,
+            col: entry.col,
+            best: entry.best_col,
+            eval: entry.score,
+            swing: entry.swing(),
+            label: entry.label(reward),
+        })
+        .collect())
+}
+
+// The review as a table with notes and the move that decided the game, its
+// columns named in `notation`.
+pub fn report(
+    bot: &mut BotPlayer,
+    board: &ConnectFour,
+    moves: &str,
+    notation: Notation,
+) -> Result<String, Box<dyn Error>> {
+    let reward = bot.reward();
+    let (reviewed, notes) = review(bot, board, moves)?;
+    let mut text = String::new();
+    writeln!(
+        text,
+        "{:>4}  {:<4}  {:>4}  {:>4}  {:>5}  {:>5}",
+        "move", "side", "col", "best", "eval", "swing"
+    )?;
+    for entry in &reviewed {
+        let line = format!(
+            "{:>4}  {:<4}  {:>4}  {:>4}  {:>+5}  {:>5}  {}",
+            entry.ply + 1,
+            side(entry.piece),
+            notation.label(entry.col),
+            notation.label(entry.best_col),
+            entry.score,
+            if entry.swing() > 0 {
+                format!("-{}", entry.swing())
+            } else {
+                String::new()
+            },
+            entry.label(reward).unwrap_or("")
+        );
+        writeln!(text, "{}", line.trim_end())?;
+    }
+    for (ply, note) in &notes {
+        writeln!(text, "Move {}: {}.", ply + 1, note)?;
+    }
+
+    // The game was decided by the costliest error of the side that lost, or
+    // by the costliest one overall in a drawn game.
+    let outcome = board.clone().replay(moves, PLAYER)?;
+    let loser = match outcome.status() {
+        GameStatus::Won { player, .. } => Some(-player),
+        GameStatus::Draw | GameStatus::InProgress => None,
+    };
+    let critical = reviewed
+        .iter()
+        .filter(|entry| loser.is_none_or(|loser| entry.piece == loser))
+        .filter(|entry| entry.swing() > 0)
+        .max_by_key(|entry| (entry.swing(), usize::MAX - entry.ply));
+    match critical {
+        Some(entry) => {
+            writeln!(
+                text,
+                "Critical moment: move {}, {} ({}) played {} instead of {}, losing {} points.",
+                entry.ply + 1,
+                side(entry.piece),
+                symbol(entry.piece),
+                notation.label(entry.col),
+                notation.label(entry.best_col),
+                entry.swing()
+            )?;
+        }
+        None => {
+            writeln!(text, "No mistakes found at this depth.")?;
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The second player leaves the first one's column of three open.
+    const MISSED_BLOCK: &str = "4141474";
+
+    #[test]
+    fn swings_and_lost_results_earn_labels() {
+        let reward = 100;
+        let mut entry = Move {
+            ply: 0,
+            piece: PLAYER,
+            col: 0,
+            best_col: 3,
+            score: 0,
+            best: 15,
+        };
+        assert_eq!(entry.label(reward), Some("inaccuracy"));
+        entry.best = 45;
+        assert_eq!(entry.label(reward), Some("mistake"));
+        entry.best = 80;
+        assert_eq!(entry.label(reward), Some("blunder"));
+        entry.best = reward;
+        entry.score = reward - 5;
+        assert_eq!(entry.label(reward), Some("blunder"));
+        entry.score = reward;
+        assert_eq!(entry.label(reward), None);
+    }
+
+    #[test]
+    fn a_missed_block_is_flagged() {
+        let mut bot = BotPlayer::new(4);
+        let board = ConnectFour::new(6, 7);
+        let entries = entries(&mut bot, &board, MISSED_BLOCK).unwrap();
+        assert_eq!(entries.len(), MISSED_BLOCK.len());
+        let missed = &entries[5];
+        assert_eq!((missed.number, missed.piece, missed.col), (6, BOT, 6));
+        assert_eq!((missed.best, missed.label), (3, Some("blunder")));
+        assert_eq!(entries[6].label, None);
+    }
+
+    #[test]
+    fn the_report_names_the_critical_moment() {
+        let mut bot = BotPlayer::new(4);
+        let board = ConnectFour::new(6, 7);
+        let moves = format!("{}{}", PASS, MISSED_BLOCK);
+        let text = report(&mut bot, &board, &moves, Notation::Letters).unwrap();
+        assert!(text.starts_with("move  side"));
+        assert!(text.contains("Move 1: bot moved first."));
+        assert!(text.contains("Critical moment: move 7, you (o) played g instead of d"));
+    }
+}
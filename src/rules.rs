@@ -0,0 +1,177 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::game::EMPTY;
+
+// How a piece played into a column comes to rest. Every move, in games and in
+// the bot's search alike, goes through these rules, so variants are searched
+// as they are played. Row 0 is the top of the board.
+pub trait Rules: Send + Sync {
+    // The row a piece played in `col` lands on, or None if the column is full.
+    fn landing_row(&self, board: &[Vec<i8>], col: usize) -> Option<usize>;
+
+    // The variant these rules are played under, for saving a board.
+    fn variant(&self) -> Variant;
+
+    // How many times a position may come up, with the same side to move,
+    // before the game is drawn; None if repeating never draws. Rules that
+    // only ever add pieces cannot repeat a position and keep the default.
+    fn repetition_limit(&self) -> Option<usize> {
+        None
+    }
+
+    // How often gravity turns, for the variants where it does.
+    fn flip_every(&self) -> Option<usize> {
+        None
+    }
+
+    // Whether pieces always fall to the bottom, which the solver, tablebase and
+    // game history take for granted.
+    fn is_standard(&self) -> bool {
+        false
+    }
+}
+
+fn lowest_empty(board: &[Vec<i8>], col: usize) -> Option<usize> {
+    (0..board.len()).rev().find(|&row| board[row][col] == EMPTY)
+}
+
+fn highest_empty(board: &[Vec<i8>], col: usize) -> Option<usize> {
+    (0..board.len()).find(|&row| board[row][col] == EMPTY)
+}
+
+pub struct Standard;
+
+impl Rules for Standard {
+    fn landing_row(&self, board: &[Vec<i8>], col: usize) -> Option<usize> {
+        lowest_empty(board, col)
+    }
+
+    fn variant(&self) -> Variant {
+        Variant::Standard
+    }
+
+    fn is_standard(&self) -> bool {
+        true
+    }
+}
+
+// Pieces rise and stack from the top down.
+pub struct AntiGravity;
+
+impl Rules for AntiGravity {
+    fn landing_row(&self, board: &[Vec<i8>], col: usize) -> Option<usize> {
+        highest_empty(board, col)
+    }
+
+    fn variant(&self) -> Variant {
+        Variant::AntiGravity
+    }
+}
+
+// Gravity turns around after every `every` pieces, starting downwards. Pieces
+// already on the board stay put, so a column fills from both ends.
+pub struct FlippingGravity {
+    pub every: usize,
+}
+
+impl Rules for FlippingGravity {
+    fn landing_row(&self, board: &[Vec<i8>], col: usize) -> Option<usize> {
+        let pieces = board
+            .iter()
+            .flatten()
+            .filter(|&&cell| cell != EMPTY)
+            .count();
+        if (pieces / self.every).is_multiple_of(2) {
+            lowest_empty(board, col)
+        } else {
+            highest_empty(board, col)
+        }
+    }
+
+    fn variant(&self) -> Variant {
+        Variant::FlipGravity
+    }
+
+    fn flip_every(&self) -> Option<usize> {
+        Some(self.every)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Variant {
+    Standard,
+    AntiGravity,
+    FlipGravity,
+}
+
+pub const DEFAULT_FLIP_EVERY: usize = 4;
+
+impl Variant {
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::AntiGravity => "anti-gravity",
+            Variant::FlipGravity => "flip-gravity",
+        }
+    }
+
+    // `flip_every` only matters for `FlipGravity`.
+    pub fn rules(self, flip_every: usize) -> Arc<dyn Rules> {
+        match self {
+            Variant::Standard => Arc::new(Standard),
+            Variant::AntiGravity => Arc::new(AntiGravity),
+            Variant::FlipGravity => Arc::new(FlippingGravity {
+                every: flip_every.max(1),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4x2 board with column 0 holding the given cells, top row first.
+    fn board(column: [i8; 4]) -> Vec<Vec<i8>> {
+        column.iter().map(|&cell| vec![cell, EMPTY]).collect()
+    }
+
+    #[test]
+    fn pieces_land_where_the_variant_says() {
+        let half = board([EMPTY, EMPTY, 1, -1]);
+        assert_eq!(Standard.landing_row(&half, 0), Some(1));
+        assert_eq!(AntiGravity.landing_row(&half, 0), Some(0));
+        let full = board([1, -1, 1, -1]);
+        assert_eq!(Standard.landing_row(&full, 0), None);
+        assert_eq!(AntiGravity.landing_row(&full, 0), None);
+    }
+
+    #[test]
+    fn gravity_turns_after_every_few_pieces() {
+        let rules = FlippingGravity { every: 2 };
+        let one = board([EMPTY, EMPTY, EMPTY, 1]);
+        assert_eq!(rules.landing_row(&one, 0), Some(2));
+        let two = board([EMPTY, EMPTY, -1, 1]);
+        assert_eq!(rules.landing_row(&two, 0), Some(0));
+        let four = board([1, -1, -1, 1]);
+        assert_eq!(rules.landing_row(&four, 1), Some(3));
+    }
+
+    #[test]
+    fn variants_build_their_rules() {
+        for variant in [
+            Variant::Standard,
+            Variant::AntiGravity,
+            Variant::FlipGravity,
+        ] {
+            assert_eq!(variant.rules(3).variant(), variant);
+        }
+        assert!(Variant::Standard.rules(3).is_standard());
+        assert!(!Variant::AntiGravity.rules(3).is_standard());
+        assert_eq!(Variant::FlipGravity.rules(0).flip_every(), Some(1));
+        assert_eq!(Variant::Standard.rules(3).flip_every(), None);
+    }
+}
@@ -0,0 +1,158 @@
+use memmap2::Mmap;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::solver::{Position, column_order};
+
+const VERSION: u8 = 1;
+const HEADER: usize = 16;
+const ENTRY_BYTES: usize = 9;
+
+enum Bytes {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Bytes::Owned(bytes) => bytes,
+            Bytes::Mapped(map) => map,
+        }
+    }
+}
+
+// Exact scores, in the solver's convention, of positions on one board size,
+// sorted by key so a lookup is a binary search. Tablebases and opening books
+// are both kept as one, told apart by the magic of their files. A file is a
+// header, then all keys as little-endian u64, then one signed byte per score;
+// it is mapped rather than read, so its pages are only touched by the lookups
+// that need them.
+pub struct ScoreTable {
+    width: usize,
+    height: usize,
+    // The fewest pieces of a tablebase's positions, the most of a book's.
+    bound: usize,
+    len: usize,
+    bytes: Bytes,
+}
+
+impl ScoreTable {
+    // `entries` must be sorted by key.
+    pub fn new(
+        magic: &[u8; 4],
+        width: usize,
+        height: usize,
+        bound: usize,
+        entries: &[(u64, i8)],
+    ) -> Self {
+        let mut bytes = Vec::with_capacity(HEADER + entries.len() * ENTRY_BYTES);
+        bytes.extend_from_slice(magic);
+        bytes.extend_from_slice(&[VERSION, width as u8, height as u8, bound as u8]);
+        bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+        for (key, _) in entries {
+            bytes.extend_from_slice(&key.to_le_bytes());
+        }
+        bytes.extend(entries.iter().map(|&(_, score)| score as u8));
+        Self {
+            width,
+            height,
+            bound,
+            len: entries.len(),
+            bytes: Bytes::Owned(bytes),
+        }
+    }
+
+    // `kind` names the files with `magic` in errors, as "a tablebase".
+    pub fn open(path: &Path, magic: &[u8; 4], kind: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        // Safety: the map is only read, and tables are written whole by
+        // `save`, never changed in place.
+        let map =
+            unsafe { Mmap::map(&file) }.map_err(|err| format!("{}: {}", path.display(), err))?;
+        let invalid = || format!("{}: not {} file", path.display(), kind);
+        if map.len() < HEADER || &map[..4] != magic || map[4] != VERSION {
+            return Err(invalid());
+        }
+        let (width, height, bound) = (map[5] as usize, map[6] as usize, map[7] as usize);
+        let len = usize::try_from(u64::from_le_bytes(map[8..16].try_into().unwrap()))
+            .map_err(|_| invalid())?;
+        let size = len
+            .checked_mul(ENTRY_BYTES)
+            .and_then(|entries| entries.checked_add(HEADER));
+        if size != Some(map.len()) || Position::new(width, height).is_none() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            width,
+            height,
+            bound,
+            len,
+            bytes: Bytes::Mapped(map),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, &*self.bytes).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn bound(&self) -> usize {
+        self.bound
+    }
+
+    // Whether `pos` is on this table's board.
+    pub fn fits(&self, pos: &Position) -> bool {
+        pos.width() == self.width && pos.height() == self.height
+    }
+
+    fn key_at(&self, i: usize) -> u64 {
+        let start = HEADER + i * 8;
+        u64::from_le_bytes(self.bytes[start..start + 8].try_into().unwrap())
+    }
+
+    pub fn score(&self, key: u64) -> Option<i32> {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = (low + high) / 2;
+            match self.key_at(mid).cmp(&key) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    return Some(self.bytes[HEADER + self.len * 8 + mid] as i8 as i32);
+                }
+            }
+        }
+        None
+    }
+
+    // The best move for the side to move and its score, the move nearest the
+    // center among equals, with the children scored by `probe`.
+    pub fn best_move(
+        &self,
+        pos: &Position,
+        probe: impl Fn(&Position) -> Option<i32>,
+    ) -> Option<(usize, i32)> {
+        let cells = (self.width * self.height) as i32;
+        if let Some(col) =
+            (0..self.width).find(|&col| pos.can_play(col) && pos.is_winning_move(col))
+        {
+            return Some((col, (cells + 1 - pos.moves() as i32) / 2));
+        }
+        column_order(self.width)
+            .filter(|&col| pos.can_play(col))
+            .filter_map(|col| {
+                let mut child = *pos;
+                child.play(col);
+                Some((col, -probe(&child)?))
+            })
+            .min_by_key(|&(_, score)| Reverse(score))
+    }
+}
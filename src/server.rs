@@ -0,0 +1,960 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use tracing::{debug, info, warn};
+
+use crate::analysis::evaluate;
+use crate::bot::BotPlayer;
+use crate::config;
+use crate::game::{BOT, ConnectFour, PLAYER, column_char};
+use crate::history;
+
+// A lobby for networked games, spoken over TCP one line at a time:
+//
+//   name <name>         registers -> welcome <name> <rating>
+//   seek                waits for another player -> waiting, later start ...
+//   seek bot            plays the bot straight away -> start ...
+//   move <col>          plays a move in the current game
+//   resign              gives up the current game
+//   who                 -> players <name> ... (everyone connected)
+//   games               -> games <id>:<o>-<x> ... (games in progress)
+//   watch <id>          follows a game -> watching <id> <o> <x> <moves>
+//   unwatch             stops following it
+//   leaderboard [season] -> leaderboard <season> <name>:<rating>:<games> ...
+//   seasons             -> seasons <id>:<start>-<end> ... (the last one open)
+//   quit                disconnects
+//
+// A new game is announced with `start <o|x> <opponent> <rating>`, o moving
+// first. Every move is sent to the opponent as `moved <col>`, and the game
+// ends with `result <win|loss|draw> <new rating>` to both players. Columns are
+// 1-based. Errors are answered with `error <message>`.
+//
+// Watching needs no name. Spectators get `moved <o|x> <col>` for every move,
+// followed by the engine's view as `eval <score for o> <best col|none>`, and
+// `result <o|x|draw>` at the end.
+//
+// Ratings are kept per season. With a season length, a season that has run
+// its course is archived with its final standings before the next game is
+// rated, and everyone starts the new one at the initial rating again. The
+// leaderboard shows the current season unless an archived one is asked for;
+// season times are Unix timestamps.
+
+const SCHEMA_VERSION: i32 = 2;
+// Players listed by `leaderboard`.
+const LEADERBOARD_SIZE: usize = 10;
+const INITIAL_RATING: f64 = 1500.0;
+// Largest rating change a single game can bring.
+const K_FACTOR: f64 = 32.0;
+// The bot is rated like any other player, under a name no one can register.
+const BOT_NAME: &str = "bot";
+
+pub struct ServerOptions {
+    pub listen: String,
+    pub db: Option<PathBuf>,
+    pub rows: usize,
+    pub cols: usize,
+    pub season_days: Option<u64>,
+}
+
+// A player's line on the leaderboard.
+struct Standing {
+    name: String,
+    rating: f64,
+    games: i64,
+}
+
+// Players and their ratings in the current season, the standings of past
+// seasons, and every finished game.
+struct ServerDb {
+    conn: Connection,
+}
+
+impl ServerDb {
+    fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let db = Self { conn };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    fn migrate(&self) -> rusqlite::Result<()> {
+        let version: i32 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < 1 {
+            self.conn.execute_batch(
+                "CREATE TABLE players (
+                    name TEXT PRIMARY KEY,
+                    rating REAL NOT NULL,
+                    games INTEGER NOT NULL
+                );
+                CREATE TABLE games (
+                    id INTEGER PRIMARY KEY,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER NOT NULL,
+                    rows INTEGER NOT NULL,
+                    cols INTEGER NOT NULL,
+                    first TEXT NOT NULL,
+                    second TEXT NOT NULL,
+                    moves TEXT NOT NULL,
+                    winner TEXT
+                );",
+            )?;
+        }
+        if version < 2 {
+            // Ratings so far make up the first season.
+            self.conn.execute_batch(
+                "CREATE TABLE seasons (
+                    id INTEGER PRIMARY KEY,
+                    started_at INTEGER NOT NULL,
+                    ended_at INTEGER
+                );
+                CREATE TABLE standings (
+                    season INTEGER NOT NULL REFERENCES seasons (id),
+                    rank INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    rating REAL NOT NULL,
+                    games INTEGER NOT NULL,
+                    PRIMARY KEY (season, rank)
+                );",
+            )?;
+            self.conn.execute(
+                "INSERT INTO seasons (started_at)
+                 VALUES (COALESCE((SELECT MIN(started_at) FROM games), ?1))",
+                [history::now()],
+            )?;
+        }
+        self.conn
+            .pragma_update(None, "user_version", SCHEMA_VERSION)
+    }
+
+    fn rating(&self, name: &str) -> rusqlite::Result<f64> {
+        let rating = self
+            .conn
+            .query_row(
+                "SELECT rating FROM players WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(rating.unwrap_or(INITIAL_RATING))
+    }
+
+    fn set_rating(&self, name: &str, rating: f64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO players (name, rating, games) VALUES (?1, ?2, 1)
+             ON CONFLICT (name) DO UPDATE SET rating = ?2, games = games + 1",
+            params![name, rating],
+        )?;
+        Ok(())
+    }
+
+    // The open season and when it started.
+    fn current_season(&self) -> rusqlite::Result<(i64, i64)> {
+        self.conn.query_row(
+            "SELECT id, started_at FROM seasons WHERE ended_at IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    // Archives the current season if it started at least `days` days ago and
+    // opens the next one with every rating reset. Returns the archived season.
+    fn end_season_after(&self, days: u64) -> rusqlite::Result<Option<i64>> {
+        let (season, started_at) = self.current_season()?;
+        let now = history::now();
+        if now - started_at < days as i64 * 86_400 {
+            return Ok(None);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO standings (season, rank, name, rating, games)
+             SELECT ?1, ROW_NUMBER() OVER (ORDER BY rating DESC, name), name, rating, games
+             FROM players",
+            [season],
+        )?;
+        tx.execute(
+            "UPDATE seasons SET ended_at = ?2 WHERE id = ?1",
+            params![season, now],
+        )?;
+        tx.execute("DELETE FROM players", [])?;
+        tx.execute("INSERT INTO seasons (started_at) VALUES (?1)", [now])?;
+        tx.commit()?;
+        Ok(Some(season))
+    }
+
+    // The best players of `season`, the current one if None.
+    fn leaderboard(&self, season: Option<i64>) -> rusqlite::Result<(i64, Vec<Standing>)> {
+        let current = self.current_season()?.0;
+        let season = season.unwrap_or(current);
+        let sql = if season == current {
+            "SELECT name, rating, games FROM players ORDER BY rating DESC, name LIMIT ?1"
+        } else {
+            "SELECT name, rating, games FROM standings WHERE season = ?2 ORDER BY rank LIMIT ?1"
+        };
+        let mut stmt = self.conn.prepare(sql)?;
+        let limit = LEADERBOARD_SIZE as i64;
+        let map = |row: &rusqlite::Row| {
+            Ok(Standing {
+                name: row.get(0)?,
+                rating: row.get(1)?,
+                games: row.get(2)?,
+            })
+        };
+        let standings = if season == current {
+            stmt.query_map([limit], map)?
+                .collect::<rusqlite::Result<_>>()?
+        } else {
+            stmt.query_map([limit, season], map)?
+                .collect::<rusqlite::Result<_>>()?
+        };
+        Ok((season, standings))
+    }
+
+    fn seasons(&self) -> rusqlite::Result<Vec<(i64, i64, Option<i64>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, started_at, ended_at FROM seasons ORDER BY id")?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect()
+    }
+
+    fn season_exists(&self, season: i64) -> rusqlite::Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM seasons WHERE id = ?1)",
+            [season],
+            |row| row.get(0),
+        )
+    }
+
+    fn record(&self, table: &Table, winner: Option<&str>) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO games (started_at, finished_at, rows, cols, first, second, moves, winner)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                table.started_at,
+                history::now(),
+                table.game.rows as i64,
+                table.game.cols as i64,
+                table.seats[0].name(),
+                table.seats[1].name(),
+                table.moves,
+                winner,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+// The game a client is playing, shared with the opponent, and its seat there.
+type Slot = Arc<Mutex<Option<(Arc<Mutex<Table>>, usize)>>>;
+
+// A registered connection. Lines sent to `outbox` are written to its socket.
+#[derive(Clone)]
+struct Client {
+    name: String,
+    outbox: Sender<String>,
+    slot: Slot,
+}
+
+impl Client {
+    fn send(&self, line: String) {
+        // A closed outbox means the client is gone; its reader cleans up.
+        let _ = self.outbox.send(line);
+    }
+}
+
+enum Seat {
+    Human(Client),
+    Bot(Box<BotPlayer>),
+}
+
+impl Seat {
+    fn name(&self) -> &str {
+        match self {
+            Seat::Human(client) => &client.name,
+            Seat::Bot(_) => BOT_NAME,
+        }
+    }
+
+    fn send(&self, line: String) {
+        if let Seat::Human(client) = self {
+            client.send(line);
+        }
+    }
+}
+
+// A read-only follower of a game, identified by its connection.
+struct Spectator {
+    connection: u64,
+    outbox: Sender<String>,
+}
+
+// A game in progress: seat 0 plays o and moves first, seat 1 plays x.
+struct Table {
+    id: u64,
+    game: ConnectFour,
+    moves: String,
+    seats: [Seat; 2],
+    started_at: i64,
+    spectators: Vec<Spectator>,
+    // Set once the game is won, drawn or resigned.
+    finished: bool,
+    // Comments for spectators; created when the first one arrives.
+    analyst: Option<Box<BotPlayer>>,
+}
+
+impl Table {
+    fn to_move(&self) -> usize {
+        self.moves.len() % 2
+    }
+
+    // Spectators that have gone away are dropped.
+    fn broadcast(&mut self, line: &str) {
+        self.spectators
+            .retain(|spectator| spectator.outbox.send(line.to_string()).is_ok());
+    }
+
+    // The analyst's score for o and its best move for the side to move.
+    fn commentary(&mut self) -> Option<String> {
+        let to_move = piece(self.to_move());
+        let analyst = self.analyst.as_mut()?;
+        let analysis = evaluate(analyst, &self.game, to_move);
+        let score = if to_move == PLAYER {
+            analysis.score
+        } else {
+            -analysis.score
+        };
+        Some(format!(
+            "eval {:+} {}",
+            score,
+            analysis
+                .best_move
+                .map_or("none".to_string(), |col| (col + 1).to_string())
+        ))
+    }
+}
+
+fn piece(seat: usize) -> i8 {
+    if seat == 0 { PLAYER } else { BOT }
+}
+
+#[derive(Default)]
+struct Lobby {
+    online: HashSet<String>,
+    waiting: Option<Client>,
+    // Finished games drop out on their own once their players leave the table.
+    tables: BTreeMap<u64, Weak<Mutex<Table>>>,
+    next_table: u64,
+}
+
+// The state of one connection.
+struct Session {
+    connection: u64,
+    outbox: Sender<String>,
+    client: Option<Client>,
+    watching: Option<Weak<Mutex<Table>>>,
+}
+
+struct Server<'a> {
+    lobby: Mutex<Lobby>,
+    db: Mutex<ServerDb>,
+    make_bot: &'a (dyn Fn() -> BotPlayer + Sync),
+    make_analyst: &'a (dyn Fn() -> BotPlayer + Sync),
+    next_connection: AtomicU64,
+    rows: usize,
+    cols: usize,
+    season_days: Option<u64>,
+}
+
+// A panicking client thread must not take the whole server down with it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+impl Server<'_> {
+    fn end_season_if_due(&self, db: &ServerDb) -> rusqlite::Result<()> {
+        if let Some(days) = self.season_days
+            && let Some(season) = db.end_season_after(days)?
+        {
+            info!(season, "season ended");
+        }
+        Ok(())
+    }
+
+    fn leaderboard(&self, season: Option<&str>) -> Result<String, String> {
+        let season = match season {
+            Some(season) => Some(
+                season
+                    .parse::<i64>()
+                    .map_err(|_| format!("no season '{}'", season))?,
+            ),
+            None => None,
+        };
+        let db = lock(&self.db);
+        self.end_season_if_due(&db).map_err(|err| err.to_string())?;
+        if let Some(season) = season
+            && !db.season_exists(season).map_err(|err| err.to_string())?
+        {
+            return Err(format!("no season '{}'", season));
+        }
+        let (season, standings) = db.leaderboard(season).map_err(|err| err.to_string())?;
+        let mut line = format!("leaderboard {}", season);
+        for standing in standings {
+            line.push_str(&format!(
+                " {}:{:.0}:{}",
+                standing.name, standing.rating, standing.games
+            ));
+        }
+        Ok(line)
+    }
+
+    fn seasons(&self) -> Result<String, String> {
+        let db = lock(&self.db);
+        self.end_season_if_due(&db).map_err(|err| err.to_string())?;
+        let mut line = "seasons".to_string();
+        for (id, started_at, ended_at) in db.seasons().map_err(|err| err.to_string())? {
+            line.push_str(&format!(
+                " {}:{}-{}",
+                id,
+                started_at,
+                ended_at.map_or(String::new(), |ended_at| ended_at.to_string())
+            ));
+        }
+        Ok(line)
+    }
+
+    fn start(&self, seats: [Seat; 2]) -> rusqlite::Result<()> {
+        let ratings = {
+            let db = lock(&self.db);
+            [db.rating(seats[0].name())?, db.rating(seats[1].name())?]
+        };
+        let table = {
+            let mut lobby = lock(&self.lobby);
+            lobby.tables.retain(|_, table| table.strong_count() > 0);
+            lobby.next_table += 1;
+            let id = lobby.next_table;
+            let table = Arc::new(Mutex::new(Table {
+                id,
+                game: ConnectFour::new(self.rows, self.cols),
+                moves: String::new(),
+                seats,
+                started_at: history::now(),
+                spectators: Vec::new(),
+                finished: false,
+                analyst: None,
+            }));
+            lobby.tables.insert(id, Arc::downgrade(&table));
+            table
+        };
+        let guard = lock(&table);
+        info!(
+            id = guard.id,
+            first = guard.seats[0].name(),
+            second = guard.seats[1].name(),
+            "server game started"
+        );
+        for (seat, color) in [(0, "o"), (1, "x")] {
+            if let Seat::Human(client) = &guard.seats[seat] {
+                *lock(&client.slot) = Some((table.clone(), seat));
+                client.send(format!(
+                    "start {} {} {:.0}",
+                    color,
+                    guard.seats[1 - seat].name(),
+                    ratings[1 - seat]
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Plays `col` for `seat`, then the bot's answer if it is the opponent.
+    fn play(&self, table: &mut Table, seat: usize, col: usize) -> Result<(), String> {
+        if table.finished {
+            return Err("the game is over".to_string());
+        }
+        if table.to_move() != seat {
+            return Err("it is not your turn".to_string());
+        }
+        if !table.game.get_valid_moves().contains(&col) {
+            return Err(format!("column {} is not playable", col + 1));
+        }
+        self.apply(table, col);
+        if !table.finished
+            && let Seat::Bot(bot) = &mut table.seats[1 - seat]
+        {
+            // The bot searches as x, which is its seat.
+            if let Some(col) = bot.get_best_move(&table.game) {
+                self.apply(table, col);
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, table: &mut Table, col: usize) {
+        let seat = table.to_move();
+        let won = table.game.winning_move(col, piece(seat));
+        table.game.drop_piece(col, piece(seat));
+        table.moves.push(column_char(col));
+        table.seats[1 - seat].send(format!("moved {}", col + 1));
+        if !table.spectators.is_empty() {
+            let color = if seat == 0 { "o" } else { "x" };
+            table.broadcast(&format!("moved {} {}", color, col + 1));
+        }
+        if won {
+            self.finish(table, Some(seat));
+        } else if table.game.get_valid_moves().is_empty() {
+            self.finish(table, None);
+        } else if !table.spectators.is_empty()
+            && let Some(comment) = table.commentary()
+        {
+            table.broadcast(&comment);
+        }
+    }
+
+    // Updates both ratings with the usual Elo formula and stores the game.
+    fn rate(&self, table: &Table, winner: Option<usize>) -> rusqlite::Result<[f64; 2]> {
+        let db = lock(&self.db);
+        self.end_season_if_due(&db)?;
+        let names = [table.seats[0].name(), table.seats[1].name()];
+        let ratings = [db.rating(names[0])?, db.rating(names[1])?];
+        let scores = match winner {
+            Some(0) => [1.0, 0.0],
+            Some(_) => [0.0, 1.0],
+            None => [0.5, 0.5],
+        };
+        let updated = [0, 1].map(|seat| {
+            let expected = expected_score(ratings[seat], ratings[1 - seat]);
+            ratings[seat] + K_FACTOR * (scores[seat] - expected)
+        });
+        db.set_rating(names[0], updated[0])?;
+        db.set_rating(names[1], updated[1])?;
+        db.record(table, winner.map(|seat| names[seat]))?;
+        Ok(updated)
+    }
+
+    // Rates and records the game, tells everyone at the table and frees the seats.
+    fn finish(&self, table: &mut Table, winner: Option<usize>) {
+        table.finished = true;
+        let names = [table.seats[0].name(), table.seats[1].name()];
+        let updated = self.rate(table, winner).unwrap_or_else(|err| {
+            warn!(%err, "could not save server game");
+            [INITIAL_RATING; 2]
+        });
+        info!(
+            first = names[0],
+            second = names[1],
+            winner = winner.map(|seat| names[seat]),
+            moves = %table.moves,
+            "server game finished"
+        );
+        for seat in [0, 1] {
+            let result = match winner {
+                Some(won) if won == seat => "win",
+                Some(_) => "loss",
+                None => "draw",
+            };
+            table.seats[seat].send(format!("result {} {:.0}", result, updated[seat]));
+            if let Seat::Human(client) = &table.seats[seat] {
+                *lock(&client.slot) = None;
+            }
+        }
+        table.broadcast(&format!(
+            "result {}",
+            winner.map_or("draw", |seat| if seat == 0 { "o" } else { "x" })
+        ));
+        table.spectators.clear();
+    }
+
+    fn current_table(client: &Client) -> Option<(Arc<Mutex<Table>>, usize)> {
+        lock(&client.slot).clone()
+    }
+
+    fn games(&self) -> String {
+        let tables: Vec<Arc<Mutex<Table>>> = lock(&self.lobby)
+            .tables
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let mut line = "games".to_string();
+        for table in tables {
+            let table = lock(&table);
+            if !table.finished {
+                line.push_str(&format!(
+                    " {}:{}-{}",
+                    table.id,
+                    table.seats[0].name(),
+                    table.seats[1].name()
+                ));
+            }
+        }
+        line
+    }
+
+    fn unwatch(session: &mut Session) {
+        if let Some(table) = session.watching.take().and_then(|table| table.upgrade()) {
+            lock(&table)
+                .spectators
+                .retain(|spectator| spectator.connection != session.connection);
+        }
+    }
+
+    fn watch(&self, session: &mut Session, id: &str) -> Result<(), String> {
+        let weak = id
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| lock(&self.lobby).tables.get(&id).cloned())
+            .ok_or_else(|| format!("no game '{}'", id))?;
+        let table = weak.upgrade().ok_or_else(|| format!("no game '{}'", id))?;
+        Self::unwatch(session);
+        let mut guard = lock(&table);
+        if guard.finished {
+            return Err(format!("game {} is over", id));
+        }
+        guard.spectators.push(Spectator {
+            connection: session.connection,
+            outbox: session.outbox.clone(),
+        });
+        let _ = session.outbox.send(format!(
+            "watching {} {} {} {}",
+            guard.id,
+            guard.seats[0].name(),
+            guard.seats[1].name(),
+            guard.moves
+        ));
+        if guard.analyst.is_none() {
+            guard.analyst = Some(Box::new((self.make_analyst)()));
+        }
+        if let Some(comment) = guard.commentary() {
+            let _ = session.outbox.send(comment);
+        }
+        info!(
+            id = guard.id,
+            spectators = guard.spectators.len(),
+            "spectator joined"
+        );
+        session.watching = Some(weak);
+        Ok(())
+    }
+
+    fn command(&self, session: &mut Session, line: &str) -> Result<(), String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let outbox = session.outbox.clone();
+        match words.as_slice() {
+            ["games"] => {
+                let _ = outbox.send(self.games());
+                return Ok(());
+            }
+            ["watch", id] => return self.watch(session, id),
+            ["leaderboard", season @ ..] if season.len() <= 1 => {
+                let _ = outbox.send(self.leaderboard(season.first().copied())?);
+                return Ok(());
+            }
+            ["seasons"] => {
+                let _ = outbox.send(self.seasons()?);
+                return Ok(());
+            }
+            ["unwatch"] => {
+                Self::unwatch(session);
+                return Ok(());
+            }
+            _ => {}
+        }
+        let client = &mut session.client;
+        match (words.as_slice(), client.as_ref()) {
+            ([], _) => Ok(()),
+            (["name", _], Some(_)) => Err("already registered".to_string()),
+            (["name", name], None) => {
+                let valid = name.len() <= 32
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if !valid || *name == BOT_NAME {
+                    return Err(format!("invalid name '{}'", name));
+                }
+                if !lock(&self.lobby).online.insert(name.to_string()) {
+                    return Err(format!("'{}' is already connected", name));
+                }
+                let rating = lock(&self.db).rating(name).map_err(|err| err.to_string())?;
+                info!(name, "player registered");
+                let _ = outbox.send(format!("welcome {} {:.0}", name, rating));
+                *client = Some(Client {
+                    name: name.to_string(),
+                    outbox: outbox.clone(),
+                    slot: Slot::default(),
+                });
+                Ok(())
+            }
+            (_, None) => Err("register with 'name <name>' first".to_string()),
+            (["who"], Some(_)) => {
+                let mut names: Vec<String> = lock(&self.lobby).online.iter().cloned().collect();
+                names.sort();
+                let _ = outbox.send(format!("players {}", names.join(" ")));
+                Ok(())
+            }
+            (["seek", rest @ ..], Some(me)) => {
+                if Self::current_table(me).is_some() {
+                    return Err("already in a game".to_string());
+                }
+                match rest {
+                    ["bot"] => {
+                        let mut lobby = lock(&self.lobby);
+                        if lobby
+                            .waiting
+                            .as_ref()
+                            .is_some_and(|other| other.name == me.name)
+                        {
+                            lobby.waiting = None;
+                        }
+                        drop(lobby);
+                        self.start([
+                            Seat::Human(me.clone()),
+                            Seat::Bot(Box::new((self.make_bot)())),
+                        ])
+                        .map_err(|err| err.to_string())
+                    }
+                    [] => {
+                        let mut lobby = lock(&self.lobby);
+                        match lobby.waiting.take() {
+                            Some(other) if other.name != me.name => {
+                                drop(lobby);
+                                self.start([Seat::Human(other), Seat::Human(me.clone())])
+                                    .map_err(|err| err.to_string())
+                            }
+                            _ => {
+                                lobby.waiting = Some(me.clone());
+                                let _ = outbox.send("waiting".to_string());
+                                Ok(())
+                            }
+                        }
+                    }
+                    _ => Err("usage: seek [bot]".to_string()),
+                }
+            }
+            (["move", col], Some(me)) => {
+                let (table, seat) = Self::current_table(me).ok_or("not in a game")?;
+                let col = col
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|col| (1..=self.cols).contains(col))
+                    .ok_or_else(|| format!("column must be 1 to {}", self.cols))?;
+                self.play(&mut lock(&table), seat, col - 1)
+            }
+            (["resign"], Some(me)) => {
+                let (table, seat) = Self::current_table(me).ok_or("not in a game")?;
+                self.resign(&mut lock(&table), seat)
+            }
+            _ => Err(format!("unknown command '{}'", line.trim())),
+        }
+    }
+
+    fn resign(&self, table: &mut Table, seat: usize) -> Result<(), String> {
+        if table.finished {
+            return Err("the game is over".to_string());
+        }
+        info!(name = table.seats[seat].name(), "player resigned");
+        self.finish(table, Some(1 - seat));
+        Ok(())
+    }
+
+    fn serve_client(&self, stream: TcpStream) -> io::Result<()> {
+        let peer = stream.peer_addr()?;
+        info!(%peer, "client connected");
+        let (outbox, inbox) = mpsc::channel::<String>();
+        let mut writer = BufWriter::new(stream.try_clone()?);
+        // The writer stops once every copy of the outbox is gone, which may be
+        // after this connection ends if an opponent still holds one.
+        thread::spawn(move || {
+            for line in inbox {
+                if writeln!(writer, "{}", line)
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut session = Session {
+            connection: self.next_connection.fetch_add(1, Ordering::Relaxed),
+            outbox: outbox.clone(),
+            client: None,
+            watching: None,
+        };
+        let _ = outbox.send("hello, register with 'name <name>'".to_string());
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            debug!(%peer, command = %line, "server command");
+            if line.trim() == "quit" {
+                break;
+            }
+            if let Err(err) = self.command(&mut session, &line) {
+                let _ = outbox.send(format!("error {}", err));
+            }
+        }
+
+        // Leaving mid-game counts as resigning.
+        Self::unwatch(&mut session);
+        if let Some(me) = &session.client {
+            if let Some((table, seat)) = Self::current_table(me) {
+                let _ = self.resign(&mut lock(&table), seat);
+            }
+            let mut lobby = lock(&self.lobby);
+            lobby.online.remove(&me.name);
+            if lobby
+                .waiting
+                .as_ref()
+                .is_some_and(|other| other.name == me.name)
+            {
+                lobby.waiting = None;
+            }
+        }
+        info!(%peer, "client disconnected");
+        Ok(())
+    }
+}
+
+// Accepts clients until the process is stopped, one thread per connection.
+// `make_analyst` supplies the engine commenting for spectators.
+pub fn run(
+    make_bot: &(dyn Fn() -> BotPlayer + Sync),
+    make_analyst: &(dyn Fn() -> BotPlayer + Sync),
+    options: &ServerOptions,
+) -> Result<(), Box<dyn Error>> {
+    let path = match &options.db {
+        Some(path) => path.clone(),
+        None => {
+            let dir = config::data_dir().ok_or("cannot determine the data directory")?;
+            fs::create_dir_all(&dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+            dir.join("server.sqlite")
+        }
+    };
+    let server = Server {
+        lobby: Mutex::new(Lobby::default()),
+        db: Mutex::new(ServerDb::open(&path)?),
+        make_bot,
+        make_analyst,
+        next_connection: AtomicU64::new(0),
+        rows: options.rows,
+        cols: options.cols,
+        season_days: options.season_days,
+    };
+    let listener =
+        TcpListener::bind(&options.listen).map_err(|err| format!("{}: {}", options.listen, err))?;
+    eprintln!("Listening on {}.", listener.local_addr()?);
+
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let server = &server;
+                    scope.spawn(move || {
+                        if let Err(err) = server.serve_client(stream) {
+                            warn!(%err, "client connection failed");
+                        }
+                    });
+                }
+                Err(err) => warn!(%err, "could not accept a connection"),
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn expected_scores() {
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+        // 400 points ahead is ten to one.
+        assert!((expected_score(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!(
+            (expected_score(1600.0, 1450.0) + expected_score(1450.0, 1600.0) - 1.0).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn first_version_ratings_become_the_first_season() {
+        let path = env::temp_dir().join(format!("connect-four-server-{}.sqlite", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE players (
+                    name TEXT PRIMARY KEY,
+                    rating REAL NOT NULL,
+                    games INTEGER NOT NULL
+                );
+                CREATE TABLE games (
+                    id INTEGER PRIMARY KEY,
+                    started_at INTEGER NOT NULL,
+                    finished_at INTEGER NOT NULL,
+                    rows INTEGER NOT NULL,
+                    cols INTEGER NOT NULL,
+                    first TEXT NOT NULL,
+                    second TEXT NOT NULL,
+                    moves TEXT NOT NULL,
+                    winner TEXT
+                );
+                INSERT INTO players VALUES ('ann', 1516.0, 1), ('bob', 1484.0, 1);
+                INSERT INTO games VALUES (1, 100, 160, 6, 7, 'ann', 'bob', '1212121', 'ann');
+                PRAGMA user_version = 1;",
+            )
+            .unwrap();
+        }
+
+        let db = ServerDb::open(&path).unwrap();
+        assert_eq!(db.seasons().unwrap(), [(1, 100, None)]);
+        assert_eq!(db.rating("ann").unwrap(), 1516.0);
+        assert_eq!(db.rating("carol").unwrap(), INITIAL_RATING);
+        drop(db);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn seasons_archive_standings_and_reset_ratings() {
+        let db = ServerDb::open(Path::new(":memory:")).unwrap();
+        db.set_rating("ann", 1530.0).unwrap();
+        db.set_rating("bob", 1470.0).unwrap();
+        db.set_rating("bob", 1490.0).unwrap();
+        // The season has only just started.
+        assert_eq!(db.end_season_after(1).unwrap(), None);
+
+        assert_eq!(db.end_season_after(0).unwrap(), Some(1));
+        assert_eq!(db.rating("ann").unwrap(), INITIAL_RATING);
+        let seasons = db.seasons().unwrap();
+        assert_eq!(seasons.len(), 2);
+        assert!(seasons[0].2.is_some() && seasons[1].2.is_none());
+        assert!(db.season_exists(1).unwrap() && !db.season_exists(3).unwrap());
+
+        let (season, standings) = db.leaderboard(Some(1)).unwrap();
+        assert_eq!(season, 1);
+        let standings: Vec<(&str, f64, i64)> = standings
+            .iter()
+            .map(|standing| (standing.name.as_str(), standing.rating, standing.games))
+            .collect();
+        assert_eq!(standings, [("ann", 1530.0, 1), ("bob", 1490.0, 2)]);
+        let (season, standings) = db.leaderboard(None).unwrap();
+        assert_eq!((season, standings.len()), (2, 0));
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tracing::{debug, info};
+
+// Searches one session may have running or waiting at a time; more are turned
+// away, so that a single client cannot queue up the whole server.
+pub const SEARCHES_PER_SESSION: usize = 2;
+
+// One hosted game with its own engine state. `state` is locked while a move is
+// played or a search runs, so each game changes one step at a time.
+pub struct Session<T> {
+    pub id: u64,
+    pub state: tokio::sync::Mutex<T>,
+    last_used: Mutex<Instant>,
+    searches: Arc<Semaphore>,
+}
+
+impl<T> Session<T> {
+    fn touch(&self) {
+        *self.last_used.lock().expect("session clock") = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_used.lock().expect("session clock").elapsed()
+    }
+}
+
+pub enum SearchRefused {
+    // The session already has its share of searches.
+    SessionBusy,
+    // The manager is shutting down.
+    Closed,
+}
+
+// What a search holds while it runs: a slot of its session and one of the
+// server's, both given back when dropped.
+pub struct SearchPermit {
+    _session: OwnedSemaphorePermit,
+    _server: OwnedSemaphorePermit,
+}
+
+// Hosts many games at once for the server modes. Sessions left alone for
+// longer than the idle timeout are dropped, and searches across all sessions
+// share a bounded number of slots, handed out in the order asked for.
+pub struct SessionManager<T> {
+    sessions: Mutex<HashMap<u64, Arc<Session<T>>>>,
+    next_id: AtomicU64,
+    idle_timeout: Duration,
+    searches: Arc<Semaphore>,
+}
+
+impl<T: Send + 'static> SessionManager<T> {
+    // At most `searches` searches run at the same time.
+    pub fn new(idle_timeout: Duration, searches: usize) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            idle_timeout,
+            searches: Arc::new(Semaphore::new(searches.max(1))),
+        }
+    }
+
+    pub fn create(&self, state: T) -> Arc<Session<T>> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let session = Arc::new(Session {
+            id,
+            state: tokio::sync::Mutex::new(state),
+            last_used: Mutex::new(Instant::now()),
+            searches: Arc::new(Semaphore::new(SEARCHES_PER_SESSION)),
+        });
+        self.sessions
+            .lock()
+            .expect("sessions lock")
+            .insert(id, session.clone());
+        session
+    }
+
+    // Looking a session up counts as using it.
+    pub fn get(&self, id: u64) -> Option<Arc<Session<T>>> {
+        let session = self
+            .sessions
+            .lock()
+            .expect("sessions lock")
+            .get(&id)
+            .cloned()?;
+        session.touch();
+        Some(session)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().expect("sessions lock").len()
+    }
+
+    // Waits for a server slot once the session has one of its own.
+    pub async fn search_permit(&self, session: &Session<T>) -> Result<SearchPermit, SearchRefused> {
+        let own = match session.searches.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(TryAcquireError::NoPermits) => return Err(SearchRefused::SessionBusy),
+            Err(TryAcquireError::Closed) => return Err(SearchRefused::Closed),
+        };
+        let server = self
+            .searches
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| SearchRefused::Closed)?;
+        Ok(SearchPermit {
+            _session: own,
+            _server: server,
+        })
+    }
+
+    // Drops the sessions idle for longer than the timeout; searches still
+    // running in them finish first.
+    pub fn remove_idle(&self) -> usize {
+        let mut sessions = self.sessions.lock().expect("sessions lock");
+        let before = sessions.len();
+        sessions.retain(|&id, session| {
+            let keep = session.idle_for() < self.idle_timeout;
+            if !keep {
+                debug!(id, "idle session removed");
+            }
+            keep
+        });
+        before - sessions.len()
+    }
+
+    // Sweeps for idle sessions a few times per timeout, on the current runtime.
+    pub fn spawn_reaper(self: &Arc<Self>) {
+        let manager = Arc::downgrade(self);
+        let period = (self.idle_timeout / 4).max(Duration::from_secs(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                let removed = manager.remove_idle();
+                if removed > 0 {
+                    info!(removed, open = manager.len(), "idle sessions removed");
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,429 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::game::{ConnectFour, EMPTY};
+use crate::tablebase::Tablebase;
+use crate::transposition::TranspositionTable;
+
+// Bitboard layout: each column takes `height + 1` bits, bottom cell first, with
+// one spare bit on top so that shifted lines never wrap into the next column.
+#[derive(Clone, Copy)]
+pub struct Position {
+    width: usize,
+    height: usize,
+    current: u64,
+    mask: u64,
+    moves: usize,
+    bottom: u64,
+    board: u64,
+}
+
+impl Position {
+    pub fn new(width: usize, height: usize) -> Option<Self> {
+        if width * (height + 1) > 64 {
+            return None;
+        }
+        let bottom = (0..width).fold(0, |acc, col| acc | 1 << (col * (height + 1)));
+        Some(Self {
+            width,
+            height,
+            current: 0,
+            mask: 0,
+            moves: 0,
+            bottom,
+            board: bottom * ((1 << height) - 1),
+        })
+    }
+
+    // `to_move` is the piece of the side whose turn it is in `game`.
+    // Only positions under standard gravity can be represented.
+    pub fn from_game(game: &ConnectFour, to_move: i8) -> Option<Self> {
+        if !game.rules.is_standard() {
+            return None;
+        }
+        let mut pos = Self::new(game.cols, game.rows)?;
+        for col in 0..game.cols {
+            for height in 0..game.rows {
+                let cell = game.board[game.rows - 1 - height][col];
+                if cell == EMPTY {
+                    break;
+                }
+                let bit = 1 << (col * (game.rows + 1) + height);
+                pos.mask |= bit;
+                if cell == to_move {
+                    pos.current |= bit;
+                }
+                pos.moves += 1;
+            }
+        }
+        Some(pos)
+    }
+
+    // Builds a position from raw bitboards in the layout above, `current`
+    // holding the stones of the side to move.
+    pub fn from_bits(width: usize, height: usize, current: u64, mask: u64) -> Option<Self> {
+        let mut pos = Self::new(width, height)?;
+        pos.current = current;
+        pos.mask = mask;
+        pos.moves = mask.count_ones() as usize;
+        Some(pos)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    // Bit of the cell `row` above the bottom of `col`.
+    pub fn cell_bit(&self, col: usize, row: usize) -> u64 {
+        1 << (col * (self.height + 1) + row)
+    }
+
+    // Whether either side already has four in a row.
+    pub fn has_four(&self) -> bool {
+        self.alignment(self.current) || self.alignment(self.current ^ self.mask)
+    }
+
+    pub fn cells(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn can_play(&self, col: usize) -> bool {
+        self.mask & self.top_mask(col) == 0
+    }
+
+    pub fn play(&mut self, col: usize) {
+        self.play_bits((self.mask + self.bottom_mask(col)) & self.column_mask(col));
+    }
+
+    pub fn is_winning_move(&self, col: usize) -> bool {
+        self.winning_position() & self.possible() & self.column_mask(col) != 0
+    }
+
+    pub fn can_win_next(&self) -> bool {
+        self.winning_position() & self.possible() != 0
+    }
+
+    // Whether the opponent would win if it were its turn.
+    pub fn opponent_threatens(&self) -> bool {
+        self.opponent_winning_position() & self.possible() != 0
+    }
+
+    pub fn key(&self) -> u64 {
+        self.current + self.mask
+    }
+
+    // The key of the position seen in a mirror. Each column's share of the key
+    // stays within the column's bits, so mirroring moves whole columns.
+    pub fn mirrored_key(&self) -> u64 {
+        let key = self.key();
+        let bits = self.height + 1;
+        let column = (1 << bits) - 1;
+        (0..self.width).fold(0, |acc, col| {
+            acc | ((key >> (col * bits)) & column) << ((self.width - 1 - col) * bits)
+        })
+    }
+
+    fn play_bits(&mut self, bits: u64) {
+        self.current ^= self.mask;
+        self.mask |= bits;
+        self.moves += 1;
+    }
+
+    fn top_mask(&self, col: usize) -> u64 {
+        (1 << (self.height - 1)) << (col * (self.height + 1))
+    }
+
+    fn bottom_mask(&self, col: usize) -> u64 {
+        1 << (col * (self.height + 1))
+    }
+
+    fn column_mask(&self, col: usize) -> u64 {
+        ((1 << self.height) - 1) << (col * (self.height + 1))
+    }
+
+    fn possible(&self) -> u64 {
+        (self.mask + self.bottom) & self.board
+    }
+
+    fn winning_position(&self) -> u64 {
+        self.compute_winning_position(self.current)
+    }
+
+    fn opponent_winning_position(&self) -> u64 {
+        self.compute_winning_position(self.current ^ self.mask)
+    }
+
+    // Moves that do not hand the opponent an immediate win.
+    fn possible_non_losing_moves(&self) -> u64 {
+        let mut possible = self.possible();
+        let opponent_win = self.opponent_winning_position();
+        let forced = possible & opponent_win;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                return 0;
+            }
+            possible = forced;
+        }
+        possible & !(opponent_win >> 1)
+    }
+
+    fn move_score(&self, bits: u64) -> u32 {
+        self.compute_winning_position(self.current | bits)
+            .count_ones()
+    }
+
+    fn alignment(&self, pos: u64) -> bool {
+        [1, self.height + 1, self.height, self.height + 2]
+            .into_iter()
+            .any(|shift| {
+                let m = pos & (pos >> shift);
+                m & (m >> (2 * shift)) != 0
+            })
+    }
+
+    // Empty cells that would complete a line of four for the given stones.
+    fn compute_winning_position(&self, pos: u64) -> u64 {
+        let h = self.height;
+        let mut r = (pos << 1) & (pos << 2) & (pos << 3);
+
+        for shift in [h + 1, h, h + 2] {
+            let mut p = (pos << shift) & (pos << (2 * shift));
+            r |= p & (pos << (3 * shift));
+            r |= p & (pos >> shift);
+            p = (pos >> shift) & (pos >> (2 * shift));
+            r |= p & (pos << shift);
+            r |= p & (pos >> (3 * shift));
+        }
+
+        r & (self.board ^ self.mask)
+    }
+}
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+// Splits `hash_mb` between `jobs` solvers as the number of solvers and the
+// megabytes each gets. A table takes at least a megabyte, so a small budget
+// runs fewer solvers rather than go over it.
+pub fn split_hash(hash_mb: usize, jobs: usize) -> (usize, usize) {
+    let solvers = jobs.clamp(1, hash_mb.max(1));
+    if solvers < jobs {
+        warn!(jobs, solvers, hash_mb, "too little hash for every job");
+    }
+    (solvers, (hash_mb / solvers).max(1))
+}
+
+// Scores follow the usual convention: positive if the side to move wins, and
+// the sooner the win the larger the score. Table keys only identify a position
+// for one board size, so a solver must not be shared between sizes.
+pub struct Solver {
+    table: TranspositionTable<u8>,
+    tablebase: Option<Arc<Tablebase>>,
+    nodes: u64,
+}
+
+impl Solver {
+    // `hash_mb` is the memory budget of the transposition table in megabytes.
+    pub fn new(hash_mb: usize) -> Self {
+        let table = TranspositionTable::new(hash_mb);
+        debug!(
+            entries = table.entries(),
+            mb = table.megabytes(),
+            "transposition table allocated"
+        );
+        Self {
+            table,
+            tablebase: None,
+            nodes: 0,
+        }
+    }
+
+    // Positions the tablebase covers are scored without searching.
+    pub fn with_tablebase(mut self, tablebase: Option<Arc<Tablebase>>) -> Self {
+        self.tablebase = tablebase;
+        self
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    // Fraction of table slots holding an entry.
+    pub fn occupancy(&self) -> f64 {
+        self.table.occupancy()
+    }
+
+    pub fn solve(&mut self, pos: &Position) -> i32 {
+        if pos.can_win_next() {
+            return win_score(pos, 1);
+        }
+        let mut min = -((pos.cells() - pos.moves) as i32) / 2;
+        let mut max = ((pos.cells() + 1 - pos.moves) as i32) / 2;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let score = self.negamax(pos, med, med + 1);
+            if score <= med {
+                max = score;
+            } else {
+                min = score;
+            }
+        }
+        debug!(
+            score = min,
+            nodes = self.nodes,
+            occupancy = self.occupancy(),
+            "solve finished"
+        );
+        min
+    }
+
+    // Whether the side to move can force a win with at most `n` of its own moves.
+    pub fn wins_within(&mut self, pos: &Position, n: u32) -> bool {
+        if n == 0 {
+            return false;
+        }
+        if pos.can_win_next() {
+            return true;
+        }
+        let target = win_score(pos, n);
+        if target <= 0 {
+            return false;
+        }
+        self.negamax(pos, target - 1, target) >= target
+    }
+
+    // Whether the score lies within `margin` of a draw, settled by two
+    // null-window searches, which is much cheaper than solving exactly.
+    pub fn is_balanced(&mut self, pos: &Position, margin: i32) -> bool {
+        if pos.can_win_next() {
+            return win_score(pos, 1) <= margin;
+        }
+        self.negamax(pos, margin, margin + 1) <= margin
+            && self.negamax(pos, -margin - 1, -margin) >= -margin
+    }
+
+    // 1 if the side to move wins, -1 if it loses and 0 for a draw, settled by
+    // null-window searches like `is_balanced`.
+    pub fn outcome(&mut self, pos: &Position) -> i32 {
+        if pos.can_win_next() || self.negamax(pos, 0, 1) > 0 {
+            1
+        } else if self.negamax(pos, -1, 0) < 0 {
+            -1
+        } else {
+            0
+        }
+    }
+
+    fn negamax(&mut self, pos: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        if let Some(score) = self.tablebase.as_ref().and_then(|tb| tb.probe(pos)) {
+            return score;
+        }
+        let cells = pos.cells();
+
+        let next = pos.possible_non_losing_moves();
+        if next == 0 {
+            return -((cells - pos.moves) as i32) / 2;
+        }
+        if pos.moves >= cells - 2 {
+            return 0;
+        }
+
+        let min = -((cells - 2 - pos.moves) as i32) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        // Offset so that every reachable bound is stored as a non-zero byte.
+        let min_score = -(cells as i32) / 2;
+        let mut max = ((cells - 1 - pos.moves) as i32) / 2;
+        if let Some(stored) = self.table.get(pos.key()) {
+            max = stored as i32 + min_score - 1;
+        }
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        let mut candidates: Vec<(u32, u64)> = column_order(pos.width)
+            .map(|col| next & pos.column_mask(col))
+            .filter(|&bits| bits != 0)
+            .map(|bits| (pos.move_score(bits), bits))
+            .collect();
+        candidates.sort_by_key(|&(score, _)| Reverse(score));
+
+        for (_, bits) in candidates {
+            let mut child = *pos;
+            child.play_bits(bits);
+            let score = -self.negamax(&child, -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        self.table.put(pos.key(), (alpha - min_score + 1) as u8);
+        alpha
+    }
+}
+
+// Score of a win on the side to move's `n`-th next move.
+fn win_score(pos: &Position, n: u32) -> i32 {
+    ((pos.cells() + 1 - pos.moves) as i32) / 2 - (n as i32 - 1)
+}
+
+// Center columns first: they are most often best and make pruning effective.
+pub fn column_order(width: usize) -> impl Iterator<Item = usize> {
+    (0..width).map(move |i| {
+        if i.is_multiple_of(2) {
+            width / 2 + i / 2
+        } else {
+            width / 2 - 1 - i / 2
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::PLAYER;
+
+    #[test]
+    fn splits_the_hash_between_solvers() {
+        assert_eq!(split_hash(16, 4), (4, 4));
+        assert_eq!(split_hash(10, 4), (4, 2));
+        assert_eq!(split_hash(2, 8), (2, 1));
+        assert_eq!(split_hash(0, 0), (1, 1));
+    }
+
+    #[test]
+    fn scores_do_not_depend_on_the_hash() {
+        let game = ConnectFour::from_moves(4, 5, "33", PLAYER).unwrap();
+        let pos = Position::from_game(&game, PLAYER).unwrap();
+        let mut small = Solver::new(1);
+        let score = small.solve(&pos);
+        assert_eq!(Solver::new(4).solve(&pos), score);
+        assert!(small.occupancy() > 0.0);
+        assert_eq!(small.outcome(&pos), score.signum());
+    }
+}
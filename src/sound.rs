@@ -0,0 +1,89 @@
+use std::io::{IsTerminal, Write, stdout};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::SoundConfig;
+use crate::explain::winning_columns;
+use crate::game::{BOT, ConnectFour, PLAYER};
+use crate::history::Winner;
+use crate::observer::Observer;
+
+// Audio feedback through the terminal bell. Everything but interactive play
+// uses `Sound::off()`, and so does play when stdout is not a terminal.
+#[derive(Clone)]
+pub struct Sound {
+    bot_move: bool,
+    threat: bool,
+    game_end: bool,
+    // Moves that would win on the spot, for either side, after the last move.
+    threats: usize,
+}
+
+impl Sound {
+    pub fn off() -> Self {
+        Self {
+            bot_move: false,
+            threat: false,
+            game_end: false,
+            threats: 0,
+        }
+    }
+
+    // With sound enabled every cue plays unless the config turns it off.
+    pub fn new(enabled: bool, config: &SoundConfig) -> Self {
+        if !enabled || !stdout().is_terminal() {
+            return Self::off();
+        }
+        Self {
+            bot_move: config.bot_move.unwrap_or(true),
+            threat: config.threat.unwrap_or(true),
+            game_end: config.game_end.unwrap_or(true),
+            threats: 0,
+        }
+    }
+
+    // Rings the bell once per entry, pausing for the given milliseconds after it.
+    fn ring(pauses: &[u64]) {
+        let mut out = stdout();
+        for &pause in pauses {
+            let _ = out.write_all(b"\x07").and_then(|_| out.flush());
+            thread::sleep(Duration::from_millis(pause));
+        }
+    }
+
+    pub fn bot_moved(&self) {
+        if self.bot_move {
+            Self::ring(&[0]);
+        }
+    }
+
+    // A quick double ring, when a side gets a move that would win on the spot.
+    pub fn threat(&self) {
+        if self.threat {
+            Self::ring(&[120, 0]);
+        }
+    }
+
+    pub fn game_over(&self) {
+        if self.game_end {
+            Self::ring(&[150, 150, 300, 0]);
+        }
+    }
+}
+
+impl Observer for Sound {
+    fn on_move(&mut self, game: &ConnectFour, piece: i8, _col: usize) {
+        if piece == BOT {
+            self.bot_moved();
+        }
+        let open = winning_columns(game, PLAYER).len() + winning_columns(game, BOT).len();
+        if open > self.threats {
+            self.threat();
+        }
+        self.threats = open;
+    }
+
+    fn on_game_end(&mut self, _game: &ConnectFour, _winner: Winner) {
+        self.game_over();
+    }
+}
@@ -0,0 +1,256 @@
+use std::error::Error;
+use tracing::info;
+
+use crate::bot::BotPlayer;
+use crate::game::PLAYER;
+use crate::i18n::{Msg, tr};
+use crate::player::{GameView, Move, Outcome, Player, RemotePlayer, play_out};
+use crate::protocol::ExternalEngine;
+use crate::tune::random_opening;
+
+pub struct MatchOptions {
+    // Commands starting the engines; the bot in this process plays a side
+    // left without one.
+    pub first: Option<String>,
+    pub second: Option<String>,
+    // Elo difference of the null and the alternative hypothesis.
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub max_games: u32,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+#[derive(Default)]
+struct Tally {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl Tally {
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Log-likelihood ratio of elo1 against elo0 under the usual normal
+    // approximation of the per-game score. Half a game of each result is added
+    // so that the variance stays positive when every game ends the same way.
+    fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let (w, d, l) = (
+            self.wins as f64 + 0.5,
+            self.draws as f64 + 0.5,
+            self.losses as f64 + 0.5,
+        );
+        let n = w + d + l;
+        let (w, d, l) = (w / n, d / n, l / n);
+        let score = w + d / 2.0;
+        let variance = w * (1.0 - score).powi(2) + d * (0.5 - score).powi(2) + l * score.powi(2);
+        let expected = |elo: f64| 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+        let (s0, s1) = (expected(elo0), expected(elo1));
+        n * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    fn elo(&self) -> f64 {
+        let score = (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64;
+        let score = score.clamp(1e-3, 1.0 - 1e-3);
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+// One side of a match.
+enum Contender {
+    Local(BotPlayer),
+    Remote(RemotePlayer),
+}
+
+impl Contender {
+    fn new(command: Option<&str>, make_bot: &impl Fn() -> BotPlayer) -> Result<Self, String> {
+        match command {
+            Some(command) => Ok(Contender::Remote(RemotePlayer::new(ExternalEngine::spawn(
+                command,
+            )?))),
+            None => Ok(Contender::Local(make_bot())),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Contender::Local(_) => "built-in bot",
+            Contender::Remote(engine) => engine.name(),
+        }
+    }
+
+    // The bot only carries its transposition table from one game to the next,
+    // which never changes a score.
+    fn new_game(&mut self) -> Result<(), String> {
+        match self {
+            Contender::Local(_) => Ok(()),
+            Contender::Remote(engine) => engine.new_game(),
+        }
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        match self {
+            Contender::Local(_) => None,
+            Contender::Remote(engine) => engine.take_error(),
+        }
+    }
+}
+
+impl Player for Contender {
+    fn choose_move(&mut self, view: &GameView) -> Move {
+        match self {
+            Contender::Local(bot) => bot.choose_move(view),
+            Contender::Remote(engine) => engine.choose_move(view),
+        }
+    }
+}
+
+// Plays one game between two engines from `opening`; the result is from the
+// point of view of `first`, who moves first. An illegal move loses the game;
+// an engine that stops answering ends the match.
+fn play(
+    first: &mut Contender,
+    second: &mut Contender,
+    opening: &str,
+    rows: usize,
+    cols: usize,
+) -> Result<f64, String> {
+    first.new_game()?;
+    second.new_game()?;
+    match play_out([&mut *first, &mut *second], rows, cols, opening)? {
+        Outcome::Won(PLAYER) => Ok(1.0),
+        Outcome::Won(_) => Ok(0.0),
+        Outcome::Draw => Ok(0.5),
+        Outcome::Quit => Err(first
+            .take_error()
+            .or_else(|| second.take_error())
+            .unwrap_or_else(|| "engine quit".to_string())),
+    }
+}
+
+// Games are played in pairs from the same random opening with colors reversed,
+// until the log-likelihood ratio crosses one of the SPRT bounds or another
+// pair would take the match past `max_games`.
+pub fn run(make_bot: impl Fn() -> BotPlayer, options: &MatchOptions) -> Result<(), Box<dyn Error>> {
+    let mut first = Contender::new(options.first.as_deref(), &make_bot)?;
+    let mut second = Contender::new(options.second.as_deref(), &make_bot)?;
+    let lower = (options.beta / (1.0 - options.alpha)).ln();
+    let upper = ((1.0 - options.beta) / options.alpha).ln();
+    let mut rng = rand::rng();
+    let mut tally = Tally::default();
+    let mut llr = 0.0;
+
+    while tally.games() + 2 <= options.max_games {
+        let opening = random_opening(options.rows, options.cols, 2, &mut rng);
+        let results = [
+            play(
+                &mut first,
+                &mut second,
+                &opening,
+                options.rows,
+                options.cols,
+            )?,
+            1.0 - play(
+                &mut second,
+                &mut first,
+                &opening,
+                options.rows,
+                options.cols,
+            )?,
+        ];
+        for result in results {
+            match result {
+                1.0 => tally.wins += 1,
+                0.0 => tally.losses += 1,
+                _ => tally.draws += 1,
+            }
+        }
+        llr = tally.llr(options.elo0, options.elo1);
+        info!(
+            games = tally.games(),
+            wins = tally.wins,
+            draws = tally.draws,
+            losses = tally.losses,
+            llr,
+            "match progress"
+        );
+        eprintln!(
+            "{}",
+            tr(
+                Msg::SprtProgress,
+                &[
+                    &tally.games(),
+                    &tally.wins,
+                    &tally.draws,
+                    &tally.losses,
+                    &format!("{:.2}", llr),
+                    &format!("{:.2}", lower),
+                    &format!("{:.2}", upper)
+                ]
+            )
+        );
+        if llr <= lower || llr >= upper {
+            break;
+        }
+    }
+
+    println!("{}", tr(Msg::SprtEngines, &[&first.name(), &second.name()]));
+    println!(
+        "{}",
+        tr(
+            Msg::SprtResult,
+            &[
+                &tally.games(),
+                &tally.wins,
+                &tally.draws,
+                &tally.losses,
+                &format!("{:+.1}", tally.elo())
+            ]
+        )
+    );
+    let llr_text = format!("{:.2}", llr);
+    let verdict = if llr >= upper {
+        tr(Msg::SprtH1, &[&options.elo1, &llr_text])
+    } else if llr <= lower {
+        tr(Msg::SprtH0, &[&options.elo1, &llr_text])
+    } else {
+        tr(Msg::SprtInconclusive, &[&tally.games(), &llr_text])
+    };
+    println!("{}", verdict);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(wins: u32, draws: u32, losses: u32) -> Tally {
+        Tally {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    #[test]
+    fn likelihood_ratio_follows_the_results() {
+        assert!(tally(30, 10, 10).llr(0.0, 20.0) > 0.0);
+        assert!(tally(10, 10, 30).llr(0.0, 20.0) < 0.0);
+        // An even score favors the hypothesis of no difference.
+        assert!(tally(20, 10, 20).llr(0.0, 20.0) < 0.0);
+        assert!(tally(40, 0, 0).llr(0.0, 20.0).is_finite());
+        assert!(tally(60, 0, 0).llr(0.0, 20.0) > tally(30, 0, 0).llr(0.0, 20.0));
+    }
+
+    #[test]
+    fn elo_difference_from_the_score() {
+        assert_eq!(tally(5, 2, 5).elo(), 0.0);
+        assert!((tally(3, 0, 1).elo() - 190.85).abs() < 0.01);
+        assert!(tally(4, 0, 0).elo().is_finite());
+        assert_eq!(tally(1, 1, 1).games(), 3);
+    }
+}
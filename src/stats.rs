@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::error::Error;
+
+use crate::game::{BOT, ConnectFour, PLAYER, parse_column};
+use crate::history::{Database, Filter, GameRecord, Winner};
+use crate::i18n::{Msg, tr};
+use crate::solver::{Position, Solver};
+
+// How far ahead a forced loss must be to count a move as the losing blunder.
+const BLUNDER_HORIZON: u32 = 4;
+const START_RATING: f64 = 1200.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Default)]
+struct Record {
+    games: u32,
+    won: u32,
+    drawn: u32,
+    lost: u32,
+    plies: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Blunder {
+    MissedWin,
+    IgnoredThreat,
+    PlayedUnderThreat,
+    AllowedForcedWin,
+}
+
+impl Blunder {
+    fn describe(self) -> String {
+        let msg = match self {
+            Blunder::MissedWin => Msg::BlunderMissedWin,
+            Blunder::IgnoredThreat => Msg::BlunderIgnoredThreat,
+            Blunder::PlayedUnderThreat => Msg::BlunderUnderThreat,
+            Blunder::AllowedForcedWin => Msg::BlunderForcedWin,
+        };
+        tr(msg, &[])
+    }
+}
+
+// The first human move after which the bot had a short forced win that
+// another move would have avoided. The human always moves first in recorded games.
+fn losing_blunder(solver: &mut Solver, record: &GameRecord) -> Option<(usize, Blunder)> {
+    let mut pos = Position::new(record.cols, record.rows)?;
+    for (ply, c) in record.moves.bytes().enumerate() {
+        // Swaps and passes are not column moves; the position is rebuilt from
+        // the board, the side to move still following from the ply count.
+        let Some(col) = parse_column(c as char) else {
+            let game =
+                ConnectFour::from_moves(record.rows, record.cols, &record.moves[..=ply], PLAYER)
+                    .ok()?;
+            let to_move = if ply % 2 == 1 { PLAYER } else { BOT };
+            pos = Position::from_game(&game, to_move)?;
+            continue;
+        };
+        if ply % 2 == 0 {
+            let mut after = pos;
+            after.play(col);
+            if solver.wins_within(&after, BLUNDER_HORIZON) {
+                let escapable = (0..pos.width())
+                    .filter(|&alt| alt != col && pos.can_play(alt))
+                    .any(|alt| {
+                        let mut child = pos;
+                        child.play(alt);
+                        !solver.wins_within(&child, BLUNDER_HORIZON)
+                    });
+                if escapable {
+                    let kind = if pos.can_win_next() {
+                        Blunder::MissedWin
+                    } else if pos.opponent_threatens() {
+                        Blunder::IgnoredThreat
+                    } else if after.can_win_next() {
+                        Blunder::PlayedUnderThreat
+                    } else {
+                        Blunder::AllowedForcedWin
+                    };
+                    return Some((ply / 2 + 1, kind));
+                }
+            }
+        }
+        pos.play(col);
+    }
+    None
+}
+
+fn expected(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+pub fn run(db: &Database, hash_mb: usize) -> Result<(), Box<dyn Error>> {
+    let mut games = db.list(&Filter::default())?;
+    if games.is_empty() {
+        println!("{}", tr(Msg::NoGames, &[]));
+        return Ok(());
+    }
+    games.reverse();
+
+    let mut records: HashMap<(String, i32, Option<String>), Record> = HashMap::new();
+    let mut blunders: HashMap<Blunder, u32> = HashMap::new();
+    let mut solvers: HashMap<(usize, usize), Solver> = HashMap::new();
+    // Each difficulty has its own rating, so the pool adjusts to how strong the
+    // levels really are against this player.
+    let mut bot_ratings: HashMap<(String, i32), f64> = HashMap::new();
+    let mut rating = START_RATING;
+    let mut months: Vec<(String, f64, u32)> = Vec::new();
+
+    for game in &games {
+        let level = (game.engine.clone(), game.depth);
+        let record = records
+            .entry((game.engine.clone(), game.depth, game.handicap.clone()))
+            .or_default();
+        record.games += 1;
+        record.plies += game.moves.len();
+        let score = match game.winner {
+            Winner::Human => {
+                record.won += 1;
+                1.0
+            }
+            Winner::Draw => {
+                record.drawn += 1;
+                0.5
+            }
+            Winner::Bot => {
+                record.lost += 1;
+                0.0
+            }
+        };
+
+        if game.winner == Winner::Bot
+            && ConnectFour::from_moves(game.rows, game.cols, &game.moves, PLAYER).is_ok()
+        {
+            let solver = match solvers.entry((game.rows, game.cols)) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(Solver::new(hash_mb)),
+            };
+            if let Some((_, kind)) = losing_blunder(solver, game) {
+                *blunders.entry(kind).or_default() += 1;
+            }
+        }
+
+        // Handicap games say little about strength on even terms.
+        if game.handicap.is_some() {
+            continue;
+        }
+        let bot_rating = bot_ratings.entry(level).or_insert(START_RATING);
+        let change = K_FACTOR * (score - expected(rating, *bot_rating));
+        rating += change;
+        *bot_rating -= change;
+
+        let month = db.format_time(game.finished_at)?[..7].to_string();
+        match months.last_mut() {
+            Some((last, value, count)) if *last == month => {
+                *value = rating;
+                *count += 1;
+            }
+            _ => months.push((month, rating, 1)),
+        }
+    }
+
+    println!("{}", tr(Msg::RecordByDifficulty, &[]));
+    println!(
+        "{:<8}  {:>5}  {:>10}  {:>5}  {:>4}  {:>5}  {:>4}  {:>9}",
+        "engine", "depth", "handicap", "games", "won", "drawn", "lost", "avg plies"
+    );
+    let mut levels: Vec<_> = records.iter().collect();
+    levels.sort_by(|a, b| a.0.cmp(b.0));
+    for ((engine, depth, handicap), record) in levels {
+        println!(
+            "{:<8}  {:>5}  {:>10}  {:>5}  {:>4}  {:>5}  {:>4}  {:>9.1}",
+            engine,
+            depth,
+            handicap.as_deref().unwrap_or("-"),
+            record.games,
+            record.won,
+            record.drawn,
+            record.lost,
+            record.plies as f64 / record.games as f64
+        );
+    }
+
+    println!();
+    println!("{}", tr(Msg::CommonBlunders, &[]));
+    if blunders.is_empty() {
+        println!("  {}", tr(Msg::NoneFound, &[]));
+    }
+    let mut blunders: Vec<_> = blunders.into_iter().collect();
+    blunders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    for (kind, count) in blunders {
+        println!("  {:>4}  {}", count, kind.describe());
+    }
+
+    println!();
+    println!("{}", tr(Msg::RatingTrend, &[&START_RATING]));
+    if months.is_empty() {
+        println!("  {}", tr(Msg::NoEvenGames, &[]));
+    }
+    for (month, value, count) in &months {
+        println!(
+            "  {}  {:>6.0}  ({})",
+            month,
+            value,
+            tr(Msg::GameCount, &[count])
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+use crate::i18n::{Msg, tr};
+use crate::score_table::ScoreTable;
+use crate::solver::Position;
+
+const MAGIC: &[u8; 4] = b"C4TB";
+// Rough upper bound on the positions a generation may enumerate before it is
+// refused; on the standard board only the last couple of moves fit.
+const MAX_POSITIONS: f64 = 5e8;
+
+// Exact scores, in the solver's convention, of every position with at least
+// `min_pieces` stones that can occur in a game.
+pub struct Tablebase {
+    table: ScoreTable,
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+// Number of ways to fill columns of at most `height` cells with `pieces` stones.
+fn fillings(width: usize, height: usize, pieces: usize) -> f64 {
+    let mut ways = vec![0.0; pieces + 1];
+    ways[0] = 1.0;
+    for _ in 0..width {
+        let mut next = vec![0.0; pieces + 1];
+        for (used, &count) in ways.iter().enumerate() {
+            for h in 0..=height.min(pieces - used) {
+                next[used + h] += count;
+            }
+        }
+        ways = next;
+    }
+    ways[pieces]
+}
+
+// Every placement of `pieces` stones with no four in a row for either side.
+// The side to move has made `pieces / 2` moves.
+fn positions(width: usize, height: usize, pieces: usize) -> Vec<Position> {
+    let empty = Position::new(width, height).expect("board fits the solver");
+    let mut found = Vec::new();
+    let mut heights = vec![0; width];
+    let mut cells = Vec::new();
+
+    fn fill_columns(
+        empty: &Position,
+        heights: &mut Vec<usize>,
+        col: usize,
+        left: usize,
+        cells: &mut Vec<u64>,
+        found: &mut Vec<Position>,
+    ) {
+        if col == heights.len() {
+            if left == 0 {
+                cells.clear();
+                for (col, &h) in heights.iter().enumerate() {
+                    cells.extend((0..h).map(|row| empty.cell_bit(col, row)));
+                }
+                let to_move = cells.len() / 2;
+                color(empty, cells, 0, 0, 0, to_move, found);
+            }
+            return;
+        }
+        for h in 0..=empty.height().min(left) {
+            heights[col] = h;
+            fill_columns(empty, heights, col + 1, left - h, cells, found);
+        }
+        heights[col] = 0;
+    }
+
+    // Assigns cells from `i` on, `current_left` of them to the side to move.
+    fn color(
+        empty: &Position,
+        cells: &[u64],
+        i: usize,
+        current: u64,
+        mask: u64,
+        current_left: usize,
+        found: &mut Vec<Position>,
+    ) {
+        let partial = Position::from_bits(empty.width(), empty.height(), current, mask)
+            .expect("board fits the solver");
+        if partial.has_four() {
+            return;
+        }
+        let Some(&bit) = cells.get(i) else {
+            found.push(partial);
+            return;
+        };
+        if current_left > 0 {
+            color(
+                empty,
+                cells,
+                i + 1,
+                current | bit,
+                mask | bit,
+                current_left - 1,
+                found,
+            );
+        }
+        if cells.len() - i > current_left {
+            color(
+                empty,
+                cells,
+                i + 1,
+                current,
+                mask | bit,
+                current_left,
+                found,
+            );
+        }
+    }
+
+    fill_columns(&empty, &mut heights, 0, pieces, &mut cells, &mut found);
+    found
+}
+
+impl Tablebase {
+    // Retrograde analysis: the fullest boards are scored first, and each
+    // position then takes the best of its already scored children.
+    pub fn generate(width: usize, height: usize, min_pieces: usize) -> Result<Self, String> {
+        let empty = Position::new(width, height).ok_or_else(|| tr(Msg::BoardTooLarge, &[]))?;
+        let cells = empty.cells();
+        if min_pieces > cells {
+            return Err(format!(
+                "a {}x{} board holds only {} pieces",
+                width, height, cells
+            ));
+        }
+        let estimate: f64 = (min_pieces..=cells)
+            .map(|k| fillings(width, height, k) * binomial(k, k / 2))
+            .sum();
+        if estimate > MAX_POSITIONS {
+            return Err(format!(
+                "about {:.1e} positions to enumerate; use more pieces or a smaller board",
+                estimate
+            ));
+        }
+
+        let mut entries: Vec<(u64, i8)> = Vec::new();
+        let mut next: HashMap<u64, i8> = HashMap::new();
+        for pieces in (min_pieces..=cells).rev() {
+            let mut level = HashMap::new();
+            for pos in positions(width, height, pieces) {
+                let score = if pos.can_win_next() {
+                    ((cells + 1 - pieces) / 2) as i8
+                } else if pieces == cells {
+                    0
+                } else {
+                    // A move that does not win leaves a position without a
+                    // four, which the previous level has scored.
+                    (0..width)
+                        .filter(|&col| pos.can_play(col))
+                        .map(|col| {
+                            let mut child = pos;
+                            child.play(col);
+                            -next[&child.key()]
+                        })
+                        .max()
+                        .unwrap_or(0)
+                };
+                level.insert(pos.key(), score);
+            }
+            info!(pieces, positions = level.len(), "tablebase level solved");
+            entries.extend(level.iter().map(|(&key, &score)| (key, score)));
+            next = level;
+        }
+
+        entries.sort_unstable_by_key(|&(key, _)| key);
+        Ok(Self {
+            table: ScoreTable::new(MAGIC, width, height, min_pieces, &entries),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn min_pieces(&self) -> usize {
+        self.table.bound()
+    }
+
+    pub fn probe(&self, pos: &Position) -> Option<i32> {
+        if !self.table.fits(pos) || pos.moves() < self.table.bound() {
+            return None;
+        }
+        self.table.score(pos.key())
+    }
+
+    // The best move for the side to move and its score.
+    pub fn best_move(&self, pos: &Position) -> Option<(usize, i32)> {
+        self.probe(pos)?;
+        self.table.best_move(pos, |child| self.probe(child))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        self.table.save(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Ok(Self {
+            table: ScoreTable::open(path, MAGIC, "a tablebase")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+    use std::{env, fs, process};
+
+    const MIN_PIECES: usize = 10;
+
+    // The positions with at least `MIN_PIECES` stones reached by playing
+    // `columns` over and over, skipping full columns and winning moves.
+    fn line(from: &Position, columns: &[usize]) -> Vec<Position> {
+        let mut pos = *from;
+        let mut reached = Vec::new();
+        for &col in columns.iter().cycle().take(16) {
+            if !pos.can_play(col) || pos.is_winning_move(col) {
+                continue;
+            }
+            pos.play(col);
+            if pos.moves() >= MIN_PIECES {
+                reached.push(pos);
+            }
+        }
+        reached
+    }
+
+    #[test]
+    fn scores_match_the_solver() {
+        let tablebase = Tablebase::generate(4, 4, MIN_PIECES).unwrap();
+        let empty = Position::new(4, 4).unwrap();
+        let mut solver = Solver::new(1);
+        let mut checked = 0;
+        for columns in [[0, 1, 2, 3], [3, 3, 0, 1], [1, 2, 2, 0], [2, 0, 3, 1]] {
+            for pos in line(&empty, &columns) {
+                assert_eq!(tablebase.probe(&pos), Some(solver.solve(&pos)));
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+        assert_eq!(tablebase.probe(&empty), None);
+    }
+
+    #[test]
+    fn survives_a_round_trip() {
+        let tablebase = Tablebase::generate(4, 4, 14).unwrap();
+        let path = env::temp_dir().join(format!("connect-four-tablebase-{}.c4tb", process::id()));
+        tablebase.save(&path).unwrap();
+        let loaded = Tablebase::load(&path);
+        fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.len(), tablebase.len());
+        assert_eq!(loaded.min_pieces(), 14);
+    }
+
+    #[test]
+    fn refuses_more_pieces_than_cells() {
+        assert!(Tablebase::generate(4, 4, 17).is_err());
+        assert!(Tablebase::generate(7, 6, 0).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use crossterm::cursor::{MoveTo, Show};
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::execute;
+use crossterm::style::ResetColor;
+use crossterm::terminal::{Clear, ClearType, disable_raw_mode, enable_raw_mode};
+use std::io::{self, IsTerminal, stdout};
+use std::panic;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+// Raw mode with mouse reporting, for reading single keys and clicks; the
+// terminal is restored when it is dropped.
+pub struct RawMode;
+
+impl RawMode {
+    pub fn enable() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(stdout(), EnableMouseCapture)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), DisableMouseCapture);
+        let _ = disable_raw_mode();
+    }
+}
+
+// Clears the screen and moves to its top-left corner. Output that is not a
+// terminal is left alone.
+pub fn clear_screen() {
+    if stdout().is_terminal() {
+        let _ = execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0));
+    }
+}
+
+// Puts the terminal back the way the shell expects it, whatever state the
+// program left it in.
+fn restore() {
+    if stdout().is_terminal() {
+        let _ = execute!(stdout(), DisableMouseCapture, ResetColor, Show);
+    }
+    let _ = disable_raw_mode();
+}
+
+// The stop flag of the search the user is waiting for, if any.
+static FOREGROUND_SEARCH: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+// While alive, Ctrl-C stops the search behind the flag it was made with, which
+// then plays the best move found so far, instead of ending the program.
+pub struct StopOnInterrupt;
+
+impl StopOnInterrupt {
+    pub fn new(stop: Arc<AtomicBool>) -> Self {
+        *FOREGROUND_SEARCH.lock().expect("search flag lock") = Some(stop);
+        Self
+    }
+}
+
+impl Drop for StopOnInterrupt {
+    fn drop(&mut self) {
+        *FOREGROUND_SEARCH.lock().expect("search flag lock") = None;
+    }
+}
+
+// Restores the terminal before a panic message is printed and when the
+// program is interrupted. Ctrl-C in raw mode arrives as a key press instead,
+// which the input functions treat as quitting.
+pub fn install_shutdown_hook() {
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        report(info);
+    }));
+    if let Err(err) = ctrlc::set_handler(|| {
+        if let Ok(search) = FOREGROUND_SEARCH.lock()
+            && let Some(stop) = search.as_ref()
+        {
+            stop.store(true, Ordering::Relaxed);
+            return;
+        }
+        restore();
+        process::exit(130);
+    }) {
+        warn!(%err, "could not install the Ctrl-C handler");
+    }
+}
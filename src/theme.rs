@@ -0,0 +1,134 @@
+use clap::ValueEnum;
+use crossterm::style::{Color, Stylize};
+use serde::Deserialize;
+use std::fmt::Write;
+use std::io::{IsTerminal, stdout};
+
+use crate::game::{BOT, ConnectFour, PLAYER, column_char, symbol};
+use crate::i18n::{Msg, tr};
+
+// The letter the human's pieces are drawn with; the bot gets the other one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Symbol {
+    O,
+    X,
+}
+
+// How the board's coordinates are shown: columns numbered as in transcripts,
+// or lettered from a with the rows numbered from the bottom, as in `d1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Notation {
+    Numbers,
+    Letters,
+}
+
+impl Notation {
+    // The label under a column; past the ninth numbers give way to letters.
+    pub fn label(self, col: usize) -> char {
+        match self {
+            Notation::Numbers => column_char(col),
+            Notation::Letters => (b'a' + col as u8) as char,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub player: Color,
+    pub bot: Color,
+    pub symbol: Symbol,
+    pub notation: Notation,
+    enabled: bool,
+}
+
+impl Theme {
+    pub fn parse(player: &str, bot: &str) -> Result<Self, String> {
+        let color = |name: &str| Color::try_from(name).map_err(|_| tr(Msg::UnknownColor, &[&name]));
+        Ok(Self {
+            player: color(player)?,
+            bot: color(bot)?,
+            symbol: Symbol::O,
+            notation: Notation::Numbers,
+            enabled: stdout().is_terminal(),
+        })
+    }
+
+    pub fn with_symbol(mut self, symbol: Symbol) -> Self {
+        self.symbol = symbol;
+        self
+    }
+
+    pub fn with_notation(mut self, notation: Notation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    pub fn column_label(&self, col: usize) -> char {
+        self.notation.label(col)
+    }
+
+    // How a cell is drawn, with the letters exchanged if the human plays x.
+    pub fn glyph(&self, cell: i8) -> &'static str {
+        match self.symbol {
+            Symbol::O => symbol(cell),
+            Symbol::X => symbol(-cell),
+        }
+    }
+
+    // A cell as the user names it: `d1` with letters, rows counted from the bottom.
+    pub fn cell_name(&self, game: &ConnectFour, row: usize, col: usize) -> String {
+        let label = self.column_label(col);
+        match self.notation {
+            Notation::Numbers => tr(Msg::CellName, &[&label, &(game.rows - row)]),
+            Notation::Letters => format!("{}{}", label, game.rows - row),
+        }
+    }
+
+    // Plain text when stdout is not a terminal.
+    pub fn render(&self, game: &ConnectFour) -> String {
+        self.render_with_line(game, None)
+    }
+
+    // Like `render`, with the cells of `line` in reverse video, or in
+    // capitals in plain text.
+    pub fn render_with_line(
+        &self,
+        game: &ConnectFour,
+        line: Option<&[(usize, usize); 4]>,
+    ) -> String {
+        let mut out = String::new();
+        for (i, row) in game.board.iter().enumerate() {
+            for (j, &cell) in row.iter().enumerate() {
+                let glyph = self.glyph(cell);
+                let highlighted = line.is_some_and(|line| line.contains(&(i, j)));
+                if !self.enabled {
+                    let _ = match highlighted {
+                        true => write!(out, " {}", glyph.to_uppercase()),
+                        false => write!(out, " {}", glyph),
+                    };
+                    continue;
+                }
+                let styled = match cell {
+                    BOT => glyph.with(self.bot),
+                    PLAYER => glyph.with(self.player),
+                    _ => glyph.stylize(),
+                };
+                let _ = match highlighted {
+                    true => write!(out, " {}", styled.reverse()),
+                    false => write!(out, " {}", styled),
+                };
+            }
+            if self.notation == Notation::Letters {
+                let _ = write!(out, "  {}", game.rows - i);
+            }
+            out.push('\n');
+        }
+        for col in 0..game.cols {
+            let _ = write!(out, " {}", self.column_label(col));
+        }
+        out.push('\n');
+        out
+    }
+}
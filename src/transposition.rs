@@ -0,0 +1,106 @@
+use std::mem;
+use tracing::warn;
+
+// A fixed-size hash table from position keys to `V`, where a new entry simply
+// replaces whatever shared its slot. Key 0 marks an empty slot, so it is never
+// stored.
+pub struct TranspositionTable<V> {
+    keys: Vec<u64>,
+    values: Vec<V>,
+    used: usize,
+}
+
+impl<V: Copy + Default> TranspositionTable<V> {
+    const ENTRY_BYTES: usize = mem::size_of::<u64>() + mem::size_of::<V>();
+
+    // Sized to fit `mb` megabytes. If that much memory cannot be had the table
+    // is halved until the allocation succeeds, so a large budget never aborts.
+    pub fn new(mb: usize) -> Self {
+        // An odd size spreads the keys better under the modulo.
+        let mut size = (mb.max(1) * 1024 * 1024 / Self::ENTRY_BYTES) | 1;
+        loop {
+            let mut keys = Vec::new();
+            let mut values = Vec::new();
+            if keys.try_reserve_exact(size).is_ok() && values.try_reserve_exact(size).is_ok() {
+                keys.resize(size, 0);
+                values.resize(size, V::default());
+                return Self {
+                    keys,
+                    values,
+                    used: 0,
+                };
+            }
+            let smaller = (size / 2) | 1;
+            warn!(
+                entries = size,
+                smaller, "not enough memory for the transposition table"
+            );
+            if smaller == size {
+                panic!("cannot allocate a transposition table");
+            }
+            size = smaller;
+        }
+    }
+
+    pub fn entries(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn megabytes(&self) -> f64 {
+        (self.keys.len() * Self::ENTRY_BYTES) as f64 / (1024.0 * 1024.0)
+    }
+
+    // Fraction of slots holding an entry.
+    pub fn occupancy(&self) -> f64 {
+        self.used as f64 / self.keys.len() as f64
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.keys.len() as u64) as usize
+    }
+
+    pub fn put(&mut self, key: u64, value: V) {
+        if key == 0 {
+            return;
+        }
+        let i = self.index(key);
+        if self.keys[i] == 0 {
+            self.used += 1;
+        }
+        self.keys[i] = key;
+        self.values[i] = value;
+    }
+
+    pub fn get(&self, key: u64) -> Option<V> {
+        let i = self.index(key);
+        (key != 0 && self.keys[i] == key).then(|| self.values[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_within_its_budget() {
+        let table = TranspositionTable::<u8>::new(1);
+        assert!((table.megabytes() - 1.0).abs() < 0.001);
+        assert_eq!(table.entries() % 2, 1);
+        assert_eq!(TranspositionTable::<u8>::new(0).entries(), table.entries());
+    }
+
+    #[test]
+    fn newer_entries_replace_older_ones() {
+        let mut table = TranspositionTable::<u8>::new(1);
+        let other = 5 + table.entries() as u64;
+        table.put(5, 1);
+        assert_eq!(table.get(5), Some(1));
+        assert_eq!(table.get(other), None);
+        table.put(other, 2);
+        assert_eq!(table.get(5), None);
+        assert_eq!(table.get(other), Some(2));
+        assert_eq!(table.occupancy(), 1.0 / table.entries() as f64);
+        table.put(0, 3);
+        assert_eq!(table.get(0), None);
+    }
+}
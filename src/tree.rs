@@ -0,0 +1,300 @@
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Nodes kept at most, however many the depth would allow.
+pub const MAX_TREE_NODES: usize = 20_000;
+
+pub const DEFAULT_TREE_DEPTH: usize = 3;
+
+enum Format {
+    Dot,
+    Json,
+}
+
+// A position reached in the search, after `col` was played from its parent.
+// Scores and windows are from the searching side's point of view, the side to
+// move at the root being `max`.
+struct Node {
+    parent: Option<usize>,
+    col: Option<usize>,
+    // Whether the searching side is to move here.
+    max: bool,
+    // The alpha-beta window the node was searched with.
+    alpha: i32,
+    beta: i32,
+    score: Option<i32>,
+    // The move into the node won on the spot.
+    win: bool,
+    // The window closed before every move here was searched.
+    cutoff: bool,
+    // Moves left unsearched by the cutoff.
+    pruned: usize,
+}
+
+// The top of the bot's last finished search, kept for `--dump-tree` and
+// written as Graphviz DOT or JSON, as the file's extension asks for.
+pub struct SearchTree {
+    file: PathBuf,
+    format: Format,
+    max_depth: usize,
+    nodes: Vec<Node>,
+    // The nodes from the root to the one being searched, None for those
+    // too deep or too many to keep.
+    path: Vec<Option<usize>>,
+    // The tree of the deepest search finished, as opposed to one abandoned.
+    finished: Vec<Node>,
+}
+
+impl SearchTree {
+    pub fn new(file: &Path, max_depth: usize) -> Result<Self, String> {
+        let format = match file.extension().and_then(|ext| ext.to_str()) {
+            Some("dot" | "gv") => Format::Dot,
+            Some("json") => Format::Json,
+            _ => {
+                return Err(format!(
+                    "{}: search trees are written as .dot or .json",
+                    file.display()
+                ));
+            }
+        };
+        Ok(Self {
+            file: file.to_path_buf(),
+            format,
+            max_depth,
+            nodes: Vec::new(),
+            path: Vec::new(),
+            finished: Vec::new(),
+        })
+    }
+
+    // Forgets the last search, before a new one.
+    pub fn clear(&mut self) {
+        self.finished.clear();
+    }
+
+    // Starts recording one iteration of the search from the root.
+    pub fn begin(&mut self) {
+        self.nodes.clear();
+        self.nodes.push(Node {
+            parent: None,
+            col: None,
+            max: true,
+            alpha: i32::MIN,
+            beta: i32::MAX,
+            score: None,
+            win: false,
+            cutoff: false,
+            pruned: 0,
+        });
+        self.path = vec![Some(0)];
+    }
+
+    pub fn open(&mut self, col: usize, max: bool, alpha: i32, beta: i32) {
+        let parent = self.path.last().copied().flatten();
+        let kept = parent.is_some()
+            && self.path.len() <= self.max_depth
+            && self.nodes.len() < MAX_TREE_NODES;
+        if !kept {
+            self.path.push(None);
+            return;
+        }
+        self.nodes.push(Node {
+            parent,
+            col: Some(col),
+            max,
+            alpha,
+            beta,
+            score: None,
+            win: false,
+            cutoff: false,
+            pruned: 0,
+        });
+        self.path.push(Some(self.nodes.len() - 1));
+    }
+
+    pub fn close(&mut self, score: i32, win: bool) {
+        if let Some(Some(id)) = self.path.pop() {
+            self.nodes[id].score = Some(score);
+            self.nodes[id].win = win;
+        }
+    }
+
+    // Marks the node being searched as cut off with `pruned` moves unsearched.
+    pub fn cutoff(&mut self, pruned: usize) {
+        if let Some(&Some(id)) = self.path.last() {
+            self.nodes[id].cutoff = true;
+            self.nodes[id].pruned = pruned;
+        }
+    }
+
+    // Keeps the iteration just searched, which scored `score` at the root.
+    pub fn finish(&mut self, score: i32) {
+        if let Some(root) = self.nodes.first_mut() {
+            root.score = Some(score);
+        }
+        self.finished = std::mem::take(&mut self.nodes);
+    }
+
+    pub fn write(&self) -> Result<(), String> {
+        let text = match self.format {
+            Format::Dot => self.dot(),
+            Format::Json => {
+                let mut children = vec![Vec::new(); self.finished.len()];
+                for (id, node) in self.finished.iter().enumerate() {
+                    if let Some(parent) = node.parent {
+                        children[parent].push(id);
+                    }
+                }
+                let root = (!self.finished.is_empty()).then(|| self.json(0, &children));
+                serde_json::to_string_pretty(&root).map_err(|err| err.to_string())?
+            }
+        };
+        fs::write(&self.file, text).map_err(|err| format!("{}: {}", self.file.display(), err))
+    }
+
+    // Max nodes are boxes and min nodes ellipses; cutoffs are red.
+    fn dot(&self) -> String {
+        let mut out = String::from("digraph search {\n    node [fontname=\"monospace\"];\n");
+        for (id, node) in self.finished.iter().enumerate() {
+            let mut label = match node.col {
+                Some(col) => format!("{}", col + 1),
+                None => "root".to_string(),
+            };
+            if let Some(score) = node.score {
+                let _ = write!(label, "\\nscore {:+}", score);
+            }
+            let _ = write!(label, "\\n[{}, {}]", bound(node.alpha), bound(node.beta));
+            if node.win {
+                label.push_str("\\nwin");
+            }
+            if node.cutoff {
+                let _ = write!(label, "\\ncutoff, {} pruned", node.pruned);
+            }
+            let shape = if node.max { "box" } else { "ellipse" };
+            let color = if node.cutoff { ", color=red" } else { "" };
+            let _ = writeln!(
+                out,
+                "    n{} [label=\"{}\", shape={}{}];",
+                id, label, shape, color
+            );
+            if let Some(parent) = node.parent {
+                let _ = writeln!(out, "    n{} -> n{};", parent, id);
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn json(&self, id: usize, children: &[Vec<usize>]) -> JsonNode {
+        let node = &self.finished[id];
+        JsonNode {
+            col: node.col.map(|col| col + 1),
+            to_move: if node.max { "max" } else { "min" },
+            score: node.score,
+            alpha: (node.alpha != i32::MIN).then_some(node.alpha),
+            beta: (node.beta != i32::MAX).then_some(node.beta),
+            win: node.win,
+            cutoff: node.cutoff,
+            pruned: node.pruned,
+            children: children[id]
+                .iter()
+                .map(|&child| self.json(child, children))
+                .collect(),
+        }
+    }
+}
+
+fn bound(value: i32) -> String {
+    match value {
+        i32::MIN => "-inf".to_string(),
+        i32::MAX => "inf".to_string(),
+        value => value.to_string(),
+    }
+}
+
+// Columns are 1-based as in json.rs; an open window side is null.
+#[derive(Serialize)]
+struct JsonNode {
+    col: Option<usize>,
+    to_move: &'static str,
+    score: Option<i32>,
+    alpha: Option<i32>,
+    beta: Option<i32>,
+    win: bool,
+    cutoff: bool,
+    pruned: usize,
+    children: Vec<JsonNode>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn temp(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("connect-four-tree-{}-{}", process::id(), name))
+    }
+
+    // A root with two moves, the first searched two plies deep and the second
+    // cut off at once.
+    fn search(tree: &mut SearchTree) {
+        tree.begin();
+        tree.open(3, false, i32::MIN, i32::MAX);
+        tree.open(2, true, i32::MIN, i32::MAX);
+        tree.close(5, false);
+        tree.close(5, false);
+        tree.open(4, false, 5, i32::MAX);
+        tree.open(4, true, 5, i32::MAX);
+        tree.close(100, true);
+        tree.cutoff(5);
+        tree.close(100, false);
+    }
+
+    #[test]
+    fn only_dot_and_json_files_are_written() {
+        assert!(SearchTree::new(&temp("a.dot"), 2).is_ok());
+        assert!(SearchTree::new(&temp("a.gv"), 2).is_ok());
+        assert!(SearchTree::new(&temp("a.txt"), 2).is_err());
+    }
+
+    #[test]
+    fn json_follows_the_search() {
+        let path = temp("b.json");
+        let mut tree = SearchTree::new(&path, 2).unwrap();
+        search(&mut tree);
+        tree.finish(5);
+        tree.write().unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let root: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(root["score"], 5);
+        assert_eq!(root["alpha"], serde_json::Value::Null);
+        let children = root["children"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0]["col"], 4);
+        assert_eq!(children[0]["children"][0]["to_move"], "max");
+        assert_eq!(children[1]["alpha"], 5);
+        assert_eq!(children[1]["cutoff"], true);
+        assert_eq!(children[1]["pruned"], 5);
+        assert_eq!(children[1]["children"][0]["win"], true);
+    }
+
+    #[test]
+    fn nodes_past_the_depth_are_dropped() {
+        let path = temp("c.dot");
+        let mut tree = SearchTree::new(&path, 1).unwrap();
+        search(&mut tree);
+        tree.finish(5);
+        // An abandoned iteration leaves the finished one in place.
+        tree.begin();
+        tree.open(1, false, i32::MIN, i32::MAX);
+        tree.write().unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(text.matches(" -> ").count(), 2);
+        assert!(text.contains("cutoff, 5 pruned"));
+        assert!(text.contains("[5, inf]"));
+    }
+}
@@ -0,0 +1,244 @@
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{debug, info};
+
+use crate::bot::{BotPlayer, Weights};
+use crate::game::{BOT, ConnectFour, PLAYER, column_char};
+use crate::i18n::{Msg, tr};
+use crate::player::{Outcome, play_out};
+use crate::solver::{Position, Solver};
+
+// Largest solver score, in either direction, of an opening counted as balanced:
+// neither side can force a win before the last few moves of the game.
+const BALANCE_MARGIN: i32 = 2;
+const BALANCE_ATTEMPTS: u32 = 200;
+// Moves of the openings tuning games start from; fewer take much longer to
+// verify as balanced.
+const OPENING_PLIES: usize = 8;
+
+pub struct TuneOptions {
+    // Weights to start from.
+    pub initial: Weights,
+    pub iterations: u32,
+    // Games per iteration; each opening is played twice with colors reversed.
+    pub games: u32,
+    pub rows: usize,
+    pub cols: usize,
+    // Transposition table budget of the solver that checks the openings.
+    pub hash_mb: usize,
+    pub output: Option<PathBuf>,
+}
+
+// Random moves to start self-play games from, so that deterministic bots do
+// not replay the same game. Openings that already decide the game are avoided.
+pub fn random_opening(rows: usize, cols: usize, plies: usize, rng: &mut impl Rng) -> String {
+    loop {
+        let mut game = ConnectFour::new(rows, cols);
+        let mut moves = String::new();
+        let mut piece = PLAYER;
+        for _ in 0..plies {
+            let Some(&col) = game.get_valid_moves().choose(rng) else {
+                break;
+            };
+            game.drop_piece(col, piece);
+            moves.push(column_char(col));
+            piece = -piece;
+        }
+        if !game.check_win(PLAYER) && !game.check_win(BOT) {
+            return moves;
+        }
+    }
+}
+
+// A random opening of `plies` moves that the solver finds close to a draw.
+pub fn balanced_opening(
+    rows: usize,
+    cols: usize,
+    plies: usize,
+    hash_mb: usize,
+    rng: &mut impl Rng,
+) -> Result<String, String> {
+    let mut solver = Solver::new(hash_mb);
+    for attempt in 1..=BALANCE_ATTEMPTS {
+        let opening = random_opening(rows, cols, plies, rng);
+        let to_move = if opening.len().is_multiple_of(2) {
+            PLAYER
+        } else {
+            BOT
+        };
+        let game = ConnectFour::from_moves(rows, cols, &opening, PLAYER)?;
+        let pos = Position::from_game(&game, to_move).ok_or_else(|| tr(Msg::BoardTooLarge, &[]))?;
+        if solver.is_balanced(&pos, BALANCE_MARGIN) {
+            info!(opening = %opening, attempt, nodes = solver.nodes(), "balanced opening found");
+            return Ok(opening);
+        }
+        debug!(opening = %opening, "opening rejected as unbalanced");
+    }
+    Err(format!(
+        "no balanced opening of {} moves found in {} tries",
+        plies, BALANCE_ATTEMPTS
+    ))
+}
+
+// Plays `first` (moving first, as PLAYER) against `second` from `opening`.
+// Returns 1 if `first` wins, 0 if `second` wins and 0.5 for a draw.
+pub fn play_game(
+    first: &mut BotPlayer,
+    second: &mut BotPlayer,
+    rows: usize,
+    cols: usize,
+    opening: &str,
+) -> Result<f64, String> {
+    match play_out([first, second], rows, cols, opening)? {
+        Outcome::Won(PLAYER) => Ok(1.0),
+        Outcome::Won(_) => Ok(0.0),
+        Outcome::Draw | Outcome::Quit => Ok(0.5),
+    }
+}
+
+fn to_weights(theta: &[f64; 3]) -> Weights {
+    Weights {
+        center: theta[0].round().max(0.0) as i32,
+        two: theta[1].round().max(0.0) as i32,
+        three: theta[2].round().max(0.0) as i32,
+    }
+}
+
+// Printed as a `[weights]` table that can be pasted into the config file.
+#[derive(Serialize)]
+struct TunedConfig {
+    weights: Weights,
+}
+
+// SPSA: each iteration perturbs all weights at once in a random direction and
+// plays the two perturbed bots against each other; the result estimates the
+// gradient and moves the weights towards the winner. Games start from balanced
+// openings, as ones that are already decided only add noise to the estimate.
+pub fn run(make_bot: impl Fn() -> BotPlayer, options: &TuneOptions) -> Result<(), Box<dyn Error>> {
+    let mut rng = rand::rng();
+    let start = options.initial;
+    let mut theta = [start.center as f64, start.two as f64, start.three as f64];
+    let games = options.games.max(2).next_multiple_of(2);
+
+    for k in 0..options.iterations {
+        let a = 4.0 / (k as f64 + 1.0 + 0.1 * options.iterations as f64).powf(0.602);
+        let c = 1.5 / (k as f64 + 1.0).powf(0.101);
+        let delta: [f64; 3] = [(); 3].map(|_| if rng.random() { 1.0 } else { -1.0 });
+        let plus = to_weights(&[0, 1, 2].map(|i| theta[i] + c * delta[i]));
+        let minus = to_weights(&[0, 1, 2].map(|i| theta[i] - c * delta[i]));
+        let mut bot_plus = make_bot().with_weights(plus);
+        let mut bot_minus = make_bot().with_weights(minus);
+
+        let mut score = 0.0;
+        for _ in 0..games / 2 {
+            let opening = balanced_opening(
+                options.rows,
+                options.cols,
+                OPENING_PLIES,
+                options.hash_mb,
+                &mut rng,
+            )?;
+            score += play_game(
+                &mut bot_plus,
+                &mut bot_minus,
+                options.rows,
+                options.cols,
+                &opening,
+            )?;
+            score += 1.0
+                - play_game(
+                    &mut bot_minus,
+                    &mut bot_plus,
+                    options.rows,
+                    options.cols,
+                    &opening,
+                )?;
+        }
+        // From -1 (minus won every game) to 1 (plus won every game).
+        let result = 2.0 * score / games as f64 - 1.0;
+        for i in 0..3 {
+            theta[i] = (theta[i] + a * result / (2.0 * c * delta[i])).max(0.0);
+        }
+
+        let current = to_weights(&theta);
+        info!(iteration = k + 1, result, ?current, "tuning iteration");
+        eprintln!(
+            "iteration {}/{}: {:+.2}, center {} two {} three {}",
+            k + 1,
+            options.iterations,
+            result,
+            current.center,
+            current.two,
+            current.three
+        );
+    }
+
+    let text = toml::to_string(&TunedConfig {
+        weights: to_weights(&theta),
+    })?;
+    match &options.output {
+        Some(path) => {
+            fs::write(path, text).map_err(|err| format!("{}: {}", path.display(), err))?
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn weights_are_rounded_and_never_negative() {
+        assert_eq!(
+            to_weights(&[2.5, -1.2, 4.49]),
+            Weights {
+                center: 3,
+                two: 0,
+                three: 4
+            }
+        );
+    }
+
+    #[test]
+    fn random_openings_are_undecided() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let opening = random_opening(4, 4, 7, &mut rng);
+            assert_eq!(opening.len(), 7);
+            let game = ConnectFour::from_moves(4, 4, &opening, PLAYER).unwrap();
+            assert!(!game.check_win(PLAYER) && !game.check_win(BOT));
+        }
+    }
+
+    #[test]
+    fn balanced_openings_are_close_to_a_draw() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let opening = balanced_opening(4, 5, 4, 1, &mut rng).unwrap();
+        let game = ConnectFour::from_moves(4, 5, &opening, PLAYER).unwrap();
+        let pos = Position::from_game(&game, PLAYER).unwrap();
+        assert!(Solver::new(1).solve(&pos).abs() <= BALANCE_MARGIN);
+    }
+
+    #[test]
+    fn games_score_the_first_bot() {
+        // Both sides have three in a column, and the side to move wins.
+        let mut first = BotPlayer::new(2);
+        let mut second = BotPlayer::new(2);
+        assert_eq!(
+            play_game(&mut first, &mut second, 6, 7, "414141").unwrap(),
+            1.0
+        );
+        assert_eq!(
+            play_game(&mut first, &mut second, 6, 7, "4141415").unwrap(),
+            0.0
+        );
+    }
+}